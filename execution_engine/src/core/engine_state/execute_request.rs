@@ -2,7 +2,7 @@
 use std::mem;
 
 use casper_hashing::Digest;
-use casper_types::{ProtocolVersion, PublicKey, SecretKey};
+use casper_types::{account::AccountHash, ProtocolVersion, PublicKey, SecretKey};
 
 use super::deploy_item::DeployItem;
 
@@ -19,6 +19,14 @@ pub struct ExecuteRequest {
     pub protocol_version: ProtocolVersion,
     /// The owner of the node that proposed the block containing this request.
     pub proposer: PublicKey,
+    /// Seed mixed into the addresses generated by the deploys, in addition to their hashes.
+    ///
+    /// Only meant for reproducing executions deterministically, e.g. in test networks, as the
+    /// resulting global state differs from that of an execution without the seed.
+    pub address_seed: Option<[u8; 32]>,
+    /// Account the unspent payment of the deploys is refunded to rather than the account which
+    /// paid for them, unless their payment code set a refund purse.
+    pub refund_account: Option<AccountHash>,
 }
 
 impl ExecuteRequest {
@@ -36,6 +44,8 @@ impl ExecuteRequest {
             deploys,
             protocol_version,
             proposer,
+            address_seed: None,
+            refund_account: None,
         }
     }
 
@@ -61,6 +71,8 @@ impl Default for ExecuteRequest {
             deploys: vec![],
             protocol_version: Default::default(),
             proposer,
+            address_seed: None,
+            refund_account: None,
         }
     }
 }
@@ -26,6 +26,7 @@ use std::{
     collections::{btree_map::Entry, BTreeMap, BTreeSet},
     convert::TryFrom,
     rc::Rc,
+    sync::Arc,
 };
 
 use num::Zero;
@@ -94,8 +95,9 @@ use crate::{
     shared::{additive_map::AdditiveMap, newtypes::CorrelationId, transform::Transform},
     storage::{
         global_state::{
-            lmdb::LmdbGlobalState, scratch::ScratchGlobalState, CommitProvider, StateProvider,
-            StateReader,
+            lmdb::LmdbGlobalState,
+            scratch::{ReadCache, ScratchGlobalState},
+            CommitProvider, StateProvider, StateReader,
         },
         trie::{merkle_proof::TrieMerkleProof, TrieRaw},
         trie_store::operations::DeleteResult,
@@ -121,7 +123,7 @@ pub const WASMLESS_TRANSFER_FIXED_GAS_PRICE: u64 = 1;
 /// Takes an engine's configuration and a provider of a state (aka the global state) to operate on.
 /// Methods implemented on this structure are the external API intended to be used by the users such
 /// as the node, test framework, and others.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EngineState<S> {
     config: EngineConfig,
     state: S,
@@ -156,6 +158,18 @@ impl EngineState<LmdbGlobalState> {
         }
     }
 
+    /// Provides a scratch engine state as [`EngineState::get_scratch_engine_state`] does, which
+    /// consults `read_cache` before reading values from LMDB.
+    pub fn get_scratch_engine_state_with_read_cache(
+        &self,
+        read_cache: Arc<dyn ReadCache>,
+    ) -> EngineState<ScratchGlobalState> {
+        EngineState {
+            config: self.config.clone(),
+            state: self.state.create_scratch().with_read_cache(read_cache),
+        }
+    }
+
     /// Writes state cached in an `EngineState<ScratchEngineState>` to LMDB.
     pub fn write_scratch_to_db(
         &self,
@@ -167,6 +181,63 @@ impl EngineState<LmdbGlobalState> {
             .put_stored_values(CorrelationId::new(), state_root_hash, stored_values)
             .map_err(Into::into)
     }
+
+    /// Computes the state root hash which would result from writing the state cached in an
+    /// `EngineState<ScratchEngineState>` to LMDB, without writing it.
+    ///
+    /// All values cached so far are rewritten into an in-memory trie on every call, so this is
+    /// intended for diagnostics rather than for the regular execution path.
+    pub fn compute_scratch_state_root_hash(
+        &self,
+        state_root_hash: Digest,
+        scratch_engine_state: &EngineState<ScratchGlobalState>,
+    ) -> Result<Digest, Error> {
+        let stored_values = scratch_engine_state.state.dirty_values();
+        self.state
+            .compute_root_of_stored_values(CorrelationId::new(), state_root_hash, stored_values)
+            .map_err(Into::into)
+    }
+
+    /// Computes the state root hash which would result from writing the values cached in the given
+    /// scratch engine state on top of `state_root_hash`, along with the Merkle proof for the
+    /// checksum registry in the resulting state, without writing anything to LMDB.
+    pub fn compute_scratch_checksum_registry_proof(
+        &self,
+        state_root_hash: Digest,
+        scratch_engine_state: &EngineState<ScratchGlobalState>,
+    ) -> Result<(Digest, TrieMerkleProof<Key, StoredValue>), Error> {
+        let stored_values = scratch_engine_state.state.dirty_values();
+        let (post_state_hash, maybe_proof) = self.state.compute_root_and_proof_of_stored_values(
+            CorrelationId::new(),
+            state_root_hash,
+            stored_values,
+            &Key::ChecksumRegistry,
+        )?;
+        let proof = maybe_proof.ok_or(Error::MissingChecksumRegistry)?;
+        Ok((post_state_hash, proof))
+    }
+
+    /// Computes the trie depths of `keys` which would result from writing the values cached in the
+    /// given scratch engine state on top of `state_root_hash`, without writing anything to LMDB.
+    ///
+    /// As with [`EngineState::compute_scratch_state_root_hash`], all cached values are rewritten
+    /// into an in-memory trie.
+    pub fn compute_scratch_trie_depths(
+        &self,
+        state_root_hash: Digest,
+        scratch_engine_state: &EngineState<ScratchGlobalState>,
+        keys: &[Key],
+    ) -> Result<Vec<Option<usize>>, Error> {
+        let stored_values = scratch_engine_state.state.dirty_values();
+        self.state
+            .compute_trie_depths_of_stored_values(
+                CorrelationId::new(),
+                state_root_hash,
+                stored_values,
+                keys,
+            )
+            .map_err(Into::into)
+    }
 }
 
 impl<S> EngineState<S>
@@ -556,7 +627,9 @@ where
         correlation_id: CorrelationId,
         mut exec_request: ExecuteRequest,
     ) -> Result<ExecutionResults, Error> {
-        let executor = Executor::new(self.config().clone());
+        let executor = Executor::new(self.config().clone())
+            .with_address_seed(exec_request.address_seed)
+            .with_refund_account(exec_request.refund_account);
 
         let deploys = exec_request.take_deploys();
         let mut results = ExecutionResults::with_capacity(deploys.len());
@@ -1102,7 +1175,10 @@ where
                 let account = deploy_item.address;
                 let maybe_runtime_args = RuntimeArgs::try_new(|args| {
                     args.insert(handle_payment::ARG_AMOUNT, finalize_cost_motes.value())?;
-                    args.insert(handle_payment::ARG_ACCOUNT, account)?;
+                    args.insert(
+                        handle_payment::ARG_ACCOUNT,
+                        executor.refund_account(account),
+                    )?;
                     args.insert(handle_payment::ARG_TARGET, rewards_target_purse)?;
                     Ok(())
                 });
@@ -1677,7 +1753,10 @@ where
 
                 let maybe_runtime_args = RuntimeArgs::try_new(|args| {
                     args.insert(handle_payment::ARG_AMOUNT, finalize_cost_motes.value())?;
-                    args.insert(handle_payment::ARG_ACCOUNT, account.account_hash())?;
+                    args.insert(
+                        handle_payment::ARG_ACCOUNT,
+                        executor.refund_account(account.account_hash()),
+                    )?;
                     args.insert(handle_payment::ARG_TARGET, rewards_target_purse)?;
                     Ok(())
                 });
@@ -15,7 +15,10 @@ use crate::{
             executable_deploy_item::ExecutionKind, execution_result::ExecutionResult, EngineConfig,
             ExecError,
         },
-        execution::{address_generator::AddressGenerator, Error},
+        execution::{
+            address_generator::{AddressGenerator, AddressGeneratorBuilder},
+            Error,
+        },
         runtime::{Runtime, RuntimeStack},
         runtime_context::RuntimeContext,
         tracking_copy::{TrackingCopy, TrackingCopyExt},
@@ -35,12 +38,48 @@ fn try_get_amount(runtime_args: &RuntimeArgs) -> Result<U512, ExecError> {
 /// Executor object deals with execution of WASM modules.
 pub struct Executor {
     config: EngineConfig,
+    address_seed: Option<[u8; 32]>,
+    refund_account: Option<AccountHash>,
 }
 
 impl Executor {
     /// Creates new executor object.
     pub fn new(config: EngineConfig) -> Self {
-        Executor { config }
+        Executor {
+            config,
+            address_seed: None,
+            refund_account: None,
+        }
+    }
+
+    /// Sets the seed mixed into the addresses generated by executed deploys, in addition to the
+    /// deploy hash and phase.
+    pub(crate) fn with_address_seed(mut self, address_seed: Option<[u8; 32]>) -> Self {
+        self.address_seed = address_seed;
+        self
+    }
+
+    /// Sets the account unspent payment is refunded to instead of the account paying for a deploy.
+    pub(crate) fn with_refund_account(mut self, refund_account: Option<AccountHash>) -> Self {
+        self.refund_account = refund_account;
+        self
+    }
+
+    /// Returns the account unspent payment of a deploy paid for by `account` is refunded to.
+    pub(crate) fn refund_account(&self, account: AccountHash) -> AccountHash {
+        self.refund_account.unwrap_or(account)
+    }
+
+    /// Creates the address generator for the given deploy and phase.
+    fn address_generator(&self, deploy_hash: DeployHash, phase: Phase) -> AddressGenerator {
+        match &self.address_seed {
+            Some(address_seed) => AddressGeneratorBuilder::new()
+                .seed_with(address_seed)
+                .seed_with(deploy_hash.as_bytes())
+                .seed_with(&[phase as u8])
+                .build(),
+            None => AddressGenerator::new(deploy_hash.as_bytes(), phase),
+        }
     }
 
     /// Executes a WASM module.
@@ -77,7 +116,7 @@ impl Executor {
         };
 
         let address_generator = {
-            let generator = AddressGenerator::new(deploy_hash.as_bytes(), phase);
+            let generator = self.address_generator(deploy_hash, phase);
             Rc::new(RefCell::new(generator))
         };
 
@@ -163,7 +202,7 @@ impl Executor {
         };
 
         let address_generator = {
-            let generator = AddressGenerator::new(deploy_hash.as_bytes(), phase);
+            let generator = self.address_generator(deploy_hash, phase);
             Rc::new(RefCell::new(generator))
         };
 
@@ -232,7 +271,7 @@ impl Executor {
         T: FromBytes + CLTyped,
     {
         let address_generator = {
-            let generator = AddressGenerator::new(deploy_hash.as_bytes(), phase);
+            let generator = self.address_generator(deploy_hash, phase);
             Rc::new(RefCell::new(generator))
         };
 
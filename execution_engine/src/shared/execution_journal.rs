@@ -5,6 +5,7 @@ use std::{iter::IntoIterator, vec::IntoIter};
 use datasize::DataSize;
 
 use casper_types::{
+    bytesrepr::{self, FromBytes, ToBytes},
     ExecutionEffect as JsonExecutionEffect, Key, TransformEntry as JsonTransformEntry,
 };
 
@@ -71,6 +72,27 @@ impl From<ExecutionJournal> for JsonExecutionEffect {
     }
 }
 
+impl ToBytes for ExecutionJournal {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        self.0.to_bytes()
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.0.serialized_length()
+    }
+
+    fn write_bytes(&self, writer: &mut Vec<u8>) -> Result<(), bytesrepr::Error> {
+        self.0.write_bytes(writer)
+    }
+}
+
+impl FromBytes for ExecutionJournal {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (inner, remainder) = Vec::<(Key, Transform)>::from_bytes(bytes)?;
+        Ok((ExecutionJournal(inner), remainder))
+    }
+}
+
 impl IntoIterator for ExecutionJournal {
     type Item = (Key, Transform);
     type IntoIter = IntoIter<Self::Item>;
@@ -11,7 +11,7 @@ use datasize::DataSize;
 use num::traits::{AsPrimitive, WrappingAdd};
 
 use casper_types::{
-    bytesrepr::{self, FromBytes, ToBytes},
+    bytesrepr::{self, FromBytes, ToBytes, U8_SERIALIZED_LENGTH},
     contracts::NamedKeys,
     CLType, CLTyped, CLValue, CLValueError, StoredValue, StoredValueTypeMismatch, U128, U256, U512,
 };
@@ -339,6 +339,113 @@ impl Default for Transform {
     }
 }
 
+const TRANSFORM_IDENTITY_TAG: u8 = 0;
+const TRANSFORM_WRITE_TAG: u8 = 1;
+const TRANSFORM_ADD_INT32_TAG: u8 = 2;
+const TRANSFORM_ADD_UINT64_TAG: u8 = 3;
+const TRANSFORM_ADD_UINT128_TAG: u8 = 4;
+const TRANSFORM_ADD_UINT256_TAG: u8 = 5;
+const TRANSFORM_ADD_UINT512_TAG: u8 = 6;
+const TRANSFORM_ADD_KEYS_TAG: u8 = 7;
+
+/// A [`Transform::Failure`] is never committed to global state, and has no serialized
+/// representation.
+impl ToBytes for Transform {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut buffer = bytesrepr::allocate_buffer(self)?;
+        self.write_bytes(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn serialized_length(&self) -> usize {
+        U8_SERIALIZED_LENGTH
+            + match self {
+                Transform::Identity | Transform::Failure(_) => 0,
+                Transform::Write(stored_value) => stored_value.serialized_length(),
+                Transform::AddInt32(value) => value.serialized_length(),
+                Transform::AddUInt64(value) => value.serialized_length(),
+                Transform::AddUInt128(value) => value.serialized_length(),
+                Transform::AddUInt256(value) => value.serialized_length(),
+                Transform::AddUInt512(value) => value.serialized_length(),
+                Transform::AddKeys(named_keys) => named_keys.serialized_length(),
+            }
+    }
+
+    fn write_bytes(&self, writer: &mut Vec<u8>) -> Result<(), bytesrepr::Error> {
+        match self {
+            Transform::Identity => writer.push(TRANSFORM_IDENTITY_TAG),
+            Transform::Write(stored_value) => {
+                writer.push(TRANSFORM_WRITE_TAG);
+                stored_value.write_bytes(writer)?;
+            }
+            Transform::AddInt32(value) => {
+                writer.push(TRANSFORM_ADD_INT32_TAG);
+                value.write_bytes(writer)?;
+            }
+            Transform::AddUInt64(value) => {
+                writer.push(TRANSFORM_ADD_UINT64_TAG);
+                value.write_bytes(writer)?;
+            }
+            Transform::AddUInt128(value) => {
+                writer.push(TRANSFORM_ADD_UINT128_TAG);
+                value.write_bytes(writer)?;
+            }
+            Transform::AddUInt256(value) => {
+                writer.push(TRANSFORM_ADD_UINT256_TAG);
+                value.write_bytes(writer)?;
+            }
+            Transform::AddUInt512(value) => {
+                writer.push(TRANSFORM_ADD_UINT512_TAG);
+                value.write_bytes(writer)?;
+            }
+            Transform::AddKeys(named_keys) => {
+                writer.push(TRANSFORM_ADD_KEYS_TAG);
+                named_keys.write_bytes(writer)?;
+            }
+            Transform::Failure(_) => return Err(bytesrepr::Error::NotRepresentable),
+        }
+        Ok(())
+    }
+}
+
+impl FromBytes for Transform {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (tag, remainder) = u8::from_bytes(bytes)?;
+        match tag {
+            TRANSFORM_IDENTITY_TAG => Ok((Transform::Identity, remainder)),
+            TRANSFORM_WRITE_TAG => {
+                let (stored_value, remainder) = StoredValue::from_bytes(remainder)?;
+                Ok((Transform::Write(stored_value), remainder))
+            }
+            TRANSFORM_ADD_INT32_TAG => {
+                let (value, remainder) = i32::from_bytes(remainder)?;
+                Ok((Transform::AddInt32(value), remainder))
+            }
+            TRANSFORM_ADD_UINT64_TAG => {
+                let (value, remainder) = u64::from_bytes(remainder)?;
+                Ok((Transform::AddUInt64(value), remainder))
+            }
+            TRANSFORM_ADD_UINT128_TAG => {
+                let (value, remainder) = U128::from_bytes(remainder)?;
+                Ok((Transform::AddUInt128(value), remainder))
+            }
+            TRANSFORM_ADD_UINT256_TAG => {
+                let (value, remainder) = U256::from_bytes(remainder)?;
+                Ok((Transform::AddUInt256(value), remainder))
+            }
+            TRANSFORM_ADD_UINT512_TAG => {
+                let (value, remainder) = U512::from_bytes(remainder)?;
+                Ok((Transform::AddUInt512(value), remainder))
+            }
+            TRANSFORM_ADD_KEYS_TAG => {
+                let (named_keys, remainder) = NamedKeys::from_bytes(remainder)?;
+                Ok((Transform::AddKeys(named_keys), remainder))
+            }
+            _ => Err(bytesrepr::Error::Formatting),
+        }
+    }
+}
+
 impl From<&Transform> for casper_types::Transform {
     fn from(transform: &Transform) -> Self {
         match transform {
@@ -426,6 +533,7 @@ pub mod gens {
 #[cfg(test)]
 mod tests {
     use num::{Bounded, Num};
+    use proptest::proptest;
 
     use casper_types::{
         account::{Account, AccountHash, ActionThresholds, AssociatedKeys},
@@ -868,4 +976,17 @@ mod tests {
         assert_eq!(ZERO_U512, add(MAX_U512, ONE_U512));
         assert_eq!(MAX_U512 - 1, add(MAX_U512, MAX_U512));
     }
+
+    proptest! {
+        #[test]
+        fn should_serialize_transform(transform in gens::transform_arb()) {
+            bytesrepr::test_serialization_roundtrip(&transform);
+        }
+    }
+
+    #[test]
+    fn should_not_serialize_failure() {
+        let failure = Transform::Failure(Error::Serialization(bytesrepr::Error::Formatting));
+        assert_eq!(failure.to_bytes(), Err(bytesrepr::Error::NotRepresentable));
+    }
 }
@@ -106,6 +106,134 @@ impl LmdbGlobalState {
         Ok(new_state_root)
     }
 
+    /// Computes the state root hash which would result from writing the stored values on top of
+    /// `prestate_hash`, without writing anything to LMDB.
+    pub fn compute_root_of_stored_values(
+        &self,
+        correlation_id: CorrelationId,
+        prestate_hash: Digest,
+        stored_values: HashMap<Key, StoredValue>,
+    ) -> Result<Digest, error::Error> {
+        let scratch_trie = self.get_scratch_store();
+        put_stored_values::<_, _, error::Error>(
+            &scratch_trie,
+            &scratch_trie,
+            correlation_id,
+            prestate_hash,
+            stored_values,
+        )
+    }
+
+    /// Computes the state root hash which would result from writing the stored values on top of
+    /// `prestate_hash`, along with a Merkle proof of the value under `key` in the resulting state,
+    /// without writing anything to LMDB.
+    pub fn compute_root_and_proof_of_stored_values(
+        &self,
+        correlation_id: CorrelationId,
+        prestate_hash: Digest,
+        stored_values: HashMap<Key, StoredValue>,
+        key: &Key,
+    ) -> Result<(Digest, Option<TrieMerkleProof<Key, StoredValue>>), error::Error> {
+        let scratch_trie = self.get_scratch_store();
+        let state_root_hash = put_stored_values::<_, _, error::Error>(
+            &scratch_trie,
+            &scratch_trie,
+            correlation_id,
+            prestate_hash,
+            stored_values,
+        )?;
+        let txn = scratch_trie.create_read_txn()?;
+        let maybe_proof = match read_with_proof::<
+            Key,
+            StoredValue,
+            ScratchTrieStore,
+            ScratchTrieStore,
+            error::Error,
+        >(
+            correlation_id, &txn, &scratch_trie, &state_root_hash, key
+        )? {
+            ReadResult::Found(proof) => Some(proof),
+            ReadResult::NotFound => None,
+            ReadResult::RootNotFound => panic!("scratch trie store has invalid root"),
+        };
+        txn.commit()?;
+        Ok((state_root_hash, maybe_proof))
+    }
+
+    /// Computes the depths which the leaves holding the values under `keys` would have in the trie
+    /// resulting from writing the stored values on top of `prestate_hash`, without writing
+    /// anything to LMDB.
+    ///
+    /// The depth of a key is `None` if there would be no value under it, see
+    /// [`LmdbGlobalState::trie_depth`].
+    pub fn compute_trie_depths_of_stored_values(
+        &self,
+        correlation_id: CorrelationId,
+        prestate_hash: Digest,
+        stored_values: HashMap<Key, StoredValue>,
+        keys: &[Key],
+    ) -> Result<Vec<Option<usize>>, error::Error> {
+        let scratch_trie = self.get_scratch_store();
+        let state_root_hash = put_stored_values::<_, _, error::Error>(
+            &scratch_trie,
+            &scratch_trie,
+            correlation_id,
+            prestate_hash,
+            stored_values,
+        )?;
+        let txn = scratch_trie.create_read_txn()?;
+        let mut depths = Vec::with_capacity(keys.len());
+        for key in keys {
+            let maybe_depth = match read_with_proof::<
+                Key,
+                StoredValue,
+                ScratchTrieStore,
+                ScratchTrieStore,
+                error::Error,
+            >(
+                correlation_id, &txn, &scratch_trie, &state_root_hash, key
+            )? {
+                ReadResult::Found(proof) => Some(proof.proof_steps().len()),
+                ReadResult::NotFound => None,
+                ReadResult::RootNotFound => panic!("scratch trie store has invalid root"),
+            };
+            depths.push(maybe_depth);
+        }
+        txn.commit()?;
+        Ok(depths)
+    }
+
+    /// Returns the depth of the leaf holding the value under `key` in the trie at `state_hash`,
+    /// i.e. the number of nodes and extensions on the path to it from the root.
+    ///
+    /// Returns `None` if there is no value under `key` or the state root is not present.
+    pub fn trie_depth(
+        &self,
+        correlation_id: CorrelationId,
+        state_hash: Digest,
+        key: &Key,
+    ) -> Result<Option<usize>, error::Error> {
+        let txn = self.environment.create_read_txn()?;
+        let maybe_depth = match read_with_proof::<
+            Key,
+            StoredValue,
+            lmdb::RoTransaction,
+            LmdbTrieStore,
+            error::Error,
+        >(
+            correlation_id,
+            &txn,
+            self.trie_store.deref(),
+            &state_hash,
+            key,
+        )? {
+            ReadResult::Found(proof) => Some(proof.proof_steps().len()),
+            ReadResult::NotFound | ReadResult::RootNotFound => None,
+        };
+        txn.commit()?;
+        Ok(maybe_depth)
+    }
+
     /// Gets a scratch trie store.
     fn get_scratch_store(&self) -> ScratchTrieStore {
         ScratchTrieStore::new(Arc::clone(&self.trie_store), Arc::clone(&self.environment))
@@ -329,6 +457,8 @@ impl StateProvider for LmdbGlobalState {
 
 #[cfg(test)]
 mod tests {
+    use std::iter;
+
     use lmdb::DatabaseFlags;
     use tempfile::tempdir;
 
@@ -431,6 +561,78 @@ mod tests {
         }
     }
 
+    #[test]
+    fn trie_depth_is_none_for_missing_keys_and_roots() {
+        let correlation_id = CorrelationId::new();
+        let (state, root_hash) = create_test_state(create_test_pairs);
+        for TestPair { key, .. } in &create_test_pairs() {
+            assert!(state
+                .trie_depth(correlation_id, root_hash, key)
+                .unwrap()
+                .is_some());
+        }
+
+        let missing_key = Key::Account(AccountHash::new([3_u8; 32]));
+        assert_eq!(
+            state
+                .trie_depth(correlation_id, root_hash, &missing_key)
+                .unwrap(),
+            None
+        );
+        let fake_hash: Digest = Digest::hash([1u8; 32]);
+        let key = create_test_pairs()[0].key;
+        assert_eq!(
+            state.trie_depth(correlation_id, fake_hash, &key).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn computed_trie_depths_match_written_trie_depths() {
+        let correlation_id = CorrelationId::new();
+        let (state, root_hash) = create_test_state(create_test_pairs);
+        let stored_values = create_test_pairs_updated()
+            .iter()
+            .cloned()
+            .map(|TestPair { key, value }| (key, value))
+            .collect::<HashMap<_, _>>();
+        let missing_key = Key::Account(AccountHash::new([4_u8; 32]));
+        let keys = create_test_pairs_updated()
+            .iter()
+            .map(|TestPair { key, .. }| *key)
+            .chain(iter::once(missing_key))
+            .collect::<Vec<_>>();
+
+        let computed_depths = state
+            .compute_trie_depths_of_stored_values(
+                correlation_id,
+                root_hash,
+                stored_values.clone(),
+                &keys,
+            )
+            .unwrap();
+        assert_eq!(computed_depths.last(), Some(&None));
+
+        // Nothing was written by computing the depths.
+        let updated_root_hash = state
+            .compute_root_of_stored_values(correlation_id, root_hash, stored_values.clone())
+            .unwrap();
+        assert!(state.checkout(updated_root_hash).unwrap().is_none());
+
+        let updated_root_hash = state
+            .put_stored_values(correlation_id, root_hash, stored_values)
+            .unwrap();
+        let written_depths = keys
+            .iter()
+            .map(|key| {
+                state
+                    .trie_depth(correlation_id, updated_root_hash, key)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(computed_depths, written_depths);
+    }
+
     #[test]
     fn checkout_fails_if_unknown_hash_is_given() {
         let (state, _) = create_test_state(create_test_pairs);
@@ -32,7 +32,7 @@ use crate::{
     },
 };
 
-use super::trie_store::operations::DeleteResult;
+pub use super::trie_store::operations::DeleteResult;
 
 /// A trait expressing the reading of state. This trait is used to abstract the underlying store.
 pub trait StateReader<K, V> {
@@ -78,6 +78,9 @@ pub enum CommitError {
     /// Trie not found while attempting to validate cache write.
     #[error("Trie not found in cache {0}")]
     TrieNotFoundInCache(Digest),
+    /// Attempted to write to a read-only global state.
+    #[error("Global state is read-only")]
+    ReadOnly,
 }
 
 /// Provides `commit` method.
@@ -30,6 +30,20 @@ use crate::{
 
 type SharedCache = Arc<RwLock<Cache>>;
 
+/// A cache of values read from global state, consulted by a [`ScratchGlobalState`] before reading
+/// a value from the trie store, e.g. to share hot values across the scratch states of several
+/// blocks.
+///
+/// As values are cached along with the state root hash they were read at, a cache must only return
+/// a value for the state root hash it was read at, or for one at which it is known to be unchanged.
+pub trait ReadCache: Send + Sync {
+    /// Returns the cached value of `key` at `state_root_hash`, if any.
+    fn get(&self, state_root_hash: Digest, key: &Key) -> Option<StoredValue>;
+
+    /// Caches the value of `key` read from the trie store at `state_root_hash`.
+    fn insert(&self, state_root_hash: Digest, key: Key, value: StoredValue);
+}
+
 struct Cache {
     cached_values: HashMap<Key, (bool, StoredValue)>,
 }
@@ -53,6 +67,20 @@ impl Cache {
         self.cached_values.get(key).map(|(_dirty, value)| value)
     }
 
+    /// Returns a copy of the written values, leaving the cache untouched.
+    fn dirty_writes(&self) -> HashMap<Key, StoredValue> {
+        self.cached_values
+            .iter()
+            .filter_map(|(key, (dirty, value))| {
+                if *dirty {
+                    Some((*key, value.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     /// Consumes self and returns only written values as values that were only read must be filtered
     /// out to prevent unnecessary writes.
     fn into_dirty_writes(self) -> HashMap<Key, StoredValue> {
@@ -64,9 +92,14 @@ impl Cache {
 }
 
 /// Global state implemented against LMDB as a backing data store.
+///
+/// Clones share the cached stored values.
+#[derive(Clone)]
 pub struct ScratchGlobalState {
     /// Underlying, cached stored values.
     cache: SharedCache,
+    /// Cache of values read from the trie store, consulted before reading from it.
+    read_cache: Option<Arc<dyn ReadCache>>,
     /// Environment for LMDB.
     pub(crate) environment: Arc<LmdbEnvironment>,
     /// Trie store held within LMDB.
@@ -79,6 +112,7 @@ pub struct ScratchGlobalState {
 /// Represents a "view" of global state at a particular root hash.
 pub struct ScratchGlobalStateView {
     cache: SharedCache,
+    read_cache: Option<Arc<dyn ReadCache>>,
     /// Environment for LMDB.
     pub(crate) environment: Arc<LmdbEnvironment>,
     /// Trie store held within LMDB.
@@ -97,17 +131,42 @@ impl ScratchGlobalState {
     ) -> Self {
         ScratchGlobalState {
             cache: Arc::new(RwLock::new(Cache::new())),
+            read_cache: None,
             environment,
             trie_store,
             empty_root_hash,
         }
     }
 
+    /// Consults `read_cache` before reading values from the trie store, and caches the values
+    /// read from it there.
+    pub fn with_read_cache(mut self, read_cache: Arc<dyn ReadCache>) -> Self {
+        self.read_cache = Some(read_cache);
+        self
+    }
+
     /// Consume self and return inner cache.
     pub fn into_inner(self) -> HashMap<Key, StoredValue> {
         let cache = mem::replace(&mut *self.cache.write().unwrap(), Cache::new());
         cache.into_dirty_writes()
     }
+
+    /// Returns a copy of the values written so far, without consuming self.
+    pub fn dirty_values(&self) -> HashMap<Key, StoredValue> {
+        self.cache.read().unwrap().dirty_writes()
+    }
+
+    /// Returns the number of values written so far, i.e. the number of values `into_inner` would
+    /// return.
+    pub fn dirty_value_count(&self) -> usize {
+        self.cache
+            .read()
+            .unwrap()
+            .cached_values
+            .values()
+            .filter(|(dirty, _value)| *dirty)
+            .count()
+    }
 }
 
 impl StateReader<Key, StoredValue> for ScratchGlobalStateView {
@@ -121,6 +180,14 @@ impl StateReader<Key, StoredValue> for ScratchGlobalStateView {
         if let Some(value) = self.cache.read().unwrap().get(key) {
             return Ok(Some(value.clone()));
         }
+        if let Some(value) = self
+            .read_cache
+            .as_ref()
+            .and_then(|read_cache| read_cache.get(self.root_hash, key))
+        {
+            self.cache.write().unwrap().insert_read(*key, value.clone());
+            return Ok(Some(value));
+        }
         let txn = self.environment.create_read_txn()?;
         let ret = match read::<Key, StoredValue, lmdb::RoTransaction, LmdbTrieStore, Self::Error>(
             correlation_id,
@@ -131,6 +198,9 @@ impl StateReader<Key, StoredValue> for ScratchGlobalStateView {
         )? {
             ReadResult::Found(value) => {
                 self.cache.write().unwrap().insert_read(*key, value.clone());
+                if let Some(read_cache) = self.read_cache.as_ref() {
+                    read_cache.insert(self.root_hash, *key, value.clone());
+                }
                 Some(value)
             }
             ReadResult::NotFound => None,
@@ -272,6 +342,7 @@ impl StateProvider for ScratchGlobalState {
         let maybe_root: Option<Trie<Key, StoredValue>> = self.trie_store.get(&txn, &state_hash)?;
         let maybe_state = maybe_root.map(|_| ScratchGlobalStateView {
             cache: Arc::clone(&self.cache),
+            read_cache: self.read_cache.clone(),
             environment: Arc::clone(&self.environment),
             trie_store: Arc::clone(&self.trie_store),
             root_hash: state_hash,
@@ -106,7 +106,10 @@
 use std::{
     borrow::Cow,
     collections::{hash_map::Entry, HashMap},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
 
 use casper_types::{
@@ -129,9 +132,20 @@ use crate::storage::{
 /// An LMDB-backed trie store.
 ///
 /// Wraps [`lmdb::Database`].
+///
+/// Counts the tries read from and written to the database.  The counts are shared by all clones
+/// of the store.
 #[derive(Debug, Clone)]
 pub struct LmdbTrieStore {
     db: Database,
+    access_counts: Arc<AccessCounts>,
+}
+
+/// The number of tries read from and written to an [`LmdbTrieStore`].
+#[derive(Debug, Default)]
+struct AccessCounts {
+    reads: AtomicU64,
+    writes: AtomicU64,
 }
 
 impl LmdbTrieStore {
@@ -143,14 +157,20 @@ impl LmdbTrieStore {
     ) -> Result<Self, error::Error> {
         let name = Self::name(maybe_name);
         let db = env.env().create_db(Some(&name), flags)?;
-        Ok(LmdbTrieStore { db })
+        Ok(LmdbTrieStore {
+            db,
+            access_counts: Default::default(),
+        })
     }
 
     /// Constructor for `LmdbTrieStore` which opens an existing lmdb store file.
     pub fn open(env: &LmdbEnvironment, maybe_name: Option<&str>) -> Result<Self, error::Error> {
         let name = Self::name(maybe_name);
         let db = env.env().open_db(Some(&name))?;
-        Ok(LmdbTrieStore { db })
+        Ok(LmdbTrieStore {
+            db,
+            access_counts: Default::default(),
+        })
     }
 
     fn name(maybe_name: Option<&str>) -> String {
@@ -163,6 +183,26 @@ impl LmdbTrieStore {
     pub fn get_db(&self) -> Database {
         self.db
     }
+
+    /// Returns the number of tries read from the database since the store was created, including
+    /// the reads of tries not present in it.
+    pub fn read_count(&self) -> u64 {
+        self.access_counts.reads.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of tries written to the database since the store was created, including
+    /// the writes in transactions which were not committed.
+    pub fn write_count(&self) -> u64 {
+        self.access_counts.writes.load(Ordering::Relaxed)
+    }
+
+    fn record_read(&self) {
+        self.access_counts.reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_write(&self) {
+        self.access_counts.writes.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 impl<K, V> Store<Digest, Trie<K, V>> for LmdbTrieStore {
@@ -173,6 +213,31 @@ impl<K, V> Store<Digest, Trie<K, V>> for LmdbTrieStore {
     fn handle(&self) -> Self::Handle {
         self.db
     }
+
+    fn get_raw<T>(&self, txn: &T, key: &Digest) -> Result<Option<Bytes>, Self::Error>
+    where
+        T: Readable<Handle = Self::Handle>,
+        Digest: AsRef<[u8]>,
+        Self::Error: From<T::Error>,
+    {
+        self.record_read();
+        Ok(txn.read(self.handle(), key.as_ref())?)
+    }
+
+    fn put_raw<T>(
+        &self,
+        txn: &mut T,
+        key: &Digest,
+        value_bytes: Cow<'_, [u8]>,
+    ) -> Result<(), Self::Error>
+    where
+        T: Writable<Handle = Self::Handle>,
+        Digest: AsRef<[u8]>,
+        Self::Error: From<T::Error>,
+    {
+        self.record_write();
+        Ok(txn.write(self.handle(), key.as_ref(), &value_bytes)?)
+    }
 }
 
 impl<K, V> TrieStore<K, V> for LmdbTrieStore {}
@@ -275,6 +340,7 @@ impl Store<Digest, Trie<Key, StoredValue>> for ScratchTrieStore {
             Some((_, trie_bytes)) => Ok(Some(trie_bytes.clone())),
             None => {
                 let handle = self.handle();
+                self.store.record_read();
                 match txn.read(handle, key.as_ref())? {
                     Some(trie_bytes) => {
                         match store.entry(*key) {
@@ -438,10 +438,14 @@ where
     }
 }
 
+/// The result of deleting a key from global state.
 #[derive(Debug, PartialEq, Eq)]
 pub enum DeleteResult {
+    /// The key was deleted, resulting in the given state root hash.
     Deleted(Digest),
+    /// The key does not exist under the given state root hash.
     DoesNotExist,
+    /// The given state root hash does not exist.
     RootNotFound,
 }
 
@@ -606,3 +606,40 @@ fn lmdb_uncommitted_transactions_across_sub_databases_do_not_persist() {
         .is_ok()
     )
 }
+
+#[test]
+fn lmdb_store_counts_reads_and_writes() {
+    let tmp_dir = tempdir().unwrap();
+    let env = LmdbEnvironment::new(
+        tmp_dir.path(),
+        DEFAULT_TEST_MAX_DB_SIZE,
+        DEFAULT_TEST_MAX_READERS,
+        true,
+    )
+    .unwrap();
+    let store = LmdbTrieStore::new(&env, None, DatabaseFlags::empty()).unwrap();
+    let data = super::create_data();
+
+    {
+        let mut txn = env.create_read_write_txn().unwrap();
+        store
+            .put_many(&mut txn, data.iter().map(Into::into))
+            .unwrap();
+        txn.commit().unwrap();
+    }
+    assert_eq!(store.write_count(), data.len() as u64);
+    assert_eq!(store.read_count(), 0);
+
+    {
+        let txn = env.create_read_txn().unwrap();
+        let keys = data.iter().map(|TestData(key, _)| key);
+        let _: Vec<Option<Trie<Bytes, Bytes>>> = store.get_many(&txn, keys).unwrap();
+        txn.commit().unwrap();
+    }
+    // Clones share the counts of the store they were cloned from.
+    let clone = store.clone();
+    assert_eq!(clone.read_count(), data.len() as u64);
+    assert_eq!(clone.write_count(), data.len() as u64);
+
+    tmp_dir.close().unwrap();
+}
@@ -2,6 +2,8 @@
 
 mod config;
 mod error;
+mod execution_results_proof;
+mod footprint;
 mod metrics;
 mod operations;
 #[cfg(test)]
@@ -13,7 +15,9 @@ use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     convert::TryInto,
     fmt::{self, Debug, Display, Formatter},
-    path::Path,
+    fs, io,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
     time::Instant,
 };
@@ -25,7 +29,7 @@ use once_cell::sync::Lazy;
 use prometheus::Registry;
 use serde::Serialize;
 use thiserror::Error;
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
 use casper_execution_engine::{
     core::engine_state::{
@@ -33,7 +37,7 @@ use casper_execution_engine::{
         engine_config::{FeeHandling, RefundHandling},
         genesis::GenesisError,
         ChainspecRegistry, DeployItem, EngineConfigBuilder, EngineState, GenesisSuccess,
-        SystemContractRegistry, UpgradeConfig, UpgradeSuccess,
+        StepSuccess, SystemContractRegistry, UpgradeConfig, UpgradeSuccess,
     },
     shared::{newtypes::CorrelationId, system_config::SystemConfig, wasm_config::WasmConfig},
     storage::{
@@ -42,7 +46,7 @@ use casper_execution_engine::{
     },
 };
 use casper_hashing::Digest;
-use casper_types::{bytesrepr::Bytes, EraId, ProtocolVersion, PublicKey, Timestamp};
+use casper_types::{bytesrepr::Bytes, EraId, Gas, ProtocolVersion, PublicKey, Timestamp};
 
 use crate::{
     components::{fetcher::FetchResponse, Component, ComponentState},
@@ -64,12 +68,37 @@ use crate::{
     NodeRng,
 };
 pub(crate) use config::Config;
-pub(crate) use error::{BlockExecutionError, ConfigError};
+pub(crate) use error::{
+    ApprovalsError, BlockExecutionError, ConfigError, MaintenanceStepError,
+    SpeculativeExecutionError, StepPhase,
+};
+pub use error::{ExecutionResultProofError, SelfCheckError};
+pub use execution_results_proof::ExecutionResultInclusionProof;
 use metrics::Metrics;
-pub use operations::execute_finalized_block;
 use operations::execute_only;
+pub use operations::{
+    apply_recorded_journals, commit_maintenance_step, compute_state_root_for_block,
+    dry_run_finalized_block, engine_self_check, execute_finalized_block,
+    execute_finalized_block_async, execute_finalized_block_checked,
+    execute_finalized_block_from_iter, execute_finalized_block_streaming, execute_only_at,
+    execute_only_batch, execute_only_with_effects, execute_only_with_root, pre_execution_checks,
+    simulate_finalized_block, verify_finalized_block, ReadOnlyExecutor, ReadOnlyState,
+    SpeculativeExecutor,
+};
+pub(crate) use operations::{
+    verify_approvals_hashes, SKIP_EMPTY_BLOCK_CHECKSUM_REGISTRY_PROTOCOL_VERSION,
+};
+pub use types::{
+    execution_results_checksum_scheme, summarize_step_journal, BatchedFlushes, BlockCheckpointing,
+    BlockChecksums, BlockExecutionOptions, CapturedJournal, CommitMode,
+    DefaultExecutionResultsChecksum, DeployOrdering, EraValidatorsCache, ExecutionCheckpoint,
+    ExecutionResultCache, ExecutionResultsAccumulator, ExecutionResultsChecksum, GasPriceOrdering,
+    MinimumReward, ReadThroughCache, RefundDestination, RewardStrategy, StateRootCheckpoint,
+    StepJournalSummary, StepOutcomeSummary, TreasuryRefunds,
+};
 pub(crate) use types::{
-    BlockAndExecutionResults, EraValidatorsRequest, StepEffectAndUpcomingEraValidators,
+    BlockAndExecutionResults, DeployExecutionInfo, EraValidatorsRequest,
+    StepEffectAndUpcomingEraValidators,
 };
 
 const COMPONENT_NAME: &str = "contract_runtime";
@@ -93,6 +122,10 @@ pub(crate) enum ContractRuntimeError {
 /// TODO: Fine tune this constant to the machine executing the node.
 const MAX_PARALLEL_INTENSIVE_TASKS: usize = 4;
 
+/// The name of the file in the storage directory holding the latest checkpoint of the execution of
+/// a block.
+const EXECUTION_CHECKPOINT_FILE_NAME: &str = "execution_checkpoint.json";
+
 pub(crate) const APPROVALS_CHECKSUM_NAME: &str = "approvals_checksum";
 pub(crate) const EXECUTION_RESULTS_CHECKSUM_NAME: &str = "execution_results_checksum";
 
@@ -117,6 +150,62 @@ where
         .expect("task panicked")
 }
 
+/// Reads the checkpoint of an interrupted execution of the block at `block_height` on top of
+/// `pre_state_root_hash`, if one was recorded.
+fn read_execution_checkpoint(
+    path: &Path,
+    block_height: u64,
+    pre_state_root_hash: Digest,
+) -> Option<ExecutionCheckpoint> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return None,
+        Err(error) => {
+            warn!(%error, path = %path.display(), "failed to read execution checkpoint");
+            return None;
+        }
+    };
+    match serde_json::from_slice::<ExecutionCheckpoint>(&bytes) {
+        Ok(checkpoint)
+            if checkpoint.block_height == block_height
+                && checkpoint.pre_state_root_hash == pre_state_root_hash =>
+        {
+            Some(checkpoint)
+        }
+        Ok(_) => None,
+        Err(error) => {
+            warn!(%error, path = %path.display(), "failed to parse execution checkpoint");
+            None
+        }
+    }
+}
+
+/// Persists the checkpoint of the execution of a block, replacing any previous one.
+///
+/// Failing to persist a checkpoint only means that an interrupted execution restarts from an
+/// earlier point, so errors are logged rather than returned.
+fn write_execution_checkpoint(path: &Path, checkpoint: &ExecutionCheckpoint) {
+    let temp_path = path.with_extension("tmp");
+    let result = serde_json::to_vec(checkpoint)
+        .map_err(io::Error::from)
+        .and_then(|bytes| fs::write(&temp_path, bytes))
+        .and_then(|()| fs::rename(&temp_path, path));
+    if let Err(error) = result {
+        warn!(%error, path = %path.display(), "failed to write execution checkpoint");
+    }
+}
+
+/// Removes the checkpoint of the execution of a block once the block has been executed.
+fn remove_execution_checkpoint(path: &Path) {
+    match fs::remove_file(path) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+        Err(error) => {
+            warn!(%error, path = %path.display(), "failed to remove execution checkpoint");
+        }
+    }
+}
+
 #[derive(DataSize, Debug, Clone, Serialize)]
 /// Wrapper for speculative execution prestate.
 pub struct SpeculativeExecutionState {
@@ -126,6 +215,14 @@ pub struct SpeculativeExecutionState {
     pub block_time: Timestamp,
     /// Protocol version used when creating the original block.
     pub protocol_version: ProtocolVersion,
+    /// Proposer of the block the deploy is executed in, to whom its fees are paid. Defaults to the
+    /// system if not given.
+    pub proposer: Option<PublicKey>,
+    /// Era of the block the deploy is executed in, if known.
+    ///
+    /// The execution engine reads the current era from global state at `state_root_hash`, so this
+    /// is only recorded with the execution for tracing.
+    pub era_id: Option<EraId>,
 }
 
 /// State to use to construct the next block in the blockchain. Includes the state root hash for the
@@ -212,6 +309,22 @@ pub(crate) struct ContractRuntime {
     system_contract_registry: Option<SystemContractRegistry>,
     activation_point: ActivationPoint,
     prune_batch_size: u64,
+    slash_equivocators: bool,
+    block_gas_limit: Option<Gas>,
+    strict_block_execution: bool,
+    max_deploys_per_block: Option<usize>,
+    max_execution_result_bytes: Option<usize>,
+    validator_floor: Option<usize>,
+    allow_maintenance_steps: bool,
+    trace_state_roots: bool,
+    parallel_deploy_execution: bool,
+    validate_proposer: bool,
+    checkpoint_interval: usize,
+    max_trie_depth: Option<usize>,
+    flush_retries: u32,
+    execution_checkpoint_path: PathBuf,
+    /// The name of the chain, which speculatively executed deploys must be intended for.
+    chain_name: String,
 }
 
 impl Debug for ContractRuntime {
@@ -494,6 +607,19 @@ impl ContractRuntime {
                         let shared_pre_state = Arc::clone(&self.execution_pre_state);
                         let activation_point = self.activation_point;
                         let prune_batch_size = self.prune_batch_size;
+                        let slash_equivocators = self.slash_equivocators;
+                        let block_gas_limit = self.block_gas_limit;
+                        let strict_block_execution = self.strict_block_execution;
+                        let max_deploys_per_block = self.max_deploys_per_block;
+                        let max_execution_result_bytes = self.max_execution_result_bytes;
+                        let validator_floor = self.validator_floor;
+                        let trace_state_roots = self.trace_state_roots;
+                        let parallel_deploy_execution = self.parallel_deploy_execution;
+                        let validate_proposer = self.validate_proposer;
+                        let checkpoint_interval = NonZeroUsize::new(self.checkpoint_interval);
+                        let max_trie_depth = self.max_trie_depth;
+                        let flush_retries = self.flush_retries;
+                        let execution_checkpoint_path = self.execution_checkpoint_path.clone();
                         effects.extend(
                             Self::execute_finalized_block_or_requeue(
                                 engine_state,
@@ -508,6 +634,19 @@ impl ContractRuntime {
                                 activation_point,
                                 key_block_height_for_activation_point,
                                 prune_batch_size,
+                                slash_equivocators,
+                                block_gas_limit,
+                                strict_block_execution,
+                                max_deploys_per_block,
+                                max_execution_result_bytes,
+                                validator_floor,
+                                trace_state_roots,
+                                parallel_deploy_execution,
+                                validate_proposer,
+                                checkpoint_interval,
+                                max_trie_depth,
+                                flush_retries,
+                                execution_checkpoint_path,
                                 meta_block_state,
                             )
                             .ignore(),
@@ -572,12 +711,18 @@ impl ContractRuntime {
                 responder,
             } => {
                 let engine_state = Arc::clone(&self.engine_state);
+                let metrics = Arc::clone(&self.metrics);
+                let chain_name = self.chain_name.clone();
                 async move {
                     let result = run_intensive_task(move || {
                         execute_only(
                             engine_state.as_ref(),
+                            Some(metrics),
                             execution_prestate,
                             DeployItem::from((*deploy).clone()),
+                            deploy.header(),
+                            &chain_name,
+                            Timestamp::now(),
                         )
                     })
                     .await;
@@ -611,6 +756,16 @@ impl ContractRuntime {
         allow_unrestricted_transfers: bool,
         refund_handling: RefundHandling,
         fee_handling: FeeHandling,
+        slash_equivocators: bool,
+        block_execution_gas_limit: Option<u64>,
+        max_execution_result_bytes: Option<u32>,
+        validator_floor: Option<u32>,
+        strict_block_execution: bool,
+        max_deploys_per_block: Option<usize>,
+        allow_maintenance_steps: bool,
+        validate_proposer: bool,
+        max_trie_depth: Option<u32>,
+        chain_name: String,
     ) -> Result<Self, ConfigError> {
         // TODO: This is bogus, get rid of this
         let execution_pre_state = Arc::new(Mutex::new(ExecutionPreState {
@@ -665,6 +820,23 @@ impl ContractRuntime {
             system_contract_registry: None,
             activation_point,
             prune_batch_size,
+            slash_equivocators,
+            block_gas_limit: block_execution_gas_limit.map(Gas::from),
+            strict_block_execution,
+            max_deploys_per_block,
+            max_execution_result_bytes: max_execution_result_bytes
+                .map(|max_execution_result_bytes| max_execution_result_bytes as usize),
+            validator_floor: validator_floor.map(|validator_floor| validator_floor as usize),
+            allow_maintenance_steps,
+            trace_state_roots: contract_runtime_config.trace_state_roots_or_default(),
+            parallel_deploy_execution: contract_runtime_config
+                .parallel_deploy_execution_or_default(),
+            validate_proposer,
+            checkpoint_interval: contract_runtime_config.checkpoint_interval_or_default(),
+            max_trie_depth: max_trie_depth.map(|max_trie_depth| max_trie_depth as usize),
+            flush_retries: contract_runtime_config.flush_retries_or_default(),
+            execution_checkpoint_path: storage_dir.join(EXECUTION_CHECKPOINT_FILE_NAME),
+            chain_name,
         })
     }
 
@@ -723,6 +895,36 @@ impl ContractRuntime {
         Ok(result)
     }
 
+    /// Commits a maintenance step on top of `state_root_hash`, evicting and slashing the given
+    /// validators outside a switch block, as described for [`commit_maintenance_step`].
+    ///
+    /// Only allowed if enabled by the chainspec.
+    pub(crate) fn commit_maintenance_step(
+        &self,
+        state_root_hash: Digest,
+        evict: Vec<PublicKey>,
+        slash: Vec<PublicKey>,
+        next_era_id: EraId,
+        era_end_timestamp: Timestamp,
+    ) -> Result<StepSuccess, MaintenanceStepError> {
+        if !self.allow_maintenance_steps {
+            return Err(MaintenanceStepError::NotAllowed);
+        }
+        debug!(%state_root_hash, ?evict, ?slash, %next_era_id, "maintenance step");
+        let result = commit_maintenance_step(
+            &self.engine_state,
+            self.protocol_version,
+            state_root_hash,
+            evict,
+            slash,
+            next_era_id,
+            era_end_timestamp,
+        )?;
+        self.engine_state.flush_environment()?;
+        debug!(post_state_hash = %result.post_state_hash, "maintenance step result");
+        Ok(result)
+    }
+
     pub(crate) fn set_initial_state(&mut self, sequential_block_state: ExecutionPreState) {
         let next_block_height = sequential_block_state.next_block_height;
         let mut execution_pre_state = self.execution_pre_state.lock().unwrap();
@@ -756,6 +958,19 @@ impl ContractRuntime {
         activation_point: ActivationPoint,
         key_block_height_for_activation_point: u64,
         prune_batch_size: u64,
+        slash_equivocators: bool,
+        block_gas_limit: Option<Gas>,
+        strict_block_execution: bool,
+        max_deploys_per_block: Option<usize>,
+        max_execution_result_bytes: Option<usize>,
+        validator_floor: Option<usize>,
+        trace_state_roots: bool,
+        parallel_deploy_execution: bool,
+        validate_proposer: bool,
+        checkpoint_interval: Option<NonZeroUsize>,
+        max_trie_depth: Option<usize>,
+        flush_retries: u32,
+        execution_checkpoint_path: PathBuf,
         mut meta_block_state: MetaBlockState,
     ) where
         REv: From<ContractRuntimeRequest>
@@ -772,9 +987,36 @@ impl ContractRuntime {
             approvals_hashes,
             execution_results,
             maybe_step_effect_and_upcoming_era_validators,
+            maybe_state_root_trace,
+            post_deploys_root,
+            final_root,
+            execution_result_index: _,
+            captured_journals: _,
+            checksums: _,
+            gas_price: _,
         } = match run_intensive_task(move || {
             debug!("ContractRuntime: execute_finalized_block");
-            execute_finalized_block(
+            let mut on_checkpoint = |checkpoint: &ExecutionCheckpoint| {
+                write_execution_checkpoint(&execution_checkpoint_path, checkpoint)
+            };
+            let on_validator_floor_breached = |era_id: EraId, validator_count: usize| {
+                warn!(
+                    %era_id,
+                    validator_count,
+                    ?validator_floor,
+                    "upcoming era has fewer validators than the floor"
+                );
+            };
+            let checkpointing = checkpoint_interval.map(|interval| BlockCheckpointing {
+                interval,
+                resume_from: read_execution_checkpoint(
+                    &execution_checkpoint_path,
+                    finalized_block.height(),
+                    current_pre_state.pre_state_root_hash,
+                ),
+                on_checkpoint: &mut on_checkpoint,
+            });
+            let result = execute_finalized_block(
                 engine_state.as_ref(),
                 Some(contract_runtime_metrics),
                 protocol_version,
@@ -784,7 +1026,27 @@ impl ContractRuntime {
                 activation_point.era_id(),
                 key_block_height_for_activation_point,
                 prune_batch_size,
-            )
+                BlockExecutionOptions {
+                    slash_equivocators,
+                    block_gas_limit,
+                    trace_state_roots,
+                    parallel_deploy_execution,
+                    validate_proposer,
+                    checkpointing,
+                    strict: strict_block_execution,
+                    max_deploys_per_block,
+                    max_trie_depth,
+                    flush_retries,
+                    max_execution_result_bytes,
+                    validator_floor,
+                    on_validator_floor_breached: Some(&on_validator_floor_breached),
+                    ..BlockExecutionOptions::default()
+                },
+            );
+            if result.is_ok() && checkpoint_interval.is_some() {
+                remove_execution_checkpoint(&execution_checkpoint_path);
+            }
+            result
         })
         .await
         {
@@ -822,8 +1084,17 @@ impl ContractRuntime {
         if let Some(StepEffectAndUpcomingEraValidators {
             step_execution_journal,
             mut upcoming_era_validators,
+            step_outcome_summary,
+            step_journal_summary,
         }) = maybe_step_effect_and_upcoming_era_validators
         {
+            debug!(
+                %post_deploys_root,
+                %final_root,
+                ?step_outcome_summary,
+                ?step_journal_summary,
+                "ContractRuntime: committed step"
+            );
             effect_builder
                 .announce_commit_step_success(current_era_id, step_execution_journal)
                 .await;
@@ -854,7 +1125,25 @@ impl ContractRuntime {
             is_switch_block = block.header().is_switch_block(),
             "executed block"
         );
+        if let Some(state_root_trace) = maybe_state_root_trace {
+            info!(
+                block_hash = %block.hash(),
+                ?state_root_trace,
+                "state roots of executed block"
+            );
+        }
 
+        let execution_results: Vec<_> = execution_results
+            .into_iter()
+            .map(
+                |DeployExecutionInfo {
+                     deploy_hash,
+                     header,
+                     result,
+                     ..
+                 }| (deploy_hash, header, result),
+            )
+            .collect();
         let execution_results_map: HashMap<_, _> = execution_results
             .iter()
             .cloned()
@@ -1071,6 +1360,16 @@ mod trie_chunking_tests {
             true,
             DEFAULT_REFUND_HANDLING,
             DEFAULT_FEE_HANDLING,
+            false,
+            None,
+            None,
+            None,
+            false,
+            None,
+            false,
+            false,
+            None,
+            "casper-example".to_string(),
         )
         .unwrap();
         let empty_state_root = contract_runtime
@@ -7,6 +7,10 @@ const DEFAULT_MAX_GLOBAL_STATE_SIZE: usize = 805_306_368_000; // 750 GiB
 const DEFAULT_MAX_READERS: u32 = 512;
 const DEFAULT_MAX_QUERY_DEPTH: u64 = 5;
 const DEFAULT_MANUAL_SYNC_ENABLED: bool = true;
+const DEFAULT_TRACE_STATE_ROOTS: bool = false;
+const DEFAULT_PARALLEL_DEPLOY_EXECUTION: bool = false;
+const DEFAULT_CHECKPOINT_INTERVAL: usize = 0;
+const DEFAULT_FLUSH_RETRIES: u32 = 0;
 
 /// Contract runtime configuration.
 #[derive(Clone, Copy, DataSize, Debug, Deserialize, Serialize)]
@@ -31,6 +35,26 @@ pub struct Config {
     ///
     /// Defaults to `true`.
     pub enable_manual_sync: Option<bool>,
+    /// Record the state root hash after every deploy of an executed block, for debugging.
+    ///
+    /// Defaults to `false`.
+    pub trace_state_roots: Option<bool>,
+    /// Experimental: concurrently execute deploys of a block which do not conflict with each
+    /// other. Deploys which fail are executed on their own.
+    ///
+    /// Defaults to `false`.
+    pub parallel_deploy_execution: Option<bool>,
+    /// The number of deploys of a block to commit between consecutive writes of the global state
+    /// to LMDB, each recording a checkpoint from which an interrupted execution of the block is
+    /// resumed on restart. `0` disables checkpointing.
+    ///
+    /// Defaults to `0`.
+    pub checkpoint_interval: Option<usize>,
+    /// The number of times a failed flush of the global state to disk is retried after executing
+    /// a block, waiting twice as long before each retry as before the previous one.
+    ///
+    /// Defaults to `0`.
+    pub flush_retries: Option<u32>,
 }
 
 impl Config {
@@ -58,6 +82,28 @@ impl Config {
         self.enable_manual_sync
             .unwrap_or(DEFAULT_MANUAL_SYNC_ENABLED)
     }
+
+    /// Is tracing of state roots enabled.
+    pub fn trace_state_roots_or_default(&self) -> bool {
+        self.trace_state_roots.unwrap_or(DEFAULT_TRACE_STATE_ROOTS)
+    }
+
+    /// Is parallel deploy execution enabled.
+    pub fn parallel_deploy_execution_or_default(&self) -> bool {
+        self.parallel_deploy_execution
+            .unwrap_or(DEFAULT_PARALLEL_DEPLOY_EXECUTION)
+    }
+
+    /// The number of deploys between block execution checkpoints, `0` if disabled.
+    pub fn checkpoint_interval_or_default(&self) -> usize {
+        self.checkpoint_interval
+            .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL)
+    }
+
+    /// The number of retries of a failed flush of the global state to disk.
+    pub fn flush_retries_or_default(&self) -> u32 {
+        self.flush_retries.unwrap_or(DEFAULT_FLUSH_RETRIES)
+    }
 }
 
 impl Default for Config {
@@ -67,6 +113,10 @@ impl Default for Config {
             max_readers: Some(DEFAULT_MAX_READERS),
             max_query_depth: Some(DEFAULT_MAX_QUERY_DEPTH),
             enable_manual_sync: Some(DEFAULT_MANUAL_SYNC_ENABLED),
+            trace_state_roots: Some(DEFAULT_TRACE_STATE_ROOTS),
+            parallel_deploy_execution: Some(DEFAULT_PARALLEL_DEPLOY_EXECUTION),
+            checkpoint_interval: Some(DEFAULT_CHECKPOINT_INTERVAL),
+            flush_retries: Some(DEFAULT_FLUSH_RETRIES),
         }
     }
 }
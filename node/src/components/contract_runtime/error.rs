@@ -4,15 +4,63 @@ use serde::Serialize;
 use thiserror::Error;
 
 use casper_execution_engine::{
-    core::engine_state::{Error as EngineStateError, StepError},
+    core::{
+        engine_state::{Error as EngineStateError, StepError},
+        execution::Error as ExecutionError,
+    },
     storage::error::lmdb::Error as StorageLmdbError,
 };
 
 use crate::{
     components::contract_runtime::ExecutionPreState,
-    types::{error::BlockCreationError, FinalizedBlock},
+    types::{
+        error::BlockCreationError, ApprovalsHash, ApprovalsHashesValidationError, BlockHash,
+        DeployHash, FinalizedBlock,
+    },
 };
 use casper_execution_engine::core::engine_state::GetEraValidatorsError;
+use casper_hashing::{Digest, MerkleConstructionError};
+use casper_types::{bytesrepr, Key, ProtocolVersion, PublicKey, Timestamp};
+
+/// What a step failed on, as determined from the [`StepError`] it returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum StepPhase {
+    /// A system contract the step calls, such as the auction, could not be found.
+    AuctionMissing,
+    /// Distributing the rewards of the era report failed.
+    InvalidReward,
+    /// The pre-state of the step could not be read, or its effects could not be committed.
+    CommitFailed,
+    /// Any other failure, e.g. of slashing or of running the auction.
+    Other,
+}
+
+impl StepPhase {
+    fn of(error: &StepError) -> Self {
+        match error {
+            StepError::GetContractError(_) | StepError::GetSystemModuleError(_) => {
+                StepPhase::AuctionMissing
+            }
+            StepError::SlashingError(error)
+            | StepError::AuctionError(error)
+            | StepError::DistributeError(error)
+            | StepError::DistributeAccumulatedFeesError(error)
+                if matches!(
+                    error,
+                    ExecutionError::MissingSystemContractRegistry
+                        | ExecutionError::MissingSystemContractHash(_)
+                ) =>
+            {
+                StepPhase::AuctionMissing
+            }
+            StepError::DistributeError(_) => StepPhase::InvalidReward,
+            StepError::RootNotFound(_)
+            | StepError::TrackingCopyError(_)
+            | StepError::ExecutionError(_) => StepPhase::CommitFailed,
+            _ => StepPhase::Other,
+        }
+    }
+}
 
 /// An error returned from mis-configuring the contract runtime component.
 #[derive(Debug, Error)]
@@ -28,10 +76,30 @@ pub(crate) enum ConfigError {
 /// An error during block execution.
 #[derive(Debug, Error, Serialize)]
 pub enum BlockExecutionError {
-    /// Currently the contract runtime can only execute one commit at a time, so we cannot handle
-    /// more than one execution result.
-    #[error("more than one execution result")]
-    MoreThanOneExecutionResult,
+    /// The execution engine returned no execution result for a deploy executed on its own.
+    #[error("no execution result for deploy {deploy_hash}")]
+    NoExecutionResult {
+        /// The hash of the deploy.
+        deploy_hash: DeployHash,
+    },
+    /// The execution engine returned more than one execution result for a deploy executed on its
+    /// own.
+    #[error("{count} execution results for deploy {deploy_hash}")]
+    MoreThanOneExecutionResult {
+        /// The hash of the deploy.
+        deploy_hash: DeployHash,
+        /// The number of execution results returned.
+        count: usize,
+    },
+    /// The execution engine returned a different number of execution results than the number of
+    /// deploys in the request, so the results cannot be matched to their deploys.
+    #[error("expected {expected} execution results, got {actual}")]
+    ExecutionResultCountMismatch {
+        /// The number of deploys in the request.
+        expected: usize,
+        /// The number of execution results returned.
+        actual: usize,
+    },
     /// Both the block to be executed and the execution pre-state specify the height of the next
     /// block. These must agree and this error will be thrown if they do not.
     #[error(
@@ -45,6 +113,113 @@ pub enum BlockExecutionError {
         /// The state of the block chain prior to block execution that was to be used.
         execution_pre_state: Box<ExecutionPreState>,
     },
+    /// The parent hash expected by the block to be executed differs from the parent hash of the
+    /// execution pre-state.
+    #[error("expected parent hash {expected}, execution pre-state has parent hash {actual}")]
+    WrongParentHash {
+        /// The parent hash expected by the block.
+        expected: BlockHash,
+        /// The parent hash of the execution pre-state.
+        actual: BlockHash,
+    },
+    /// The execution pre-state's root is not present in global state, e.g. as it has been pruned.
+    #[error("pre-state root {state_root_hash} is not present in global state")]
+    PrunedStateRoot {
+        /// The missing state root hash.
+        state_root_hash: Digest,
+    },
+    /// The protocol version the block is to be executed under is lower than the one the global
+    /// state at the execution pre-state's root was produced under.
+    #[error("protocol version {actual} is lower than {expected_min} of the pre-state root")]
+    ProtocolVersionRegression {
+        /// The protocol version recorded in global state at the pre-state root hash.
+        expected_min: ProtocolVersion,
+        /// The protocol version the block was to be executed under.
+        actual: ProtocolVersion,
+    },
+    /// Additional era reports were given for a block which is not a switch block, or under a
+    /// protocol version which doesn't allow a block to end more than one era.
+    #[error("{count} additional era reports not allowed for block at height {block_height}")]
+    UnexpectedEraReports {
+        /// The number of additional era reports.
+        count: usize,
+        /// The height of the block.
+        block_height: u64,
+    },
+    /// A seed override was given to a node built without the `testing` feature.
+    #[error("overriding the execution seed requires the testing feature")]
+    SeedOverrideNotAllowed,
+    /// A checksum scheme override was given to a node built without the `testing` feature.
+    #[error("overriding the execution results checksum scheme requires the testing feature")]
+    ChecksumOverrideNotAllowed,
+    /// The block includes the same deploy more than once.
+    #[error("block includes deploy {deploy_hash} more than once")]
+    DuplicateDeploy {
+        /// The hash of the repeated deploy.
+        deploy_hash: DeployHash,
+    },
+    /// The state root hash computed by executing a block differs from the expected one.
+    #[error(
+        "computed state root hash {actual} differs from expected {expected}, \
+         first divergent deploy: {first_divergent_deploy:?}"
+    )]
+    RootMismatch {
+        /// The expected state root hash.
+        expected: Digest,
+        /// The computed state root hash.
+        actual: Digest,
+        /// The first deploy after which the computed state root differs from the reference one,
+        /// if known.
+        first_divergent_deploy: Option<DeployHash>,
+    },
+    /// Executing the block twice yielded different state roots or execution results.
+    #[error(
+        "nondeterministic execution: first run yielded state root {first_run}, second run \
+         yielded {second_run}"
+    )]
+    NondeterministicExecution {
+        /// The last state root recorded by the first run, before pruning.
+        first_run: Digest,
+        /// The last state root recorded by the second run, before pruning.
+        second_run: Digest,
+    },
+    /// The Merkle proof of the checksum registry could not be created.
+    #[error("failed to get proof of checksum registry at state root {state_root_hash}: {source}")]
+    ChecksumRegistryProof {
+        /// The state root hash at which the proof was requested.
+        state_root_hash: Digest,
+        /// The underlying error.
+        #[serde(skip_serializing)]
+        source: EngineStateError,
+    },
+    /// The proposer of the block is not a validator of the block's era.
+    #[error("block proposer {proposer} is not a validator of the block's era")]
+    UnknownProposer {
+        /// The proposer of the block.
+        proposer: Box<PublicKey>,
+    },
+    /// The checkpoint to resume execution of a block from does not belong to the block.
+    #[error("execution checkpoint does not match the block at height {block_height}")]
+    InvalidCheckpoint {
+        /// The height of the block being executed.
+        block_height: u64,
+    },
+    /// Checkpointing was requested while streaming the deploy results, which checkpoints retain.
+    #[error("cannot checkpoint execution of a block while streaming its deploy results")]
+    CheckpointingWhileStreaming,
+    /// The system contract registry is missing from global state, so the era validators could not
+    /// be read.
+    #[error("system contract registry missing at state root {state_root_hash}")]
+    MissingSystemContractRegistry {
+        /// The state root hash at which the era validators were requested.
+        state_root_hash: Digest,
+    },
+    /// The global state could not be read back at the state root hash just written to LMDB.
+    #[error("state root {state_root_hash} not readable after being written")]
+    RootNotReadable {
+        /// The state root hash which was written.
+        state_root_hash: Digest,
+    },
     /// A core error thrown by the execution engine.
     #[error(transparent)]
     EngineState(
@@ -52,13 +227,15 @@ pub enum BlockExecutionError {
         #[serde(skip_serializing)]
         EngineStateError,
     ),
-    /// An error that occurred when trying to run the auction contract.
-    #[error(transparent)]
-    Step(
-        #[from]
+    /// Committing the step at the end of an era failed.
+    #[error("step failed ({phase:?}): {source}")]
+    StepFailed {
+        /// What the step failed on.
+        phase: StepPhase,
+        /// The error returned by the execution engine.
         #[serde(skip_serializing)]
-        StepError,
-    ),
+        source: StepError,
+    },
     /// An error that occurred while creating a block.
     #[error(transparent)]
     BlockCreation(#[from] BlockCreationError),
@@ -69,6 +246,80 @@ pub enum BlockExecutionError {
         #[serde(skip_serializing)]
         lmdb::Error,
     ),
+    /// Flushing the LMDB environment failed on every attempt.
+    #[error("failed to flush LMDB environment after {attempts} attempts: {source}")]
+    FlushFailed {
+        /// The number of attempts made.
+        attempts: u32,
+        /// The error of the last attempt.
+        #[serde(skip_serializing)]
+        source: lmdb::Error,
+    },
+    /// The gas consumed by the deploys of a block exceeded the block execution gas limit, so
+    /// execution of the block was aborted.
+    #[error("block execution gas limit exceeded after executing {executed} of {total} deploys")]
+    BlockGasLimitExceeded {
+        /// The number of deploys executed, including the one which exceeded the limit.
+        executed: usize,
+        /// The number of deploys in the block.
+        total: usize,
+    },
+    /// A deploy failed while executing a block in strict mode, so execution of the block was
+    /// aborted.
+    #[error("deploy {deploy_hash} failed in strict mode: {error}")]
+    DeployFailedInStrictMode {
+        /// The hash of the failed deploy.
+        deploy_hash: DeployHash,
+        /// The error the deploy failed with.
+        #[serde(skip_serializing)]
+        error: EngineStateError,
+    },
+    /// A block with deploys left the state root hash unchanged, although executing it was required
+    /// to change global state.
+    #[error("block with deploys left the state root hash {state_root_hash} unchanged")]
+    NoStateProgress {
+        /// The state root hash both before and after executing the block.
+        state_root_hash: Digest,
+    },
+    /// The effects of a deploy could not be committed, as a transform of the value under a key is
+    /// incompatible with the value held, e.g. an addition to a non-numeric value.
+    #[error("failed to apply effect of deploy {deploy_hash} to key {key}: {error}")]
+    EffectApplyConflict {
+        /// The hash of the deploy.
+        deploy_hash: DeployHash,
+        /// The key under which the transform could not be applied.
+        key: Key,
+        /// The error returned when committing the effects.
+        #[serde(skip_serializing)]
+        error: EngineStateError,
+    },
+    /// The block includes more deploys than a block may contain, so it was not executed.
+    #[error("block includes {count} deploys, the maximum is {max}")]
+    TooManyDeploys {
+        /// The number of deploys in the block.
+        count: usize,
+        /// The maximum number of deploys a block may contain.
+        max: usize,
+    },
+    /// The serialized execution result of a deploy exceeds the maximum size, so execution of the
+    /// block was aborted.
+    #[error("execution result of deploy {deploy_hash} is {size} bytes, exceeding the maximum")]
+    ExecutionResultTooLarge {
+        /// The hash of the deploy.
+        deploy_hash: DeployHash,
+        /// The size of the serialized execution result in bytes.
+        size: usize,
+    },
+    /// A key written by the block lies deeper in the global state trie than the configured limit.
+    #[error("key {key} written at trie depth {depth}, the maximum is {max}")]
+    TrieDepthExceeded {
+        /// The written key.
+        key: Key,
+        /// The depth of the key in the trie.
+        depth: usize,
+        /// The maximum trie depth.
+        max: usize,
+    },
     /// An error that occurred while getting era validators.
     #[error(transparent)]
     GetEraValidators(
@@ -77,3 +328,194 @@ pub enum BlockExecutionError {
         GetEraValidatorsError,
     ),
 }
+
+impl From<StepError> for BlockExecutionError {
+    fn from(source: StepError) -> Self {
+        BlockExecutionError::StepFailed {
+            phase: StepPhase::of(&source),
+            source,
+        }
+    }
+}
+
+/// An error during speculative execution of a deploy.
+#[derive(Debug, Error)]
+pub enum SpeculativeExecutionError {
+    /// The deploy would be rejected when executing a finalized block, so it was not executed.
+    #[error("invalid deploy: {0}")]
+    InvalidDeploy(InvalidSpeculativeDeploy),
+    /// A core error thrown by the execution engine.
+    #[error(transparent)]
+    EngineState(#[from] EngineStateError),
+}
+
+/// An error committing a maintenance step, see
+/// [`ContractRuntime::commit_maintenance_step`](super::ContractRuntime::commit_maintenance_step).
+#[derive(Debug, Error)]
+pub(crate) enum MaintenanceStepError {
+    /// Maintenance steps are not allowed by the chainspec.
+    #[error("maintenance steps are not allowed by the chainspec")]
+    NotAllowed,
+    /// An error that occurred when trying to run the auction contract.
+    #[error(transparent)]
+    Step(#[from] StepError),
+    /// An error that occurred while interacting with lmdb.
+    #[error(transparent)]
+    Lmdb(#[from] lmdb::Error),
+}
+
+/// An error returned by the self-check of the execution engine, see
+/// [`engine_self_check`](super::engine_self_check).
+#[derive(Debug, Error)]
+pub enum SelfCheckError {
+    /// The state root to execute the check deploy against is not present in global state.
+    #[error("state root {state_root_hash} missing from global state")]
+    MissingRoot {
+        /// The state root hash the check was run against.
+        state_root_hash: Digest,
+    },
+    /// The execution engine panicked while executing the check deploy.
+    #[error("execution engine panicked: {message}")]
+    Panicked {
+        /// The panic message, if it could be recovered.
+        message: String,
+    },
+    /// The execution engine returned other than exactly one execution result for the check
+    /// deploy.
+    #[error("expected exactly one execution result, got {count}")]
+    UnexpectedResultCount {
+        /// The number of execution results returned.
+        count: usize,
+    },
+    /// A core error thrown by the execution engine.
+    #[error(transparent)]
+    EngineState(#[from] EngineStateError),
+}
+
+/// The reason a deploy was rejected for speculative execution.
+#[derive(Debug, Error)]
+pub enum InvalidSpeculativeDeploy {
+    /// The deploy's time-to-live has expired.
+    #[error("deploy expired at {expiry}, current time is {current_time}")]
+    Expired {
+        /// The time at which the deploy expired.
+        expiry: Timestamp,
+        /// The time at which execution was requested.
+        current_time: Timestamp,
+    },
+    /// The deploy is intended for a different chain.
+    #[error("invalid chain name: expected {expected}, got {got}")]
+    InvalidChainName {
+        /// The name of the chain the deploy was to be executed on.
+        expected: String,
+        /// The chain name in the deploy's header.
+        got: String,
+    },
+}
+
+/// An error verifying the approvals hashes of a block against its deploys, see
+/// [`verify_approvals_hashes`](super::verify_approvals_hashes).
+#[derive(Debug, Error)]
+pub(crate) enum ApprovalsError {
+    /// The approvals hashes are bound to a different block.
+    #[error("approvals hashes are for block {actual}, expected block {expected}")]
+    WrongBlockHash {
+        /// The hash of the block being verified.
+        expected: BlockHash,
+        /// The hash of the block the approvals hashes are for.
+        actual: BlockHash,
+    },
+    /// The numbers of deploys, approvals hashes and deploys of the block are not all equal.
+    #[error(
+        "block has {block_deploy_count} deploys, got {approvals_hash_count} approvals hashes and \
+         {deploy_count} deploys"
+    )]
+    CountMismatch {
+        /// The number of deploys and transfers of the block.
+        block_deploy_count: usize,
+        /// The number of approvals hashes.
+        approvals_hash_count: usize,
+        /// The number of deploys given.
+        deploy_count: usize,
+    },
+    /// A deploy given is not the one at its position in the block.
+    #[error("expected deploy {expected} at index {index}, got {actual}")]
+    WrongDeploy {
+        /// The position of the deploy in the block.
+        index: usize,
+        /// The hash of the deploy at the position in the block.
+        expected: DeployHash,
+        /// The hash of the deploy given.
+        actual: DeployHash,
+    },
+    /// The approvals hash of a deploy could not be computed.
+    #[error("failed to compute approvals hash of deploy {deploy_hash}: {error}")]
+    ApprovalsHash {
+        /// The hash of the deploy.
+        deploy_hash: DeployHash,
+        /// The underlying error.
+        error: bytesrepr::Error,
+    },
+    /// The approvals hash computed from a deploy differs from the one given for it.
+    #[error(
+        "approvals hash {computed} of deploy {deploy_hash} differs from approvals hash {given}"
+    )]
+    ApprovalsHashMismatch {
+        /// The hash of the deploy.
+        deploy_hash: DeployHash,
+        /// The approvals hash computed from the deploy's approvals.
+        computed: ApprovalsHash,
+        /// The approvals hash given for the deploy.
+        given: ApprovalsHash,
+    },
+    /// The proof of the approvals checksum does not match the block.
+    #[error(transparent)]
+    InvalidProof(#[from] ApprovalsHashesValidationError),
+}
+
+/// An error constructing an
+/// [`ExecutionResultInclusionProof`](super::ExecutionResultInclusionProof).
+#[derive(Debug, Error)]
+pub enum ExecutionResultProofError {
+    /// There is no execution result at the requested index.
+    #[error("no execution result at index {index}, the block has {count}")]
+    IndexOutOfBounds {
+        /// The requested index.
+        index: usize,
+        /// The number of execution results.
+        count: usize,
+    },
+    /// The execution results could not be serialized.
+    #[error("failed to serialize execution results: {0}")]
+    BytesRepr(bytesrepr::Error),
+    /// The Merkle proof of a chunk of the serialized execution results could not be created.
+    #[error(transparent)]
+    MerkleConstruction(#[from] MerkleConstructionError),
+}
+
+impl From<bytesrepr::Error> for ExecutionResultProofError {
+    fn from(error: bytesrepr::Error) -> Self {
+        ExecutionResultProofError::BytesRepr(error)
+    }
+}
+
+/// An error decoding [`BlockAndExecutionResults`](super::BlockAndExecutionResults) from its
+/// versioned binary format.
+#[derive(Debug, Error)]
+pub enum VersionedBytesError {
+    /// The encoding uses a format version this node does not support.
+    #[error("unsupported format version {0}")]
+    UnsupportedVersion(u8),
+    /// The checksum of the encoding does not match its contents.
+    #[error("checksum mismatch")]
+    ChecksumMismatch,
+    /// The contents of the encoding could not be parsed.
+    #[error("failed to parse: {0}")]
+    BytesRepr(bytesrepr::Error),
+}
+
+impl From<bytesrepr::Error> for VersionedBytesError {
+    fn from(error: bytesrepr::Error) -> Self {
+        VersionedBytesError::BytesRepr(error)
+    }
+}
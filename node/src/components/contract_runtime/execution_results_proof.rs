@@ -0,0 +1,197 @@
+//! Proofs of inclusion of an execution result in a block's execution results checksum.
+
+use std::convert::TryFrom;
+
+use casper_hashing::{ChunkWithProof, Digest};
+use casper_types::{
+    bytesrepr::{self, Bytes, FromBytes, ToBytes, U32_SERIALIZED_LENGTH},
+    ExecutionResult,
+};
+
+use crate::components::contract_runtime::error::ExecutionResultProofError;
+
+/// A proof that an execution result is the one at a given index in the execution results of a
+/// block, verifiable against the block's execution results checksum alone.
+///
+/// The checksum is the hash of the serialized `Vec<ExecutionResult>`, which is split into chunks
+/// of [`ChunkWithProof::CHUNK_SIZE_BYTES`] and hashed as a Merkle tree if it does not fit into a
+/// single chunk. A serialized result can only be located by parsing the results preceding it, so
+/// the proof holds the serialized results up to and including the proven one: all of them if they
+/// fit into a single chunk, or otherwise the chunks with their Merkle proofs up to the one holding
+/// the end of the proven result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionResultInclusionProof {
+    index: u32,
+    serialized_results: SerializedResults,
+}
+
+/// The serialized execution results held by an [`ExecutionResultInclusionProof`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SerializedResults {
+    /// All of the serialized results, which fit into a single chunk.
+    Unchunked(Bytes),
+    /// The leading chunks of the serialized results.
+    Chunked(Vec<ChunkWithProof>),
+}
+
+impl ExecutionResultInclusionProof {
+    /// Constructs the proof of inclusion of the execution result at `index` in `execution_results`.
+    pub fn new(
+        execution_results: &[ExecutionResult],
+        index: usize,
+    ) -> Result<Self, ExecutionResultProofError> {
+        if index >= execution_results.len() {
+            return Err(ExecutionResultProofError::IndexOutOfBounds {
+                index,
+                count: execution_results.len(),
+            });
+        }
+        // Serialized as a `Vec<ExecutionResult>`, without cloning the results into one.
+        let count = u32::try_from(execution_results.len())
+            .map_err(|_| bytesrepr::Error::NotRepresentable)?;
+        let mut bytes = count.to_bytes()?;
+        for execution_result in execution_results {
+            execution_result.write_bytes(&mut bytes)?;
+        }
+        let serialized_results = if bytes.len() <= ChunkWithProof::CHUNK_SIZE_BYTES {
+            SerializedResults::Unchunked(Bytes::from(bytes))
+        } else {
+            let end = U32_SERIALIZED_LENGTH
+                + execution_results[..=index]
+                    .iter()
+                    .map(ToBytes::serialized_length)
+                    .sum::<usize>();
+            let chunk_count =
+                (end + ChunkWithProof::CHUNK_SIZE_BYTES - 1) / ChunkWithProof::CHUNK_SIZE_BYTES;
+            let chunks = (0..chunk_count as u64)
+                .map(|chunk_index| ChunkWithProof::new(&bytes, chunk_index))
+                .collect::<Result<_, _>>()?;
+            SerializedResults::Chunked(chunks)
+        };
+        Ok(ExecutionResultInclusionProof {
+            // Less than `count`, so representable.
+            index: index as u32,
+            serialized_results,
+        })
+    }
+
+    /// Returns the index of the proven execution result in the execution results of the block.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns `true` if `execution_result` is the one at the proof's index in the execution
+    /// results with the given checksum.
+    pub fn verify(
+        &self,
+        execution_results_checksum: Digest,
+        execution_result: &ExecutionResult,
+    ) -> bool {
+        let proven_result = match &self.serialized_results {
+            SerializedResults::Unchunked(bytes) => {
+                if bytes.len() > ChunkWithProof::CHUNK_SIZE_BYTES
+                    || Digest::hash(bytes) != execution_results_checksum
+                {
+                    return false;
+                }
+                result_at(bytes.as_slice(), self.index)
+            }
+            SerializedResults::Chunked(chunks) => {
+                let mut bytes = Vec::new();
+                for (chunk_index, chunk) in chunks.iter().enumerate() {
+                    if chunk.verify().is_err()
+                        || chunk.proof().index() != chunk_index as u64
+                        || chunk.proof().root_hash() != execution_results_checksum
+                    {
+                        return false;
+                    }
+                    bytes.extend_from_slice(chunk.chunk());
+                }
+                result_at(&bytes, self.index)
+            }
+        };
+        proven_result.map_or(false, |proven_result| proven_result == *execution_result)
+    }
+}
+
+/// Parses the execution result at `index` from the leading bytes of a serialized
+/// `Vec<ExecutionResult>`.
+fn result_at(bytes: &[u8], index: u32) -> Result<ExecutionResult, bytesrepr::Error> {
+    let (count, mut remainder) = u32::from_bytes(bytes)?;
+    if index >= count {
+        return Err(bytesrepr::Error::Formatting);
+    }
+    for _ in 0..index {
+        remainder = ExecutionResult::from_bytes(remainder)?.1;
+    }
+    ExecutionResult::from_bytes(remainder).map(|(execution_result, _)| execution_result)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use casper_types::testing::TestRng;
+
+    use super::*;
+    use crate::components::contract_runtime::{
+        operations::compute_execution_results_checksum, DefaultExecutionResultsChecksum,
+    };
+
+    #[test]
+    fn should_verify_inclusion_of_execution_results() {
+        let mut rng = TestRng::new();
+        let execution_results = (0..5)
+            .map(|_| rng.gen::<ExecutionResult>())
+            .collect::<Vec<_>>();
+        let checksum = compute_execution_results_checksum(
+            &DefaultExecutionResultsChecksum,
+            execution_results.iter(),
+        )
+        .unwrap();
+
+        for (index, execution_result) in execution_results.iter().enumerate() {
+            let proof = ExecutionResultInclusionProof::new(&execution_results, index).unwrap();
+            assert_eq!(proof.index() as usize, index);
+            assert!(proof.verify(checksum, execution_result));
+
+            // The proof does not hold for a different result, nor for a different checksum.
+            let other_index = (index + 1) % execution_results.len();
+            assert!(!proof.verify(checksum, &execution_results[other_index]));
+            assert!(!proof.verify(Digest::hash([0; 32]), execution_result));
+        }
+    }
+
+    #[test]
+    fn should_not_verify_tampered_execution_result() {
+        let mut rng = TestRng::new();
+        let mut execution_results = (0..3)
+            .map(|_| rng.gen::<ExecutionResult>())
+            .collect::<Vec<_>>();
+        let checksum = compute_execution_results_checksum(
+            &DefaultExecutionResultsChecksum,
+            execution_results.iter(),
+        )
+        .unwrap();
+
+        let tampered_result = loop {
+            let execution_result = rng.gen::<ExecutionResult>();
+            if execution_result != execution_results[1] {
+                break execution_result;
+            }
+        };
+        execution_results[1] = tampered_result.clone();
+        let proof = ExecutionResultInclusionProof::new(&execution_results, 1).unwrap();
+        assert!(!proof.verify(checksum, &tampered_result));
+    }
+
+    #[test]
+    fn should_not_prove_out_of_bounds_index() {
+        let mut rng = TestRng::new();
+        let execution_results = vec![rng.gen::<ExecutionResult>()];
+        assert!(matches!(
+            ExecutionResultInclusionProof::new(&execution_results, 1),
+            Err(ExecutionResultProofError::IndexOutOfBounds { index: 1, count: 1 })
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+//! Detection of conflicts between deploys executed against the same global state.
+
+use std::collections::BTreeSet;
+
+use casper_execution_engine::shared::{additive_map::AdditiveMap, transform::Transform};
+use casper_types::{Key, StoredValue};
+
+/// The global state keys accessed by a deploy, classified by how they were accessed.
+///
+/// Two successful deploys whose footprints do not conflict can be executed against the same global
+/// state and have their effects committed one after the other.  The footprint of a failed deploy
+/// is incomplete, as the EE discards what its session read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct DeployFootprint {
+    /// Keys which were read, or overwritten with the value they already held.
+    read: BTreeSet<Key>,
+    /// Purse balances which were added to.
+    added: BTreeSet<Key>,
+    /// Keys which were modified in any other way.
+    written: BTreeSet<Key>,
+}
+
+impl DeployFootprint {
+    /// Constructs the footprint of a deploy from its effects.
+    ///
+    /// `read_pre_state` must return the value held by a key in the global state the deploy was
+    /// executed against.
+    pub(super) fn new<F, E>(
+        effects: &AdditiveMap<Key, Transform>,
+        mut read_pre_state: F,
+    ) -> Result<Self, E>
+    where
+        F: FnMut(&Key) -> Result<Option<StoredValue>, E>,
+    {
+        let mut footprint = DeployFootprint::default();
+        for (key, transform) in effects.iter() {
+            match transform {
+                Transform::Identity => {
+                    footprint.read.insert(*key);
+                }
+                Transform::Write(value) => {
+                    if read_pre_state(key)?.as_ref() == Some(value) {
+                        footprint.read.insert(*key);
+                    } else {
+                        footprint.written.insert(*key);
+                    }
+                }
+                // The additive map folds a read preceding an addition into the addition. Only
+                // the mint adds to balances, and it reads a balance beforehand only to check that
+                // the purse exists, so additions to balances commute. Any other addition may
+                // depend on the value read, and is treated as a write.
+                Transform::AddUInt512(_) if matches!(key, Key::Balance(_)) => {
+                    footprint.added.insert(*key);
+                }
+                Transform::AddInt32(_)
+                | Transform::AddUInt64(_)
+                | Transform::AddUInt128(_)
+                | Transform::AddUInt256(_)
+                | Transform::AddUInt512(_)
+                | Transform::AddKeys(_)
+                | Transform::Failure(_) => {
+                    footprint.written.insert(*key);
+                }
+            }
+        }
+        Ok(footprint)
+    }
+
+    /// Returns `true` if the effects of either deploy could alter the execution of the other.
+    pub(super) fn conflicts_with(&self, other: &DeployFootprint) -> bool {
+        fn modifies_what_is_used(lhs: &DeployFootprint, rhs: &DeployFootprint) -> bool {
+            lhs.written.iter().any(|key| {
+                rhs.read.contains(key) || rhs.added.contains(key) || rhs.written.contains(key)
+            }) || lhs.added.iter().any(|key| rhs.read.contains(key))
+        }
+        modifies_what_is_used(self, other) || modifies_what_is_used(other, self)
+    }
+
+    /// Merges `other` into this footprint, so that it covers the keys accessed by both deploys.
+    pub(super) fn merge(&mut self, other: DeployFootprint) {
+        self.read.extend(other.read);
+        self.added.extend(other.added);
+        self.written.extend(other.written);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, convert::Infallible};
+
+    use casper_types::{CLValue, URef, U512};
+
+    use super::*;
+
+    fn footprint(
+        pre_state: &BTreeMap<Key, StoredValue>,
+        transforms: Vec<(Key, Transform)>,
+    ) -> DeployFootprint {
+        let effects: AdditiveMap<Key, Transform> = transforms.into_iter().collect();
+        DeployFootprint::new(&effects, |key| {
+            Ok::<_, Infallible>(pre_state.get(key).cloned())
+        })
+        .unwrap()
+    }
+
+    fn balance(value: u64) -> StoredValue {
+        StoredValue::CLValue(CLValue::from_t(U512::from(value)).unwrap())
+    }
+
+    #[test]
+    fn should_detect_conflicts() {
+        let purse = Key::Balance(URef::default().addr());
+        let other_purse = Key::Balance([1; 32]);
+        let pre_state = BTreeMap::from([(purse, balance(0)), (other_purse, balance(0))]);
+
+        let read = footprint(&pre_state, vec![(purse, Transform::Identity)]);
+        let added = footprint(&pre_state, vec![(purse, Transform::AddUInt512(1.into()))]);
+        let written = footprint(&pre_state, vec![(purse, Transform::Write(balance(1)))]);
+        let unchanged = footprint(&pre_state, vec![(purse, Transform::Write(balance(0)))]);
+        let unrelated = footprint(
+            &pre_state,
+            vec![(other_purse, Transform::Write(balance(1)))],
+        );
+
+        assert_eq!(unchanged, read);
+        assert!(!read.conflicts_with(&read));
+        assert!(!added.conflicts_with(&added));
+        assert!(read.conflicts_with(&added));
+        assert!(added.conflicts_with(&read));
+        assert!(written.conflicts_with(&read));
+        assert!(written.conflicts_with(&added));
+        assert!(written.conflicts_with(&written));
+        assert!(!written.conflicts_with(&unrelated));
+
+        let mut merged = read.clone();
+        merged.merge(unrelated.clone());
+        assert!(merged.conflicts_with(&added));
+        assert!(merged.conflicts_with(&unrelated));
+    }
+
+    #[test]
+    fn should_treat_additions_to_other_keys_as_writes() {
+        let key = Key::Hash([2; 32]);
+        let added = footprint(&BTreeMap::new(), vec![(key, Transform::AddInt32(1))]);
+        assert!(added.conflicts_with(&added));
+    }
+}
@@ -1,4 +1,6 @@
-use prometheus::{self, Gauge, Histogram, IntGauge, Registry};
+use prometheus::{
+    self, Counter, Gauge, Histogram, HistogramOpts, HistogramVec, IntCounter, IntGauge, Registry,
+};
 
 use crate::{unregister_metric, utils};
 
@@ -16,6 +18,12 @@ const EXPONENTIAL_BUCKET_COUNT: usize = 10;
 
 const RUN_EXECUTE_NAME: &str = "contract_runtime_run_execute";
 const RUN_EXECUTE_HELP: &str = "time in seconds to execute but not commit a contract";
+/// The label of `run_execute` distinguishing the execution of blocks from speculative execution.
+const RUN_EXECUTE_LABEL: &str = "execution";
+
+const SLOWEST_DEPLOY_IN_BLOCK_NAME: &str = "contract_runtime_slowest_deploy_in_block";
+const SLOWEST_DEPLOY_IN_BLOCK_HELP: &str =
+    "time in seconds to execute the slowest deploy of the latest executed block";
 
 const APPLY_EFFECT_NAME: &str = "contract_runtime_apply_commit";
 const APPLY_EFFECT_HELP: &str = "time in seconds to commit the execution effects of a contract";
@@ -48,17 +56,122 @@ const GET_TRIE_HELP: &str = "time in seconds to get a trie";
 const EXEC_BLOCK_NAME: &str = "contract_runtime_execute_block";
 const EXEC_BLOCK_HELP: &str = "time in seconds to execute all deploys in a block";
 
+const EXEC_BLOCK_SWITCH_NAME: &str = "contract_runtime_execute_block_switch";
+const EXEC_BLOCK_SWITCH_HELP: &str =
+    "time in seconds to execute a switch block, including the step at era end";
+
+const EXEC_BLOCK_NORMAL_NAME: &str = "contract_runtime_execute_block_normal";
+const EXEC_BLOCK_NORMAL_HELP: &str =
+    "time in seconds to execute a block which is not a switch block";
+
+const DEPLOYS_PER_BLOCK_NAME: &str = "contract_runtime_deploys_per_block";
+const DEPLOYS_PER_BLOCK_HELP: &str = "number of deploys in each executed block";
+
+const GAS_PER_BLOCK_NAME: &str = "contract_runtime_gas_per_block";
+const GAS_PER_BLOCK_HELP: &str = "gas consumed by the deploys of each executed block";
+
+const TRIE_READS_PER_BLOCK_NAME: &str = "contract_runtime_trie_reads_per_block";
+const TRIE_READS_PER_BLOCK_HELP: &str =
+    "number of tries read from global state to execute each block";
+
+const TRIE_WRITES_PER_BLOCK_NAME: &str = "contract_runtime_trie_writes_per_block";
+const TRIE_WRITES_PER_BLOCK_HELP: &str =
+    "number of tries written to global state to execute each block";
+
+const CHECKSUM_REGISTRY_PROOF_SIZE_NAME: &str =
+    "contract_runtime_checksum_registry_proof_size_bytes";
+const CHECKSUM_REGISTRY_PROOF_SIZE_HELP: &str =
+    "serialized size in bytes of the proof of the checksum registry of each executed block";
+
+const EXEC_BLOCK_GAS_LIMIT_EXCEEDED_NAME: &str =
+    "contract_runtime_execute_block_gas_limit_exceeded";
+const EXEC_BLOCK_GAS_LIMIT_EXCEEDED_HELP: &str =
+    "number of blocks whose execution was aborted for exceeding the block execution gas limit";
+
+const DEPLOYS_EXECUTED_TOTAL_NAME: &str = "contract_runtime_deploys_executed_total";
+const DEPLOYS_EXECUTED_TOTAL_HELP: &str = "number of deploys executed as part of executing blocks";
+
+const DEPLOYS_SUCCEEDED_TOTAL_NAME: &str = "contract_runtime_deploys_succeeded_total";
+const DEPLOYS_SUCCEEDED_TOTAL_HELP: &str =
+    "number of committed deploys which executed successfully";
+
+const DEPLOYS_FAILED_TOTAL_NAME: &str = "contract_runtime_deploys_failed_total";
+const DEPLOYS_FAILED_TOTAL_HELP: &str = "number of committed deploys whose execution failed";
+
+const TOTAL_GAS_CONSUMED_NAME: &str = "contract_runtime_total_gas_consumed";
+const TOTAL_GAS_CONSUMED_HELP: &str =
+    "gas consumed by all deploys executed as part of executing blocks";
+
+const SCRATCH_STATE_TRANSFORM_COUNT_NAME: &str = "contract_runtime_scratch_state_transform_count";
+const SCRATCH_STATE_TRANSFORM_COUNT_HELP: &str =
+    "number of values cached in the scratch state when it was last written to the database";
+
+const EXECUTION_RESULT_CACHE_HITS_NAME: &str = "contract_runtime_execution_result_cache_hits";
+const EXECUTION_RESULT_CACHE_HITS_HELP: &str =
+    "number of deploys whose execution result was served from the execution result cache";
+
+const ERA_VALIDATORS_CACHE_HITS_NAME: &str = "contract_runtime_era_validators_cache_hits";
+const ERA_VALIDATORS_CACHE_HITS_HELP: &str =
+    "number of switch blocks whose upcoming era validators were served from the cache";
+
+const READ_THROUGH_CACHE_HITS_NAME: &str = "contract_runtime_read_through_cache_hits";
+const READ_THROUGH_CACHE_HITS_HELP: &str =
+    "number of global state reads served from the read-through cache";
+
+const SCRATCH_WRITE_TO_DB_NAME: &str = "contract_runtime_scratch_write_to_db";
+const SCRATCH_WRITE_TO_DB_HELP: &str =
+    "time in seconds to write the changes cached while executing a block to global state";
+
+const FLUSH_ENVIRONMENT_NAME: &str = "contract_runtime_flush_environment";
+const FLUSH_ENVIRONMENT_HELP: &str =
+    "time in seconds to flush global state to disk after executing a block";
+
+const BLOCK_ASSEMBLY_NAME: &str = "contract_runtime_block_assembly";
+const BLOCK_ASSEMBLY_HELP: &str =
+    "time in seconds to construct the block and its approvals hashes after executing it";
+
 const LATEST_COMMIT_STEP_NAME: &str = "contract_runtime_latest_commit_step";
 const LATEST_COMMIT_STEP_HELP: &str = "duration in seconds of latest commit step at era end";
 
+const LAST_ERA_TOTAL_REWARDS_NAME: &str = "contract_runtime_last_era_total_rewards";
+const LAST_ERA_TOTAL_REWARDS_HELP: &str =
+    "sum of the rewards in the era report of the latest switch block executed";
+
+const VALIDATORS_EVICTED_NAME: &str = "contract_runtime_validators_evicted";
+const VALIDATORS_EVICTED_HELP: &str =
+    "number of validators evicted by the step of the latest switch block executed";
+
+const VALIDATORS_REWARDED_NAME: &str = "contract_runtime_validators_rewarded";
+const VALIDATORS_REWARDED_HELP: &str =
+    "number of validators rewarded by the step of the latest switch block executed";
+
 const EXEC_QUEUE_SIZE_NAME: &str = "execution_queue_size";
 const EXEC_QUEUE_SIZE_HELP: &str =
     "number of blocks that are currently enqueued and waiting for execution";
 
+/// What deploys are executed for, distinguished by the label of `run_execute`.
+#[derive(Clone, Copy, Debug)]
+pub(super) enum ExecutionKind {
+    /// Executing the deploys of a block.
+    Block,
+    /// Executing deploys speculatively, without committing their effects.
+    Speculative,
+}
+
+impl ExecutionKind {
+    fn label(self) -> &'static str {
+        match self {
+            ExecutionKind::Block => "block",
+            ExecutionKind::Speculative => "speculative",
+        }
+    }
+}
+
 /// Metrics for the contract runtime component.
 #[derive(Debug)]
 pub struct Metrics {
-    pub(super) run_execute: Histogram,
+    /// The time to execute deploys, labelled by the [`ExecutionKind`].
+    run_execute: HistogramVec,
     pub(super) apply_effect: Histogram,
     pub(super) commit_upgrade: Histogram,
     pub(super) run_query: Histogram,
@@ -69,7 +182,54 @@ pub struct Metrics {
     pub(super) put_trie: Histogram,
     pub(super) get_trie: Histogram,
     pub(super) exec_block: Histogram,
+    pub(super) exec_block_switch: Histogram,
+    pub(super) exec_block_normal: Histogram,
+    pub(super) deploys_per_block: Histogram,
+    pub(super) gas_per_block: Histogram,
+    /// The number of tries read from the trie store while executing each block.
+    ///
+    /// Counted as the change in the reads of the trie store over the execution of the block, so
+    /// it includes the reads of anything else using the store meanwhile, such as queries.
+    pub(super) trie_reads_per_block: Histogram,
+    /// The number of tries written to the trie store while executing each block, counted as for
+    /// `trie_reads_per_block`.
+    pub(super) trie_writes_per_block: Histogram,
+    /// The serialized size of the proof of the checksum registry read from LMDB for each block,
+    /// which is part of the approvals hashes downloaded with it.
+    pub(super) checksum_registry_proof_size_bytes: Histogram,
+    pub(super) exec_block_gas_limit_exceeded: IntCounter,
+    pub(super) deploys_executed_total: IntCounter,
+    pub(super) deploys_succeeded_total: IntCounter,
+    pub(super) deploys_failed_total: IntCounter,
+    /// The gas consumed by all deploys committed since the node started.
+    ///
+    /// The counter holds an `f64`, so it is only exact up to 2^53 gas, and the cost of each deploy
+    /// is capped at `u64::MAX` before being added.
+    pub(super) total_gas_consumed: Counter,
+    pub(super) execution_result_cache_hits: IntCounter,
+    pub(super) era_validators_cache_hits: IntCounter,
+    pub(super) read_through_cache_hits: IntCounter,
+    /// The number of values written by the transforms committed to the scratch state, set just
+    /// before it is written to LMDB.
+    pub(super) scratch_state_transform_count: IntGauge,
+    pub(super) scratch_write_to_db: Histogram,
+    pub(super) flush_environment: Histogram,
+    /// The time to construct an executed block and its approvals hashes, including reading the
+    /// proof of the checksum registry.
+    pub(super) block_assembly: Histogram,
     pub(super) latest_commit_step: Gauge,
+    /// The sum of the rewards in the era report of the latest switch block, as reported by
+    /// consensus.
+    pub(super) last_era_total_rewards: Gauge,
+    /// The number of validators evicted by the step of the latest switch block.
+    pub(super) validators_evicted: IntGauge,
+    /// The number of validators rewarded by the step of the latest switch block, excluding those
+    /// whose rewards were withheld.
+    pub(super) validators_rewarded: IntGauge,
+    /// The longest time spent executing a single deploy of the latest executed block.
+    ///
+    /// Deploys executed concurrently are timed together.
+    pub(super) slowest_deploy_in_block: Gauge,
     pub(super) exec_queue_size: IntGauge,
     registry: Registry,
 }
@@ -89,19 +249,90 @@ impl Metrics {
         // Anything above that should be a warning signal.
         let tiny_buckets = prometheus::exponential_buckets(0.001, 2.0, 10)?;
 
+        // From 1 deploy up to 2048.
+        let deploy_count_buckets = prometheus::exponential_buckets(1.0, 2.0, 12)?;
+
+        // From 0.1 CSPR worth of gas at a gas price of 1, up to about 400,000 CSPR.
+        let gas_buckets = prometheus::exponential_buckets(100_000_000.0, 4.0, 12)?;
+
+        // From 1 trie up to about 4 million.
+        let trie_count_buckets = prometheus::exponential_buckets(1.0, 4.0, 12)?;
+
+        // From 256 bytes up to 512 KiB.
+        let proof_size_buckets = prometheus::exponential_buckets(256.0, 2.0, 12)?;
+
         let latest_commit_step = Gauge::new(LATEST_COMMIT_STEP_NAME, LATEST_COMMIT_STEP_HELP)?;
         registry.register(Box::new(latest_commit_step.clone()))?;
 
+        let last_era_total_rewards =
+            Gauge::new(LAST_ERA_TOTAL_REWARDS_NAME, LAST_ERA_TOTAL_REWARDS_HELP)?;
+        registry.register(Box::new(last_era_total_rewards.clone()))?;
+
+        let validators_evicted = IntGauge::new(VALIDATORS_EVICTED_NAME, VALIDATORS_EVICTED_HELP)?;
+        registry.register(Box::new(validators_evicted.clone()))?;
+
+        let validators_rewarded =
+            IntGauge::new(VALIDATORS_REWARDED_NAME, VALIDATORS_REWARDED_HELP)?;
+        registry.register(Box::new(validators_rewarded.clone()))?;
+
+        let slowest_deploy_in_block =
+            Gauge::new(SLOWEST_DEPLOY_IN_BLOCK_NAME, SLOWEST_DEPLOY_IN_BLOCK_HELP)?;
+        registry.register(Box::new(slowest_deploy_in_block.clone()))?;
+
+        let run_execute = HistogramVec::new(
+            HistogramOpts::new(RUN_EXECUTE_NAME, RUN_EXECUTE_HELP).buckets(common_buckets.clone()),
+            &[RUN_EXECUTE_LABEL],
+        )?;
+        registry.register(Box::new(run_execute.clone()))?;
+
         let exec_queue_size = IntGauge::new(EXEC_QUEUE_SIZE_NAME, EXEC_QUEUE_SIZE_HELP)?;
         registry.register(Box::new(exec_queue_size.clone()))?;
 
+        let exec_block_gas_limit_exceeded = IntCounter::new(
+            EXEC_BLOCK_GAS_LIMIT_EXCEEDED_NAME,
+            EXEC_BLOCK_GAS_LIMIT_EXCEEDED_HELP,
+        )?;
+        registry.register(Box::new(exec_block_gas_limit_exceeded.clone()))?;
+
+        let deploys_executed_total =
+            IntCounter::new(DEPLOYS_EXECUTED_TOTAL_NAME, DEPLOYS_EXECUTED_TOTAL_HELP)?;
+        registry.register(Box::new(deploys_executed_total.clone()))?;
+
+        let deploys_succeeded_total =
+            IntCounter::new(DEPLOYS_SUCCEEDED_TOTAL_NAME, DEPLOYS_SUCCEEDED_TOTAL_HELP)?;
+        registry.register(Box::new(deploys_succeeded_total.clone()))?;
+
+        let deploys_failed_total =
+            IntCounter::new(DEPLOYS_FAILED_TOTAL_NAME, DEPLOYS_FAILED_TOTAL_HELP)?;
+        registry.register(Box::new(deploys_failed_total.clone()))?;
+
+        let total_gas_consumed = Counter::new(TOTAL_GAS_CONSUMED_NAME, TOTAL_GAS_CONSUMED_HELP)?;
+        registry.register(Box::new(total_gas_consumed.clone()))?;
+
+        let execution_result_cache_hits = IntCounter::new(
+            EXECUTION_RESULT_CACHE_HITS_NAME,
+            EXECUTION_RESULT_CACHE_HITS_HELP,
+        )?;
+        registry.register(Box::new(execution_result_cache_hits.clone()))?;
+
+        let era_validators_cache_hits = IntCounter::new(
+            ERA_VALIDATORS_CACHE_HITS_NAME,
+            ERA_VALIDATORS_CACHE_HITS_HELP,
+        )?;
+        registry.register(Box::new(era_validators_cache_hits.clone()))?;
+
+        let read_through_cache_hits =
+            IntCounter::new(READ_THROUGH_CACHE_HITS_NAME, READ_THROUGH_CACHE_HITS_HELP)?;
+        registry.register(Box::new(read_through_cache_hits.clone()))?;
+
+        let scratch_state_transform_count = IntGauge::new(
+            SCRATCH_STATE_TRANSFORM_COUNT_NAME,
+            SCRATCH_STATE_TRANSFORM_COUNT_HELP,
+        )?;
+        registry.register(Box::new(scratch_state_transform_count.clone()))?;
+
         Ok(Metrics {
-            run_execute: utils::register_histogram_metric(
-                registry,
-                RUN_EXECUTE_NAME,
-                RUN_EXECUTE_HELP,
-                common_buckets.clone(),
-            )?,
+            run_execute,
             apply_effect: utils::register_histogram_metric(
                 registry,
                 APPLY_EFFECT_NAME,
@@ -160,13 +391,91 @@ impl Metrics {
                 registry,
                 EXEC_BLOCK_NAME,
                 EXEC_BLOCK_HELP,
+                common_buckets.clone(),
+            )?,
+            exec_block_switch: utils::register_histogram_metric(
+                registry,
+                EXEC_BLOCK_SWITCH_NAME,
+                EXEC_BLOCK_SWITCH_HELP,
+                common_buckets.clone(),
+            )?,
+            exec_block_normal: utils::register_histogram_metric(
+                registry,
+                EXEC_BLOCK_NORMAL_NAME,
+                EXEC_BLOCK_NORMAL_HELP,
+                common_buckets.clone(),
+            )?,
+            deploys_per_block: utils::register_histogram_metric(
+                registry,
+                DEPLOYS_PER_BLOCK_NAME,
+                DEPLOYS_PER_BLOCK_HELP,
+                deploy_count_buckets,
+            )?,
+            gas_per_block: utils::register_histogram_metric(
+                registry,
+                GAS_PER_BLOCK_NAME,
+                GAS_PER_BLOCK_HELP,
+                gas_buckets,
+            )?,
+            trie_reads_per_block: utils::register_histogram_metric(
+                registry,
+                TRIE_READS_PER_BLOCK_NAME,
+                TRIE_READS_PER_BLOCK_HELP,
+                trie_count_buckets.clone(),
+            )?,
+            trie_writes_per_block: utils::register_histogram_metric(
+                registry,
+                TRIE_WRITES_PER_BLOCK_NAME,
+                TRIE_WRITES_PER_BLOCK_HELP,
+                trie_count_buckets,
+            )?,
+            checksum_registry_proof_size_bytes: utils::register_histogram_metric(
+                registry,
+                CHECKSUM_REGISTRY_PROOF_SIZE_NAME,
+                CHECKSUM_REGISTRY_PROOF_SIZE_HELP,
+                proof_size_buckets,
+            )?,
+            exec_block_gas_limit_exceeded,
+            deploys_executed_total,
+            deploys_succeeded_total,
+            deploys_failed_total,
+            total_gas_consumed,
+            execution_result_cache_hits,
+            era_validators_cache_hits,
+            read_through_cache_hits,
+            scratch_state_transform_count,
+            scratch_write_to_db: utils::register_histogram_metric(
+                registry,
+                SCRATCH_WRITE_TO_DB_NAME,
+                SCRATCH_WRITE_TO_DB_HELP,
+                common_buckets.clone(),
+            )?,
+            flush_environment: utils::register_histogram_metric(
+                registry,
+                FLUSH_ENVIRONMENT_NAME,
+                FLUSH_ENVIRONMENT_HELP,
+                common_buckets.clone(),
+            )?,
+            block_assembly: utils::register_histogram_metric(
+                registry,
+                BLOCK_ASSEMBLY_NAME,
+                BLOCK_ASSEMBLY_HELP,
                 common_buckets,
             )?,
             latest_commit_step,
+            last_era_total_rewards,
+            validators_evicted,
+            validators_rewarded,
+            slowest_deploy_in_block,
             exec_queue_size,
             registry: registry.clone(),
         })
     }
+
+    /// Returns the histogram of the time to execute deploys for the given purpose.
+    pub(super) fn run_execute(&self, kind: ExecutionKind) -> Histogram {
+        self.run_execute.with_label_values(&[kind.label()])
+    }
 }
 
 impl Drop for Metrics {
@@ -182,7 +491,84 @@ impl Drop for Metrics {
         unregister_metric!(self.registry, self.put_trie);
         unregister_metric!(self.registry, self.get_trie);
         unregister_metric!(self.registry, self.exec_block);
+        unregister_metric!(self.registry, self.exec_block_switch);
+        unregister_metric!(self.registry, self.exec_block_normal);
+        unregister_metric!(self.registry, self.deploys_per_block);
+        unregister_metric!(self.registry, self.gas_per_block);
+        unregister_metric!(self.registry, self.trie_reads_per_block);
+        unregister_metric!(self.registry, self.trie_writes_per_block);
+        unregister_metric!(self.registry, self.checksum_registry_proof_size_bytes);
+        unregister_metric!(self.registry, self.exec_block_gas_limit_exceeded);
+        unregister_metric!(self.registry, self.deploys_executed_total);
+        unregister_metric!(self.registry, self.deploys_succeeded_total);
+        unregister_metric!(self.registry, self.deploys_failed_total);
+        unregister_metric!(self.registry, self.total_gas_consumed);
+        unregister_metric!(self.registry, self.execution_result_cache_hits);
+        unregister_metric!(self.registry, self.era_validators_cache_hits);
+        unregister_metric!(self.registry, self.read_through_cache_hits);
+        unregister_metric!(self.registry, self.scratch_state_transform_count);
+        unregister_metric!(self.registry, self.scratch_write_to_db);
+        unregister_metric!(self.registry, self.flush_environment);
+        unregister_metric!(self.registry, self.block_assembly);
         unregister_metric!(self.registry, self.latest_commit_step);
+        unregister_metric!(self.registry, self.last_era_total_rewards);
+        unregister_metric!(self.registry, self.validators_evicted);
+        unregister_metric!(self.registry, self.validators_rewarded);
+        unregister_metric!(self.registry, self.slowest_deploy_in_block);
         unregister_metric!(self.registry, self.exec_queue_size);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_register_and_unregister_block_execution_phase_metrics() {
+        let registry = Registry::new();
+        let registered_names = |registry: &Registry| {
+            registry
+                .gather()
+                .into_iter()
+                .map(|metric_family| metric_family.get_name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let metrics = Metrics::new(&registry).unwrap();
+        metrics.scratch_write_to_db.observe(1.0);
+        metrics.flush_environment.observe(1.0);
+        metrics.deploys_executed_total.inc();
+        let names = registered_names(&registry);
+        for name in [
+            SCRATCH_WRITE_TO_DB_NAME,
+            FLUSH_ENVIRONMENT_NAME,
+            DEPLOYS_EXECUTED_TOTAL_NAME,
+        ] {
+            assert!(names.iter().any(|registered| registered == name));
+        }
+
+        drop(metrics);
+        assert!(registered_names(&registry).is_empty());
+    }
+
+    #[test]
+    fn should_observe_deploys_and_gas_per_block() {
+        let registry = Registry::new();
+        let metrics = Metrics::new(&registry).unwrap();
+        metrics.deploys_per_block.observe(3.0);
+        metrics.gas_per_block.observe(300_000_000.0);
+
+        assert_eq!(metrics.deploys_per_block.get_sample_count(), 1);
+        assert_eq!(metrics.deploys_per_block.get_sample_sum() as u64, 3);
+        assert_eq!(metrics.gas_per_block.get_sample_count(), 1);
+        assert_eq!(metrics.gas_per_block.get_sample_sum() as u64, 300_000_000);
+        let names = registry
+            .gather()
+            .into_iter()
+            .map(|metric_family| metric_family.get_name().to_string())
+            .collect::<Vec<_>>();
+        for name in [DEPLOYS_PER_BLOCK_NAME, GAS_PER_BLOCK_NAME] {
+            assert!(names.iter().any(|registered| registered == name));
+        }
+    }
+}
@@ -1,39 +1,82 @@
-use std::{cmp, collections::BTreeMap, ops::Range, sync::Arc, time::Instant};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    cmp,
+    collections::{BTreeMap, BTreeSet, HashSet, VecDeque},
+    iter, mem,
+    num::NonZeroUsize,
+    ops::Range,
+    panic,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
 
-use itertools::Itertools;
-use tracing::{debug, error, info, trace, warn};
+use itertools::{Either, Itertools};
+use linked_hash_map::LinkedHashMap;
+use once_cell::sync::OnceCell;
+use tracing::{debug, error, field, info, info_span, trace, warn};
 
 use casper_execution_engine::{
     core::{
         engine_state::{
-            self, execution_result::ExecutionResults, step::EvictItem, ChecksumRegistry,
-            DeployItem, EngineState, ExecuteRequest, ExecutionResult as EngineExecutionResult,
-            GetEraValidatorsRequest, PruneConfig, PruneResult, RewardItem, StepError, StepRequest,
-            StepSuccess,
+            self,
+            execution_result::ExecutionResults,
+            step::{EvictItem, SlashItem},
+            ChecksumRegistry, DeployItem, EngineConfig, EngineState, ExecutableDeployItem,
+            ExecuteRequest, ExecutionResult as EngineExecutionResult, GetEraValidatorsError,
+            GetEraValidatorsRequest, PruneConfig, PruneResult, QueryRequest, QueryResult,
+            RewardItem, StepError, StepRequest, StepSuccess,
         },
         execution,
     },
-    shared::{additive_map::AdditiveMap, newtypes::CorrelationId, transform::Transform},
-    storage::global_state::{lmdb::LmdbGlobalState, CommitProvider, StateProvider},
+    shared::{
+        additive_map::AdditiveMap, execution_journal::ExecutionJournal, newtypes::CorrelationId,
+        transform::Transform,
+    },
+    storage::{
+        global_state::{
+            lmdb::LmdbGlobalState, scratch::ScratchGlobalState, CommitError, CommitProvider,
+            DeleteResult, StateProvider,
+        },
+        trie::{merkle_proof::TrieMerkleProof, TrieRaw},
+    },
 };
 use casper_hashing::Digest;
 use casper_types::{
-    CLValue, DeployHash, EraId, ExecutionResult, Key, ProtocolVersion, PublicKey, U512,
+    account::AccountHash,
+    bytesrepr::{Bytes, ToBytes},
+    runtime_args,
+    system::{auction::EraValidators, mint, MINT},
+    ApiError, CLValue, EraId, ExecutionResult, Gas, Key, ProtocolVersion, PublicKey, RuntimeArgs,
+    StoredValue, Timestamp, U512,
 };
 
 use crate::{
     components::{
         consensus::EraReport,
         contract_runtime::{
-            error::BlockExecutionError, types::StepEffectAndUpcomingEraValidators,
+            error::{
+                ApprovalsError, BlockExecutionError, InvalidSpeculativeDeploy, SelfCheckError,
+                SpeculativeExecutionError,
+            },
+            footprint::DeployFootprint,
+            metrics::ExecutionKind,
+            types::{
+                execution_results_checksum_scheme, summarize_step_journal, BatchExecuteRequest,
+                BlockChecksums, BlockExecutionOptions, CapturedJournal, CommitMode,
+                DeployExecutionInfo, DeployOutcome, EraValidatorsCache, ExecutionCheckpoint,
+                ExecutionResultsChecksum, MinimumReward, RewardStrategy, StateRootCheckpoint,
+                StepEffectAndUpcomingEraValidators, StepOutcomeSummary,
+            },
             BlockAndExecutionResults, ExecutionPreState, Metrics, SpeculativeExecutionState,
             APPROVALS_CHECKSUM_NAME, EXECUTION_RESULTS_CHECKSUM_NAME,
         },
         fetcher::FetchItem,
     },
     types::{
-        self, error::BlockCreationError, ApprovalsHashes, Block, Chunkable, Deploy, DeployHeader,
-        FinalizedBlock,
+        self, error::BlockCreationError, ApprovalsHashes, Block, BlockHash, Deploy, DeployHash,
+        DeployHeader, DeployId, FinalizedBlock, VecChecksumBuilder,
     },
 };
 
@@ -87,7 +130,34 @@ fn calculate_prune_eras(
     Some(range.map(EraId::new).map(Key::EraInfo).collect())
 }
 
+/// The protocol version from which blocks without deploys leave the checksum registry of their
+/// parent in place, so that their state root hash only changes if they run the step.
+pub(crate) const SKIP_EMPTY_BLOCK_CHECKSUM_REGISTRY_PROTOCOL_VERSION: ProtocolVersion =
+    ProtocolVersion::from_parts(2, 0, 0);
+
+/// The protocol version from which the era end timestamp passed to the step may differ from the
+/// timestamp of the switch block.
+const ERA_END_TIMESTAMP_OVERRIDE_PROTOCOL_VERSION: ProtocolVersion =
+    ProtocolVersion::from_parts(2, 0, 0);
+
+/// The protocol version from which rewards below the [`MinimumReward`] are carried over to the next
+/// era rather than paid.
+const MINIMUM_REWARD_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::from_parts(2, 0, 0);
+
+/// The protocol version from which the era report may be transformed before running the step.
+const ERA_REPORT_TRANSFORM_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::from_parts(2, 0, 0);
+
+/// The protocol version from which a switch block may end more than one era, e.g. when catching up
+/// on an experimental chain.
+const MULTI_ERA_SWITCH_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::from_parts(2, 0, 0);
+
 /// Executes a finalized block.
+///
+/// All events emitted during execution are grouped under an `execute_block` span, which records
+/// the resulting state root hash and the elapsed time on exit. A single correlation id, recorded
+/// by the span, is passed to all execution engine calls made for the block.
+///
+/// How the block is executed is configured by `options`, see [`BlockExecutionOptions`].
 #[allow(clippy::too_many_arguments)]
 pub fn execute_finalized_block(
     engine_state: &EngineState<LmdbGlobalState>,
@@ -99,138 +169,1329 @@ pub fn execute_finalized_block(
     activation_point_era_id: EraId,
     key_block_height_for_activation_point: u64,
     prune_batch_size: u64,
+    options: BlockExecutionOptions<'_>,
+) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+    execute_in_block_span(&finalized_block, deploys.len(), |correlation_id| {
+        execute_or_simulate_finalized_block(
+            engine_state,
+            correlation_id,
+            metrics,
+            protocol_version,
+            execution_pre_state,
+            finalized_block,
+            deploys.iter().map(Deploy::fetch_id).collect(),
+            deploys.into_iter(),
+            activation_point_era_id,
+            key_block_height_for_activation_point,
+            prune_batch_size,
+            options,
+            None,
+            false,
+        )
+        .map(BlockExecution::into_executed_block)
+        .and_then(|executed_block| executed_block.assemble(engine_state, correlation_id))
+    })
+}
+
+/// Executes a finalized block as [`execute_finalized_block`] does, on tokio's blocking thread pool
+/// so as not to stall the async task calling it.
+///
+/// As the hooks of [`BlockExecutionOptions`] need not be sendable to another thread, the options
+/// are created by calling `options` on the blocking thread. A panic while executing the block is
+/// resumed in the calling task.
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_finalized_block_async(
+    engine_state: Arc<EngineState<LmdbGlobalState>>,
+    metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    execution_pre_state: ExecutionPreState,
+    finalized_block: FinalizedBlock,
+    deploys: Vec<Deploy>,
+    activation_point_era_id: EraId,
+    key_block_height_for_activation_point: u64,
+    prune_batch_size: u64,
+    options: impl FnOnce() -> BlockExecutionOptions<'static> + Send + 'static,
+) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+    let task = tokio::task::spawn_blocking(move || {
+        execute_finalized_block(
+            engine_state.as_ref(),
+            metrics,
+            protocol_version,
+            execution_pre_state,
+            finalized_block,
+            deploys,
+            activation_point_era_id,
+            key_block_height_for_activation_point,
+            prune_batch_size,
+            options(),
+        )
+    });
+    match task.await {
+        Ok(result) => result,
+        Err(error) => match error.try_into_panic() {
+            Ok(payload) => panic::resume_unwind(payload),
+            // Blocking tasks are only cancelled when the runtime shuts down.
+            Err(error) => panic!("block execution task cancelled: {}", error),
+        },
+    }
+}
+
+/// Executes a finalized block as [`execute_finalized_block`] does, but passes the result of each
+/// deploy to `on_deploy_result` as soon as it is committed rather than collecting them.
+///
+/// The results are passed in execution order, and the returned `BlockAndExecutionResults` holds
+/// none of them. Their serialized form is still retained until the execution results checksum of
+/// the block is computed, as the checksum covers all of them. As checkpoints retain the results of
+/// the deploys executed so far, giving `options.checkpointing` is a
+/// [`CheckpointingWhileStreaming`](BlockExecutionError::CheckpointingWhileStreaming) error.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_finalized_block_streaming(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    execution_pre_state: ExecutionPreState,
+    finalized_block: FinalizedBlock,
+    deploys: Vec<Deploy>,
+    activation_point_era_id: EraId,
+    key_block_height_for_activation_point: u64,
+    prune_batch_size: u64,
+    options: BlockExecutionOptions<'_>,
+    on_deploy_result: &mut dyn FnMut(DeployExecutionInfo),
 ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+    execute_in_block_span(&finalized_block, deploys.len(), |correlation_id| {
+        execute_or_simulate_finalized_block(
+            engine_state,
+            correlation_id,
+            metrics,
+            protocol_version,
+            execution_pre_state,
+            finalized_block,
+            deploys.iter().map(Deploy::fetch_id).collect(),
+            deploys.into_iter(),
+            activation_point_era_id,
+            key_block_height_for_activation_point,
+            prune_batch_size,
+            options,
+            Some(on_deploy_result),
+            false,
+        )
+        .map(BlockExecution::into_executed_block)
+        .and_then(|executed_block| executed_block.assemble(engine_state, correlation_id))
+    })
+}
+
+/// Runs the validations of a finalized block which do not require executing it, as done by
+/// [`execute_finalized_block`] before executing any deploy.
+///
+/// Returns an error if the block's height does not follow the execution pre-state, if
+/// `expected_parent_hash` is given and differs from the parent hash of the execution pre-state, or
+/// if `deploys` includes the same deploy more than once.
+pub fn pre_execution_checks(
+    finalized_block: &FinalizedBlock,
+    execution_pre_state: &ExecutionPreState,
+    deploys: &[Deploy],
+    expected_parent_hash: Option<BlockHash>,
+) -> Result<(), BlockExecutionError> {
+    check_before_execution(
+        finalized_block,
+        execution_pre_state,
+        deploys.iter().map(Deploy::hash),
+        expected_parent_hash,
+    )
+}
+
+/// Verifies that `approvals_hashes` are those of `deploys`, which must be the deploys and
+/// transfers of `block` in the block's order, independently of executing the block.
+///
+/// Checks that the approvals hashes are bound to the block, that the approvals hash computed from
+/// each deploy's approvals matches the one given for it, and that the proof of the checksum
+/// registry included with them holds the checksum of the approvals hashes at the block's state
+/// root hash.
+pub(crate) fn verify_approvals_hashes(
+    block: &Block,
+    approvals_hashes: &ApprovalsHashes,
+    deploys: &[Deploy],
+) -> Result<(), ApprovalsError> {
+    if approvals_hashes.block_hash() != block.hash() {
+        return Err(ApprovalsError::WrongBlockHash {
+            expected: *block.hash(),
+            actual: *approvals_hashes.block_hash(),
+        });
+    }
+    let block_deploy_count = block.deploy_and_transfer_hashes().count();
+    if approvals_hashes.approvals_hashes().len() != block_deploy_count
+        || deploys.len() != block_deploy_count
+    {
+        return Err(ApprovalsError::CountMismatch {
+            block_deploy_count,
+            approvals_hash_count: approvals_hashes.approvals_hashes().len(),
+            deploy_count: deploys.len(),
+        });
+    }
+    for (index, ((expected, given), deploy)) in block
+        .deploy_and_transfer_hashes()
+        .zip(approvals_hashes.approvals_hashes())
+        .zip(deploys)
+        .enumerate()
+    {
+        let deploy_hash = *deploy.hash();
+        if deploy_hash != *expected {
+            return Err(ApprovalsError::WrongDeploy {
+                index,
+                expected: *expected,
+                actual: deploy_hash,
+            });
+        }
+        let computed = deploy
+            .approvals_hash()
+            .map_err(|error| ApprovalsError::ApprovalsHash { deploy_hash, error })?;
+        if computed != *given {
+            return Err(ApprovalsError::ApprovalsHashMismatch {
+                deploy_hash,
+                computed,
+                given: *given,
+            });
+        }
+    }
+    approvals_hashes.validate(block)?;
+    Ok(())
+}
+
+/// Runs the checks of [`pre_execution_checks`] given the hashes of the deploys.
+fn check_before_execution<'a>(
+    finalized_block: &FinalizedBlock,
+    execution_pre_state: &ExecutionPreState,
+    deploy_hashes: impl ExactSizeIterator<Item = &'a DeployHash>,
+    expected_parent_hash: Option<BlockHash>,
+) -> Result<(), BlockExecutionError> {
     if finalized_block.height() != execution_pre_state.next_block_height {
         return Err(BlockExecutionError::WrongBlockHeight {
-            finalized_block: Box::new(finalized_block),
-            execution_pre_state: Box::new(execution_pre_state),
+            finalized_block: Box::new(finalized_block.clone()),
+            execution_pre_state: Box::new(execution_pre_state.clone()),
+        });
+    }
+    if let Some(expected_parent_hash) = expected_parent_hash {
+        if expected_parent_hash != execution_pre_state.parent_hash {
+            return Err(BlockExecutionError::WrongParentHash {
+                expected: expected_parent_hash,
+                actual: execution_pre_state.parent_hash,
+            });
+        }
+    }
+    let mut seen_deploy_hashes = HashSet::with_capacity(deploy_hashes.len());
+    for deploy_hash in deploy_hashes {
+        if !seen_deploy_hashes.insert(*deploy_hash) {
+            return Err(BlockExecutionError::DuplicateDeploy {
+                deploy_hash: *deploy_hash,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Executes a finalized block as [`execute_finalized_block`] does, but pulls its deploys from
+/// `deploys` one at a time rather than taking them all up front, e.g. to read them from storage as
+/// they are executed.
+///
+/// The deploys must be yielded in the order of the block, and are traversed twice: once to compute
+/// the approvals checksum and check for duplicates before executing any of them, then again to
+/// execute them. With `parallel_deploy_execution`, every deploy is loaded into memory at once to
+/// find the ones which can be executed concurrently, as it is with a `deploy_ordering` to sort
+/// them.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_finalized_block_from_iter<I>(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    execution_pre_state: ExecutionPreState,
+    finalized_block: FinalizedBlock,
+    deploys: I,
+    activation_point_era_id: EraId,
+    key_block_height_for_activation_point: u64,
+    prune_batch_size: u64,
+    options: BlockExecutionOptions<'_>,
+) -> Result<BlockAndExecutionResults, BlockExecutionError>
+where
+    I: IntoIterator<Item = Deploy>,
+    I::IntoIter: Clone,
+{
+    let deploys = deploys.into_iter();
+    let deploy_ids = deploys
+        .clone()
+        .map(|deploy| deploy.fetch_id())
+        .collect_vec();
+    execute_in_block_span(&finalized_block, deploy_ids.len(), |correlation_id| {
+        execute_or_simulate_finalized_block(
+            engine_state,
+            correlation_id,
+            metrics,
+            protocol_version,
+            execution_pre_state,
+            finalized_block,
+            deploy_ids,
+            deploys,
+            activation_point_era_id,
+            key_block_height_for_activation_point,
+            prune_batch_size,
+            options,
+            None,
+            false,
+        )
+        .map(BlockExecution::into_executed_block)
+        .and_then(|executed_block| executed_block.assemble(engine_state, correlation_id))
+    })
+}
+
+/// Executes a finalized block as [`execute_finalized_block`] does, writing the resulting global
+/// state to LMDB unless the commit mode of `options` forbids it, but only returns the state root
+/// hash of the block.
+///
+/// The block, its approvals hashes and the proof of the checksum registry are not assembled, e.g.
+/// to catch up on or validate blocks whose headers are already known.
+#[allow(clippy::too_many_arguments)]
+pub fn compute_state_root_for_block(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    execution_pre_state: ExecutionPreState,
+    finalized_block: FinalizedBlock,
+    deploys: Vec<Deploy>,
+    activation_point_era_id: EraId,
+    key_block_height_for_activation_point: u64,
+    prune_batch_size: u64,
+    options: BlockExecutionOptions<'_>,
+) -> Result<Digest, BlockExecutionError> {
+    let executed_block = execute_or_simulate_finalized_block(
+        engine_state,
+        CorrelationId::new(),
+        metrics,
+        protocol_version,
+        execution_pre_state,
+        finalized_block,
+        deploys.iter().map(Deploy::fetch_id).collect(),
+        deploys.into_iter(),
+        activation_point_era_id,
+        key_block_height_for_activation_point,
+        prune_batch_size,
+        options,
+        None,
+        false,
+    )
+    .map(BlockExecution::into_executed_block)?;
+    Ok(executed_block.state_root_hash)
+}
+
+/// Runs `execute` with a new correlation id within an `execute_block` span, recording the
+/// resulting state root hash and the elapsed time on exit.
+fn execute_in_block_span(
+    finalized_block: &FinalizedBlock,
+    deploy_count: usize,
+    execute: impl FnOnce(CorrelationId) -> Result<BlockAndExecutionResults, BlockExecutionError>,
+) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+    let correlation_id = CorrelationId::new();
+    let span = info_span!(
+        "execute_block",
+        %correlation_id,
+        height = finalized_block.height(),
+        era_id = finalized_block.era_id().value(),
+        deploy_count,
+        state_root_hash = field::Empty,
+        elapsed_ms = field::Empty,
+    );
+    let _entered = span.enter();
+    let start = Instant::now();
+    let result = execute(correlation_id);
+    if let Ok(block_and_execution_results) = result.as_ref() {
+        span.record(
+            "state_root_hash",
+            &field::display(block_and_execution_results.block.state_root_hash()),
+        );
+    }
+    span.record("elapsed_ms", start.elapsed().as_millis() as u64);
+    result
+}
+
+/// Executes a finalized block entirely in memory, without writing anything to LMDB.
+///
+/// The returned block carries the state root hash the block would have if it were executed by
+/// [`execute_finalized_block`] with the same `options`, but global state is left unchanged, as
+/// their commit mode is ignored. As pruning writes to LMDB, no era info is pruned.
+///
+/// The execution results checksum is computed as when committing, as the checksum registry is part
+/// of that state root hash. Use [`dry_run_finalized_block`] if only the results of the deploys are
+/// needed.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_finalized_block(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    execution_pre_state: ExecutionPreState,
+    finalized_block: FinalizedBlock,
+    deploys: Vec<Deploy>,
+    options: BlockExecutionOptions<'_>,
+) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+    let correlation_id = CorrelationId::new();
+    execute_or_simulate_finalized_block(
+        engine_state,
+        correlation_id,
+        metrics,
+        protocol_version,
+        execution_pre_state,
+        finalized_block,
+        deploys.iter().map(Deploy::fetch_id).collect(),
+        deploys.into_iter(),
+        // Pruning is skipped when simulating, so the activation point and batch size are unused.
+        EraId::new(0),
+        0,
+        0,
+        BlockExecutionOptions {
+            commit_mode: CommitMode::Forbidden,
+            ..options
+        },
+        None,
+        false,
+    )
+    .map(BlockExecution::into_executed_block)
+    .and_then(|executed_block| executed_block.assemble(engine_state, correlation_id))
+}
+
+/// Executes the deploys of a finalized block in memory, without writing anything to LMDB, and
+/// returns their execution results.
+///
+/// The deploys are executed as by [`simulate_finalized_block`] with the same `options`, but the
+/// checksum registry is not written and the step is not run, so neither the execution results
+/// checksum nor any state root hash is computed. Only the results are returned, without the effects
+/// captured for `capture_journals`.
+pub fn dry_run_finalized_block(
+    engine_state: &EngineState<LmdbGlobalState>,
+    protocol_version: ProtocolVersion,
+    execution_pre_state: ExecutionPreState,
+    finalized_block: FinalizedBlock,
+    deploys: Vec<Deploy>,
+    options: BlockExecutionOptions<'_>,
+) -> Result<Vec<DeployExecutionInfo>, BlockExecutionError> {
+    let block_execution = execute_or_simulate_finalized_block(
+        engine_state,
+        CorrelationId::new(),
+        None,
+        protocol_version,
+        execution_pre_state,
+        finalized_block,
+        deploys.iter().map(Deploy::fetch_id).collect(),
+        deploys.into_iter(),
+        // Nothing is pruned by a dry run, so the activation point and batch size are unused.
+        EraId::new(0),
+        0,
+        0,
+        options,
+        None,
+        true,
+    )?;
+    match block_execution {
+        BlockExecution::DryRun(execution_results) => Ok(execution_results),
+        BlockExecution::Block(_) => unreachable!("a dry run only executes the deploys"),
+    }
+}
+
+/// Executes a finalized block without writing to LMDB, and checks that it yields `expected_root`.
+///
+/// If the computed state root hash differs, a [`BlockExecutionError::RootMismatch`] is returned.
+/// Given the state root trace of a reference execution of the block, e.g. by a node which computed
+/// `expected_root`, the error identifies the first deploy after which the state roots diverge. It
+/// is `None` if no reference trace is given, or if the roots only diverge after the last deploy.
+///
+/// The block is simulated as by [`simulate_finalized_block`], so the commit mode of `options` is
+/// ignored, and its state roots are always traced.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_finalized_block(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    execution_pre_state: ExecutionPreState,
+    finalized_block: FinalizedBlock,
+    deploys: Vec<Deploy>,
+    options: BlockExecutionOptions<'_>,
+    expected_root: Digest,
+    reference_state_root_trace: Option<&[(StateRootCheckpoint, Digest)]>,
+) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+    let block_and_execution_results = simulate_finalized_block(
+        engine_state,
+        metrics,
+        protocol_version,
+        execution_pre_state,
+        finalized_block,
+        deploys,
+        BlockExecutionOptions {
+            trace_state_roots: true,
+            ..options
+        },
+    )?;
+    let actual = *block_and_execution_results.block.state_root_hash();
+    if actual == expected_root {
+        return Ok(block_and_execution_results);
+    }
+
+    let deploy_roots = |state_root_trace: &[(StateRootCheckpoint, Digest)]| {
+        state_root_trace
+            .iter()
+            .filter_map(|(checkpoint, root)| match checkpoint {
+                StateRootCheckpoint::Deploy(deploy_hash) => Some((*deploy_hash, *root)),
+                _ => None,
+            })
+            .collect_vec()
+    };
+    let first_divergent_deploy = reference_state_root_trace.and_then(|reference| {
+        let state_root_trace = block_and_execution_results
+            .maybe_state_root_trace
+            .as_deref()
+            .unwrap_or_default();
+        deploy_roots(state_root_trace)
+            .into_iter()
+            .zip(deploy_roots(reference))
+            .find(|(computed, reference)| computed != reference)
+            .map(|((deploy_hash, _), _)| deploy_hash)
+    });
+    Err(BlockExecutionError::RootMismatch {
+        expected: expected_root,
+        actual,
+        first_divergent_deploy,
+    })
+}
+
+/// Executes a finalized block as [`execute_finalized_block`] does, but executes it twice to check
+/// that its execution is deterministic.
+///
+/// The block is first executed entirely in memory, and then again in a fresh scratch state whose
+/// changes are written to LMDB. If the state roots recorded after each deploy and the step, or the
+/// execution results, of the two runs differ, a [`BlockExecutionError::NondeterministicExecution`]
+/// is returned. The state written by the second run is then not referenced by any block.
+///
+/// As every block is executed twice, this is only meant for testing and for debugging suspected
+/// nondeterminism of the execution engine. `options` is called to create the options of each run,
+/// with state roots always traced. The first run is always simulated, while the second follows the
+/// commit mode of the options. Only the second run is recorded in `metrics`.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_finalized_block_checked<'a>(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    execution_pre_state: ExecutionPreState,
+    finalized_block: FinalizedBlock,
+    deploys: Vec<Deploy>,
+    activation_point_era_id: EraId,
+    key_block_height_for_activation_point: u64,
+    prune_batch_size: u64,
+    options: &dyn Fn() -> BlockExecutionOptions<'a>,
+) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+    let deploy_ids: Vec<DeployId> = deploys.iter().map(Deploy::fetch_id).collect();
+    execute_in_block_span(&finalized_block, deploys.len(), |correlation_id| {
+        let execute = |metrics: Option<Arc<Metrics>>,
+                       deploys: Vec<Deploy>,
+                       options: BlockExecutionOptions<'a>| {
+            execute_or_simulate_finalized_block(
+                engine_state,
+                correlation_id,
+                metrics,
+                protocol_version,
+                execution_pre_state.clone(),
+                finalized_block.clone(),
+                deploy_ids.clone(),
+                deploys.into_iter(),
+                activation_point_era_id,
+                key_block_height_for_activation_point,
+                prune_batch_size,
+                BlockExecutionOptions {
+                    trace_state_roots: true,
+                    ..options
+                },
+                None,
+                false,
+            )
+            .map(BlockExecution::into_executed_block)
+        };
+        let first_options = BlockExecutionOptions {
+            commit_mode: CommitMode::Forbidden,
+            ..options()
+        };
+        let first_run = execute(None, deploys.clone(), first_options)?;
+        let second_run = execute(metrics, deploys, options())?;
+
+        // Pruning is skipped when simulating, so the roots after pruning are not compared.
+        let state_roots = |executed_block: &ExecutedBlock| {
+            executed_block
+                .maybe_state_root_trace
+                .iter()
+                .flatten()
+                .filter(|(checkpoint, _)| *checkpoint != StateRootCheckpoint::Prune)
+                .cloned()
+                .collect_vec()
+        };
+        let same_results = first_run
+            .execution_results
+            .iter()
+            .map(|deploy_execution_info| &deploy_execution_info.result)
+            .eq(second_run
+                .execution_results
+                .iter()
+                .map(|deploy_execution_info| &deploy_execution_info.result));
+        let first_run_roots = state_roots(&first_run);
+        let second_run_roots = state_roots(&second_run);
+        if first_run_roots != second_run_roots || !same_results {
+            let last_root = |roots: &[(StateRootCheckpoint, Digest)]| {
+                roots
+                    .last()
+                    .map_or(execution_pre_state.pre_state_root_hash, |(_, root)| *root)
+            };
+            return Err(BlockExecutionError::NondeterministicExecution {
+                first_run: last_root(&first_run_roots),
+                second_run: last_root(&second_run_roots),
+            });
+        }
+        second_run.assemble(engine_state, correlation_id)
+    })
+}
+
+/// Executes a finalized block, writing the resulting global state to LMDB unless the commit mode of
+/// `options` forbids it. With `dry_run`, only the deploys of the block are executed and nothing is
+/// written to LMDB.
+///
+/// The block and its approvals hashes are not assembled, see [`ExecutedBlock::assemble`].
+#[allow(clippy::too_many_arguments)]
+fn execute_or_simulate_finalized_block(
+    engine_state: &EngineState<LmdbGlobalState>,
+    correlation_id: CorrelationId,
+    metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    execution_pre_state: ExecutionPreState,
+    finalized_block: FinalizedBlock,
+    deploy_ids: Vec<DeployId>,
+    deploys: impl Iterator<Item = Deploy>,
+    activation_point_era_id: EraId,
+    key_block_height_for_activation_point: u64,
+    prune_batch_size: u64,
+    options: BlockExecutionOptions<'_>,
+    mut on_deploy_result: Option<&mut dyn FnMut(DeployExecutionInfo)>,
+    dry_run: bool,
+) -> Result<BlockExecution, BlockExecutionError> {
+    let BlockExecutionOptions {
+        slash_equivocators,
+        block_gas_limit,
+        trace_state_roots,
+        parallel_deploy_execution,
+        validate_proposer,
+        reward_strategy,
+        on_era_transition,
+        mut checkpointing,
+        era_end_timestamp,
+        batched_flushes,
+        expected_parent_hash,
+        effect_observer,
+        scratch_state,
+        verify_roundtrip,
+        minimum_reward,
+        execution_result_cache,
+        proposer_override,
+        strict,
+        era_report_transform,
+        require_state_progress,
+        max_deploys_per_block,
+        capture_journals,
+        deploy_ordering,
+        max_trie_depth,
+        era_validators_cache,
+        seed_override,
+        flush_retries,
+        refund_destination,
+        max_execution_result_bytes,
+        validator_floor,
+        on_validator_floor_breached,
+        read_through_cache,
+        additional_era_reports,
+        deploy_filter,
+        yield_hook,
+        commit_mode,
+        checksum_scheme,
+    } = options;
+    if seed_override.is_some() && !cfg!(any(feature = "testing", test)) {
+        return Err(BlockExecutionError::SeedOverrideNotAllowed);
+    }
+    if !additional_era_reports.is_empty()
+        && (finalized_block.era_report().is_none()
+            || protocol_version < MULTI_ERA_SWITCH_PROTOCOL_VERSION)
+    {
+        return Err(BlockExecutionError::UnexpectedEraReports {
+            count: additional_era_reports.len(),
+            block_height: finalized_block.height(),
         });
     }
+    // The era following the last one ended by the block, if it is a switch block.
+    let next_era_id = finalized_block.era_id() + 1 + additional_era_reports.len() as u64;
+    // Checkpoints retain the results of the deploys executed so far.
+    if checkpointing.is_some() && on_deploy_result.is_some() {
+        return Err(BlockExecutionError::CheckpointingWhileStreaming);
+    }
+    if checksum_scheme.is_some() && !cfg!(any(feature = "testing", test)) {
+        return Err(BlockExecutionError::ChecksumOverrideNotAllowed);
+    }
+    let simulate = dry_run || commit_mode == CommitMode::Forbidden;
+    // Checkpoints are written to LMDB, before the trie depth of the block's effects is checked.
+    if simulate || max_trie_depth.is_some() {
+        checkpointing = None;
+    }
+    if let Some(max) = max_deploys_per_block {
+        if deploy_ids.len() > max {
+            return Err(BlockExecutionError::TooManyDeploys {
+                count: deploy_ids.len(),
+                max,
+            });
+        }
+    }
+    check_before_execution(
+        &finalized_block,
+        &execution_pre_state,
+        deploy_ids.iter().map(DeployId::deploy_hash),
+        expected_parent_hash,
+    )?;
     let ExecutionPreState {
         pre_state_root_hash,
         parent_hash,
         parent_seed,
         next_block_height: _,
     } = execution_pre_state;
+    if engine_state.tracking_copy(pre_state_root_hash)?.is_none() {
+        return Err(BlockExecutionError::PrunedStateRoot {
+            state_root_hash: pre_state_root_hash,
+        });
+    }
+    check_protocol_version(
+        engine_state,
+        correlation_id,
+        protocol_version,
+        pre_state_root_hash,
+    )?;
     let mut state_root_hash = pre_state_root_hash;
-    let mut execution_results: Vec<(_, DeployHeader, ExecutionResult)> =
-        Vec::with_capacity(deploys.len());
+    let deploy_count = deploy_ids.len();
+    let mut execution_results: Vec<DeployExecutionInfo> = Vec::with_capacity(deploy_count);
+    let mut captured_journals = capture_journals.then(|| Vec::with_capacity(deploy_count));
+    let mut maybe_state_root_trace = trace_state_roots.then(Vec::new);
+    // The written keys are needed both to check the trie depth and to advance the read-through
+    // cache to the post-state root hash.
+    let mut maybe_written_keys =
+        (max_trie_depth.is_some() || read_through_cache.is_some()).then(BTreeSet::new);
+    let mut block_gas_consumed = Gas::default();
+    let mut slowest_deploy = Duration::ZERO;
+    let mut executed_deploy_count = 0;
+    let mut streamed_results_checksum = VecChecksumBuilder::new();
     // Run any deploys that must be executed
     let block_time = finalized_block.timestamp().millis();
     let start = Instant::now();
-    let deploy_ids = deploys.iter().map(|deploy| deploy.fetch_id()).collect_vec();
-    let approvals_checksum = types::compute_approvals_checksum(deploy_ids.clone())
-        .map_err(BlockCreationError::BytesRepr)?;
-
-    // Create a new EngineState that reads from LMDB but only caches changes in memory.
-    let scratch_state = engine_state.get_scratch_engine_state();
-
-    // WARNING: Do not change the order of `deploys` as it will result in a different root hash.
-    for deploy in deploys {
-        let deploy_hash = *deploy.hash();
-        let deploy_header = deploy.header().clone();
-        let execute_request = ExecuteRequest::new(
-            state_root_hash,
-            block_time,
-            vec![DeployItem::from(deploy)],
+    let trie_store = engine_state.get_state().trie_store();
+    let (trie_reads_before, trie_writes_before) =
+        (trie_store.read_count(), trie_store.write_count());
+    if validate_proposer {
+        check_proposer(
+            engine_state,
+            correlation_id,
             protocol_version,
-            *finalized_block.proposer(),
-        );
-
-        // TODO: this is currently working coincidentally because we are passing only one
-        // deploy_item per exec. The execution results coming back from the EE lack the
-        // mapping between deploy_hash and execution result, and this outer logic is
-        // enriching it with the deploy hash. If we were passing multiple deploys per exec
-        // the relation between the deploy and the execution results would be lost.
-        let result = execute(&scratch_state, metrics.clone(), execute_request)?;
-
-        trace!(?deploy_hash, ?result, "deploy execution result");
-        // As for now a given state is expected to exist.
-        let (state_hash, execution_result) = commit_execution_results(
-            &scratch_state,
-            metrics.clone(),
-            state_root_hash,
-            deploy_hash.into(),
-            result,
+            pre_state_root_hash,
+            &finalized_block,
         )?;
-        execution_results.push((deploy_hash, deploy_header, execution_result));
-        state_root_hash = state_hash;
     }
+    let approvals_checksum = types::compute_approvals_checksum(deploy_ids.clone())
+        .map_err(BlockCreationError::BytesRepr)?;
 
-    // Write the deploy approvals and execution results Merkle root hashes to global state if there
-    // were any deploys.
-    let execution_results_checksum = compute_execution_results_checksum(
-        &execution_results
-            .iter()
-            .map(|(_, _, result)| result)
-            .cloned()
-            .collect(),
-    )?;
+    // Create a new EngineState that reads from LMDB but only caches changes in memory. Reads at the
+    // pre-state root hash go through the read-through cache, if given.
+    let cache_hits_before = read_through_cache.map(|read_through_cache| {
+        read_through_cache.checkout(pre_state_root_hash);
+        read_through_cache.hits()
+    });
+    let mut scratch_state = scratch_state.unwrap_or_else(|| match read_through_cache {
+        Some(read_through_cache) => engine_state
+            .get_scratch_engine_state_with_read_cache(Arc::new(read_through_cache.clone())),
+        None => engine_state.get_scratch_engine_state(),
+    });
+    // The state root hash the changes cached by the scratch state apply to.
+    let mut scratch_base_state_root_hash = pre_state_root_hash;
 
-    let mut effects = AdditiveMap::new();
-    let mut checksum_registry = ChecksumRegistry::new();
-    checksum_registry.insert(APPROVALS_CHECKSUM_NAME, approvals_checksum);
-    checksum_registry.insert(EXECUTION_RESULTS_CHECKSUM_NAME, execution_results_checksum);
-    let _ = effects.insert(
-        Key::ChecksumRegistry,
-        Transform::Write(
-            CLValue::from_t(checksum_registry)
-                .map_err(BlockCreationError::CLValue)?
-                .into(),
-        ),
-    );
-    scratch_state.apply_effect(CorrelationId::new(), state_root_hash, effects)?;
+    let deploys = match deploy_ordering {
+        Some(deploy_ordering) => {
+            let mut deploys = deploys.collect_vec();
+            deploy_ordering.order(&mut deploys);
+            Either::Left(deploys.into_iter())
+        }
+        None => Either::Right(deploys),
+    };
+    // The deploys vetoed by the deploy filter, recorded as they are taken from `deploys`.
+    let vetoed_deploys = RefCell::new(HashSet::new());
+    let deploys = deploys.inspect(|deploy| {
+        if deploy_filter.map_or(false, |deploy_filter| !deploy_filter(deploy)) {
+            let _ = vetoed_deploys.borrow_mut().insert(*deploy.hash());
+        }
+    });
+    // WARNING: Do not change the order of `deploys` as it will result in a different root hash. Any
+    // `DeployOrdering` has already been applied.
+    let mut pending_deploys = PendingDeploys::new(deploys);
 
-    if let Some(metrics) = metrics.as_ref() {
-        metrics.exec_block.observe(start.elapsed().as_secs_f64());
+    let maybe_resume_from = checkpointing
+        .as_mut()
+        .and_then(|checkpointing| checkpointing.resume_from.take());
+    if let Some(checkpoint) = maybe_resume_from {
+        // The deploys may be executed in a different order than that of the block, so the
+        // checkpoint is matched against the deploys actually skipped.
+        let is_valid = checkpoint.block_height == finalized_block.height()
+            && checkpoint.pre_state_root_hash == pre_state_root_hash
+            && checkpoint.deploy_index() <= deploy_count
+            && pending_deploys
+                .skip(checkpoint.deploy_index())
+                .iter()
+                .eq(checkpoint
+                    .execution_results
+                    .iter()
+                    .map(|deploy_execution_info| &deploy_execution_info.deploy_hash));
+        if !is_valid {
+            return Err(BlockExecutionError::InvalidCheckpoint {
+                block_height: finalized_block.height(),
+            });
+        }
+        debug!(
+            deploy_index = checkpoint.deploy_index(),
+            state_root_hash = %checkpoint.state_root_hash,
+            "resuming block execution from checkpoint"
+        );
+        executed_deploy_count = checkpoint.deploy_index();
+        block_gas_consumed = checkpoint
+            .execution_results
+            .iter()
+            .fold(Gas::default(), |gas, deploy_execution_info| {
+                gas + Gas::new(deploy_execution_info.cost)
+            });
+        state_root_hash = checkpoint.state_root_hash;
+        scratch_base_state_root_hash = checkpoint.state_root_hash;
+        execution_results = checkpoint.execution_results;
     }
-
-    // If the finalized block has an era report, run the auction contract and get the upcoming era
-    // validators.
-    let maybe_step_effect_and_upcoming_era_validators =
-        if let Some(era_report) = finalized_block.era_report() {
-            let StepSuccess {
-                post_state_hash: _, // ignore the post-state-hash returned from scratch
-                execution_journal: step_execution_journal,
-            } = commit_step(
-                &scratch_state, // engine_state
-                metrics,
+    // The keys written by the deploys skipped when resuming are unknown.
+    let resumed_from_checkpoint = executed_deploy_count > 0;
+    let mut last_checkpoint_deploy_count = executed_deploy_count;
+    let deploy_proposer = proposer_override.unwrap_or_else(|| *finalized_block.proposer());
+    let refund_account = refund_destination
+        .and_then(|refund_destination| refund_destination.refund_account(protocol_version));
+    // The scratch state root hash does not change as deploys are committed, so the global state a
+    // deploy is executed against is identified for the execution result cache by the pre-state
+    // root hash, the block's execution context and the deploys committed so far.
+    let mut maybe_cache_pre_state = match execution_result_cache {
+        Some(_) => {
+            let block_context = (
+                block_time,
+                &deploy_proposer,
                 protocol_version,
-                state_root_hash,
-                era_report,
-                finalized_block.timestamp().millis(),
-                finalized_block.era_id().successor(),
+                seed_override,
+                refund_account,
+            )
+                .to_bytes()
+                .map_err(BlockCreationError::BytesRepr)?;
+            Some(Digest::hash_pair(state_root_hash, block_context))
+        }
+        None => None,
+    };
+    let mut is_first_batch = true;
+    while !pending_deploys.is_empty() {
+        if let Some(yield_hook) = yield_hook.filter(|_| !is_first_batch) {
+            yield_hook();
+        }
+        is_first_batch = false;
+        // Deploys executed concurrently cannot be vetoed.
+        let executed_deploys = if parallel_deploy_execution && deploy_filter.is_none() {
+            let pending_deploys = pending_deploys.buffer_all();
+            let execute_start = Instant::now();
+            let results = execute_non_conflicting_deploys(
+                &scratch_state,
+                correlation_id,
+                metrics.clone(),
+                state_root_hash,
+                block_time,
+                protocol_version,
+                &deploy_proposer,
+                pending_deploys.iter().map(|(_, deploy_item)| deploy_item),
+                seed_override,
+                refund_account,
             )?;
+            slowest_deploy = cmp::max(slowest_deploy, execute_start.elapsed());
+            pending_deploys
+                .drain(..results.len())
+                .map(|(deploy_header, _)| deploy_header)
+                .zip(results)
+                .collect_vec()
+        } else {
+            let (deploy_header, deploy_item) = pending_deploys
+                .pop_front()
+                .expect("pending deploys should not be empty");
+            // NOTE: The EE executes every deploy in a single request against the same parent
+            // state, i.e. a deploy does not observe the effects of those preceding it in the
+            // request. Each deploy is therefore sent in its own request, on top of its
+            // predecessors' effects. Whether deploys of a request were independent cannot be told
+            // from their results either, as the EE discards what a failed session read.
+            let deploy_hash = DeployHash::from(deploy_item.deploy_hash);
+            if vetoed_deploys.borrow().contains(&deploy_hash) {
+                debug!(?deploy_hash, "deploy vetoed by deploy filter");
+                executed_deploy_count += 1;
+                if let Some(captured_journals) = captured_journals.as_mut() {
+                    captured_journals.push(CapturedJournal {
+                        deploy_hash,
+                        effects: AdditiveMap::new(),
+                        outcome: DeployOutcome::Vetoed,
+                    });
+                }
+                let deploy_execution_info =
+                    vetoed_deploy_execution_info(deploy_hash, deploy_header);
+                match on_deploy_result.as_mut() {
+                    Some(on_deploy_result) => {
+                        streamed_results_checksum
+                            .push(&deploy_execution_info.result)
+                            .map_err(BlockCreationError::BytesRepr)?;
+                        on_deploy_result(deploy_execution_info);
+                    }
+                    None => execution_results.push(deploy_execution_info),
+                }
+                continue;
+            }
+            // The deploy hash does not cover the approvals, which determine the authorization
+            // keys the deploy is executed with.
+            let maybe_cache_key = match maybe_cache_pre_state {
+                Some(cache_pre_state) => {
+                    let authorization_keys = deploy_item
+                        .authorization_keys
+                        .to_bytes()
+                        .map_err(BlockCreationError::BytesRepr)?;
+                    Some(Digest::hash_pair(cache_pre_state, authorization_keys))
+                }
+                None => None,
+            };
+            let maybe_cached_result = execution_result_cache
+                .zip(maybe_cache_key)
+                .and_then(|(cache, cache_key)| cache.get(deploy_hash, cache_key));
+            if let Some(ee_execution_result) = maybe_cached_result {
+                if let Some(metrics) = metrics.as_ref() {
+                    metrics.execution_result_cache_hits.inc();
+                }
+                vec![(deploy_header, (deploy_hash, ee_execution_result))]
+            } else {
+                let batch_execute_request = BatchExecuteRequest::new(
+                    state_root_hash,
+                    block_time,
+                    vec![deploy_item],
+                    protocol_version,
+                    deploy_proposer.clone(),
+                )
+                .with_address_seed(seed_override)
+                .with_refund_account(refund_account);
+                let execute_start = Instant::now();
+                let results = execute_batch(
+                    &scratch_state,
+                    correlation_id,
+                    metrics.clone(),
+                    batch_execute_request,
+                )?;
+                slowest_deploy = cmp::max(slowest_deploy, execute_start.elapsed());
+                if let (Some(cache), Some(cache_key)) = (execution_result_cache, maybe_cache_key) {
+                    for (deploy_hash, ee_execution_result) in &results {
+                        cache.insert(*deploy_hash, cache_key, ee_execution_result.clone());
+                    }
+                }
+                results
+                    .into_iter()
+                    .map(|result| (deploy_header.clone(), result))
+                    .collect_vec()
+            }
+        };
 
-            state_root_hash =
-                engine_state.write_scratch_to_db(state_root_hash, scratch_state.into_inner())?;
+        for (deploy_header, (deploy_hash, ee_execution_result)) in executed_deploys {
+            trace!(
+                ?deploy_hash,
+                ?ee_execution_result,
+                "deploy execution result"
+            );
+            let ee_execution_result = match ee_execution_result {
+                EngineExecutionResult::Failure { error, .. } if strict => {
+                    return Err(BlockExecutionError::DeployFailedInStrictMode {
+                        deploy_hash,
+                        error,
+                    });
+                }
+                ee_execution_result => ee_execution_result,
+            };
+            let cost = ee_execution_result.cost().value();
+            let maybe_effects = captured_journals
+                .is_some()
+                .then(|| AdditiveMap::from(ee_execution_result.execution_journal().clone()));
+            if let Some(written_keys) = maybe_written_keys.as_mut() {
+                written_keys.extend(
+                    ee_execution_result
+                        .execution_journal()
+                        .iter()
+                        .filter(|(_, transform)| *transform != Transform::Identity)
+                        .map(|(key, _)| *key),
+                );
+            }
+            // As for now a given state is expected to exist.
+            let (state_hash, result, outcome) = commit_execution_result(
+                &scratch_state,
+                correlation_id,
+                metrics.clone(),
+                state_root_hash,
+                deploy_hash,
+                ee_execution_result,
+                effect_observer,
+            )?;
+            block_gas_consumed += Gas::new(cost);
+            if let Some(metrics) = metrics.as_ref() {
+                metrics.deploys_executed_total.inc();
+            }
+            executed_deploy_count += 1;
+            if let (Some(captured_journals), Some(effects)) =
+                (captured_journals.as_mut(), maybe_effects)
+            {
+                captured_journals.push(CapturedJournal {
+                    deploy_hash,
+                    effects,
+                    outcome: outcome.clone(),
+                });
+            }
+            if let Some(max) = max_execution_result_bytes {
+                let size = result.serialized_length();
+                if size > max {
+                    return Err(BlockExecutionError::ExecutionResultTooLarge { deploy_hash, size });
+                }
+            }
+            let deploy_execution_info = DeployExecutionInfo {
+                deploy_hash,
+                header: deploy_header,
+                effect_count: types::count_effects(&result),
+                result,
+                outcome,
+                cost,
+            };
+            match on_deploy_result.as_mut() {
+                // Streamed results are only retained in serialized form, to compute their checksum.
+                Some(on_deploy_result) => {
+                    streamed_results_checksum
+                        .push(&deploy_execution_info.result)
+                        .map_err(BlockCreationError::BytesRepr)?;
+                    on_deploy_result(deploy_execution_info);
+                }
+                None => execution_results.push(deploy_execution_info),
+            }
+            state_root_hash = state_hash;
+            maybe_cache_pre_state = maybe_cache_pre_state
+                .map(|cache_pre_state| Digest::hash_pair(cache_pre_state, deploy_hash));
 
-            // In this flow we execute using a recent state root hash where the system contract
-            // registry is guaranteed to exist.
-            let system_contract_registry = None;
+            // Changing which deploys get executed changes the resulting state root hash, so the
+            // limit must be identical across the network.
+            if let Some(block_gas_limit) = block_gas_limit {
+                if block_gas_consumed > block_gas_limit {
+                    if let Some(metrics) = metrics.as_ref() {
+                        metrics.exec_block_gas_limit_exceeded.inc();
+                    }
+                    return Err(BlockExecutionError::BlockGasLimitExceeded {
+                        executed: executed_deploy_count,
+                        total: deploy_count,
+                    });
+                }
+            }
 
-            let upcoming_era_validators = engine_state.get_era_validators(
-                CorrelationId::new(),
-                system_contract_registry,
-                GetEraValidatorsRequest::new(state_root_hash, protocol_version),
+            if let Some(state_root_trace) = maybe_state_root_trace.as_mut() {
+                // The scratch state does not compute state roots, so derive the one LMDB would
+                // have.
+                let post_deploy_state_root_hash = engine_state.compute_scratch_state_root_hash(
+                    scratch_base_state_root_hash,
+                    &scratch_state,
+                )?;
+                state_root_trace.push((
+                    StateRootCheckpoint::Deploy(deploy_hash),
+                    post_deploy_state_root_hash,
+                ));
+            }
+        }
+
+        if let Some(checkpointing) = checkpointing.as_mut() {
+            if executed_deploy_count - last_checkpoint_deploy_count >= checkpointing.interval.get()
+                && !pending_deploys.is_empty()
+            {
+                let checkpoint_scratch_state =
+                    mem::replace(&mut scratch_state, engine_state.get_scratch_engine_state());
+                state_root_hash = write_scratch_to_db(
+                    engine_state,
+                    metrics.as_deref(),
+                    state_root_hash,
+                    checkpoint_scratch_state,
+                )?;
+                retry_flush(flush_retries, FLUSH_RETRY_BACKOFF, || {
+                    engine_state.flush_environment()
+                })?;
+                scratch_base_state_root_hash = state_root_hash;
+                last_checkpoint_deploy_count = executed_deploy_count;
+                let checkpoint = ExecutionCheckpoint {
+                    block_height: finalized_block.height(),
+                    pre_state_root_hash,
+                    state_root_hash,
+                    execution_results: execution_results.clone(),
+                };
+                debug!(
+                    deploy_index = checkpoint.deploy_index(),
+                    %state_root_hash,
+                    "recorded block execution checkpoint"
+                );
+                (checkpointing.on_checkpoint)(&checkpoint);
+            }
+        }
+    }
+
+    // The checksum registry and the step only contribute to the state root hash, which a dry run
+    // does not compute.
+    if dry_run {
+        return Ok(BlockExecution::DryRun(execution_results));
+    }
+
+    // Write the deploy approvals and execution results Merkle root hashes to global state if there
+    // were any deploys. Prior to `SKIP_EMPTY_BLOCK_CHECKSUM_REGISTRY_PROTOCOL_VERSION` they are
+    // written for empty blocks too. As the approvals hashes of every block carry a proof of the
+    // checksum registry, it is also written if global state doesn't hold one yet.
+    let skip_checksum_registry = deploy_count == 0
+        && protocol_version >= SKIP_EMPTY_BLOCK_CHECKSUM_REGISTRY_PROTOCOL_VERSION
+        && engine_state
+            .get_checksum_registry(correlation_id, pre_state_root_hash)?
+            .is_some();
+    let checksums = if skip_checksum_registry {
+        None
+    } else {
+        let checksum_scheme =
+            checksum_scheme.unwrap_or_else(|| execution_results_checksum_scheme(protocol_version));
+        let execution_results_checksum = if on_deploy_result.is_some() {
+            streamed_results_checksum.finalize_with(|bytes| checksum_scheme.checksum(bytes))
+        } else {
+            compute_execution_results_checksum(
+                checksum_scheme,
+                execution_results
+                    .iter()
+                    .map(|deploy_execution_info| &deploy_execution_info.result),
+            )?
+        };
+
+        let mut effects = AdditiveMap::new();
+        let mut checksum_registry = ChecksumRegistry::new();
+        checksum_registry.insert(APPROVALS_CHECKSUM_NAME, approvals_checksum);
+        checksum_registry.insert(EXECUTION_RESULTS_CHECKSUM_NAME, execution_results_checksum);
+        let _ = effects.insert(
+            Key::ChecksumRegistry,
+            Transform::Write(
+                CLValue::from_t(checksum_registry)
+                    .map_err(BlockCreationError::CLValue)?
+                    .into(),
+            ),
+        );
+        scratch_state.apply_effect(correlation_id, state_root_hash, effects)?;
+        if let Some(written_keys) = maybe_written_keys.as_mut() {
+            written_keys.insert(Key::ChecksumRegistry);
+        }
+        Some(BlockChecksums::new(
+            approvals_checksum,
+            execution_results_checksum,
+        ))
+    };
+
+    // At switch blocks, the effects of the deploys and of the step are written to LMDB together,
+    // so the state root hash preceding the step is only computed in memory.
+    let maybe_post_deploys_root = if maybe_state_root_trace.is_some()
+        || finalized_block.era_report().is_some()
+    {
+        Some(
+            engine_state
+                .compute_scratch_state_root_hash(scratch_base_state_root_hash, &scratch_state)?,
+        )
+    } else {
+        None
+    };
+    if let (Some(state_root_trace), Some(post_deploys_root)) =
+        (maybe_state_root_trace.as_mut(), maybe_post_deploys_root)
+    {
+        state_root_trace.push((StateRootCheckpoint::ChecksumRegistry, post_deploys_root));
+    }
+
+    if let Some(metrics) = metrics.as_ref() {
+        metrics.exec_block.observe(start.elapsed().as_secs_f64());
+        metrics.deploys_per_block.observe(deploy_count as f64);
+        let block_gas = cmp::min(block_gas_consumed.value(), U512::from(u64::MAX)).as_u64();
+        metrics.gas_per_block.observe(block_gas as f64);
+        metrics
+            .slowest_deploy_in_block
+            .set(slowest_deploy.as_secs_f64());
+    }
+
+    // If the finalized block has an era report, run the auction contract and get the upcoming era
+    // validators.
+    let mut maybe_simulated_proof_of_checksum_registry = None;
+    let (post_deploys_root, maybe_step_effect_and_upcoming_era_validators) =
+        if let (Some(era_report), Some(post_deploys_root)) =
+            (finalized_block.era_report(), maybe_post_deploys_root)
+        {
+            let era_reports = iter::once(era_report).chain(additional_era_reports);
+            let (step_execution_journal, step_outcome_summary) = commit_steps(
+                &scratch_state, // engine_state
+                correlation_id,
+                metrics.clone(),
+                protocol_version,
+                state_root_hash,
+                finalized_block.era_id(),
+                era_reports.clone(),
+                era_end_timestamp_millis(&finalized_block, protocol_version, era_end_timestamp),
+                slash_equivocators,
+                reward_strategy.as_deref(),
+                minimum_reward,
+                era_report_transform,
             )?;
-            Some(StepEffectAndUpcomingEraValidators {
-                step_execution_journal,
-                upcoming_era_validators,
-            })
+            if let Some(metrics) = metrics.as_ref() {
+                // Summed as `u128`, which cannot overflow for fewer than 2^64 validators.
+                let total_rewards = era_reports
+                    .flat_map(|era_report| era_report.rewards.values())
+                    .map(|reward| u128::from(*reward))
+                    .sum::<u128>();
+                metrics.last_era_total_rewards.set(total_rewards as f64);
+                // The summary holds the evict and reward items of the committed step request.
+                metrics
+                    .validators_evicted
+                    .set(step_outcome_summary.evicted.len() as i64);
+                metrics
+                    .validators_rewarded
+                    .set(step_outcome_summary.rewarded.len() as i64);
+            }
+
+            // When simulating, the upcoming era validators can only be read from the scratch state,
+            // which reflects the effects of the step under its unchanged state root hash.
+            let maybe_simulated_upcoming_era_validators = if simulate {
+                Some(get_upcoming_era_validators(
+                    &scratch_state,
+                    correlation_id,
+                    protocol_version,
+                    state_root_hash,
+                )?)
+            } else {
+                None
+            };
+
+            if let Some(written_keys) = maybe_written_keys.as_mut() {
+                written_keys.extend(step_execution_journal.iter().map(|(key, _)| *key));
+            }
+            if let (Some(max), Some(written_keys)) = (
+                max_trie_depth.filter(|_| !simulate),
+                maybe_written_keys.as_ref(),
+            ) {
+                check_trie_depth(
+                    engine_state,
+                    scratch_base_state_root_hash,
+                    &scratch_state,
+                    written_keys,
+                    max,
+                )?;
+            }
+
+            (state_root_hash, maybe_simulated_proof_of_checksum_registry) =
+                write_or_simulate_scratch_to_db(
+                    engine_state,
+                    metrics.as_deref(),
+                    scratch_base_state_root_hash,
+                    state_root_hash,
+                    scratch_state,
+                    simulate,
+                )?;
+            if let Some(state_root_trace) = maybe_state_root_trace.as_mut() {
+                state_root_trace.push((StateRootCheckpoint::Step, state_root_hash));
+            }
+
+            let upcoming_era_validators = match maybe_simulated_upcoming_era_validators {
+                Some(upcoming_era_validators) => upcoming_era_validators,
+                None => get_cached_upcoming_era_validators(
+                    engine_state,
+                    correlation_id,
+                    metrics.as_deref(),
+                    era_validators_cache,
+                    protocol_version,
+                    state_root_hash,
+                )?,
+            };
+            (
+                post_deploys_root,
+                Some(StepEffectAndUpcomingEraValidators {
+                    step_journal_summary: summarize_step_journal(&step_execution_journal),
+                    step_execution_journal,
+                    upcoming_era_validators,
+                    step_outcome_summary,
+                }),
+            )
         } else {
+            if let (Some(max), Some(written_keys)) = (
+                max_trie_depth.filter(|_| !simulate),
+                maybe_written_keys.as_ref(),
+            ) {
+                check_trie_depth(
+                    engine_state,
+                    scratch_base_state_root_hash,
+                    &scratch_state,
+                    written_keys,
+                    max,
+                )?;
+            }
+
             // Finally, the new state-root-hash from the cumulative changes to global state is
             // returned when they are written to LMDB.
-            state_root_hash =
-                engine_state.write_scratch_to_db(state_root_hash, scratch_state.into_inner())?;
-            None
+            (state_root_hash, maybe_simulated_proof_of_checksum_registry) =
+                write_or_simulate_scratch_to_db(
+                    engine_state,
+                    metrics.as_deref(),
+                    scratch_base_state_root_hash,
+                    state_root_hash,
+                    scratch_state,
+                    simulate,
+                )?;
+            (state_root_hash, None)
         };
 
-    // Flush once, after all deploys have been executed.
-    engine_state.flush_environment()?;
+    if deploy_count > 0 && state_root_hash == pre_state_root_hash {
+        debug!(
+            %state_root_hash,
+            deploy_count, "block with deploys left the state root hash unchanged"
+        );
+        if require_state_progress {
+            return Err(BlockExecutionError::NoStateProgress { state_root_hash });
+        }
+    }
+
+    if verify_roundtrip && !simulate {
+        verify_root_readable(engine_state, correlation_id, state_root_hash)?;
+    }
 
-    // Pruning
-    if let Some(previous_block_height) = finalized_block.height().checked_sub(1) {
+    // Flush once, after all deploys have been executed, or once the batch of blocks is complete.
+    if !simulate {
+        match batched_flushes {
+            Some(batched_flushes) => {
+                if batched_flushes.record_block() {
+                    retry_flush(flush_retries, FLUSH_RETRY_BACKOFF, || {
+                        batched_flushes.flush_now(engine_state, metrics.as_deref())
+                    })?;
+                }
+            }
+            None => retry_flush(flush_retries, FLUSH_RETRY_BACKOFF, || {
+                flush_environment(engine_state, metrics.as_deref())
+            })?,
+        }
+    }
+
+    // Pruning, which is skipped when simulating as it writes to LMDB.
+    let maybe_previous_block_height = if simulate {
+        None
+    } else {
+        finalized_block.height().checked_sub(1)
+    };
+    if let Some(previous_block_height) = maybe_previous_block_height {
         if let Some(keys_to_prune) = calculate_prune_eras(
             activation_point_era_id,
             key_block_height_for_activation_point,
@@ -247,8 +1508,11 @@ pub fn execute_finalized_block(
                 last_key=?last_key,
                 "commit prune: preparing prune config"
             );
+            if let Some(written_keys) = maybe_written_keys.as_mut() {
+                written_keys.extend(keys_to_prune.iter().copied());
+            }
             let prune_config = PruneConfig::new(state_root_hash, keys_to_prune);
-            match engine_state.commit_prune(CorrelationId::new(), prune_config) {
+            match engine_state.commit_prune(correlation_id, prune_config) {
                 Ok(PruneResult::RootNotFound) => {
                     error!(
                         previous_block_height,
@@ -278,6 +1542,9 @@ pub fn execute_finalized_block(
                         "commit prune: success"
                     );
                     state_root_hash = post_state_hash;
+                    if let Some(state_root_trace) = maybe_state_root_trace.as_mut() {
+                        state_root_trace.push((StateRootCheckpoint::Prune, state_root_hash));
+                    }
                 }
                 Err(error) => {
                     error!(
@@ -299,61 +1566,572 @@ pub fn execute_finalized_block(
                 |StepEffectAndUpcomingEraValidators {
                      upcoming_era_validators,
                      ..
-                 }| {
-                    upcoming_era_validators
-                        .get(&finalized_block.era_id().successor())
-                        .cloned()
-                },
+                 }| { upcoming_era_validators.get(&next_era_id).cloned() },
             );
+    if let (Some(on_era_transition), Some(next_era_validator_weights)) =
+        (on_era_transition, next_era_validator_weights.as_ref())
+    {
+        on_era_transition(next_era_id, next_era_validator_weights);
+    }
+    if let (
+        Some(validator_floor),
+        Some(on_validator_floor_breached),
+        Some(next_era_validator_weights),
+    ) = (
+        validator_floor,
+        on_validator_floor_breached,
+        next_era_validator_weights.as_ref(),
+    ) {
+        if next_era_validator_weights.len() < validator_floor {
+            on_validator_floor_breached(next_era_id, next_era_validator_weights.len());
+        }
+    }
+
+    if let (Some(read_through_cache), false) = (read_through_cache, simulate) {
+        if resumed_from_checkpoint {
+            read_through_cache.checkout(state_root_hash);
+        } else {
+            read_through_cache.advance(
+                pre_state_root_hash,
+                state_root_hash,
+                maybe_written_keys.unwrap_or_default(),
+            );
+        }
+    }
+
+    // The step accounts for much of the execution time of switch blocks, so they are timed apart.
+    if let Some(metrics) = metrics.as_ref() {
+        if let (Some(read_through_cache), Some(hits_before)) =
+            (read_through_cache, cache_hits_before)
+        {
+            metrics
+                .read_through_cache_hits
+                .inc_by(read_through_cache.hits().saturating_sub(hits_before));
+        }
+        let exec_block_total = if finalized_block.era_report().is_some() {
+            &metrics.exec_block_switch
+        } else {
+            &metrics.exec_block_normal
+        };
+        exec_block_total.observe(start.elapsed().as_secs_f64());
+        metrics
+            .trie_reads_per_block
+            .observe(trie_store.read_count().saturating_sub(trie_reads_before) as f64);
+        metrics
+            .trie_writes_per_block
+            .observe(trie_store.write_count().saturating_sub(trie_writes_before) as f64);
+    }
 
-    let block = Arc::new(Block::new(
+    Ok(BlockExecution::Block(ExecutedBlock {
         parent_hash,
         parent_seed,
         state_root_hash,
         finalized_block,
         next_era_validator_weights,
         protocol_version,
-    )?);
-
-    let approvals_hashes = deploy_ids
-        .into_iter()
-        .map(|id| id.destructure().1)
-        .collect();
-    let proof_of_checksum_registry =
-        engine_state.get_checksum_registry_proof(CorrelationId::new(), state_root_hash)?;
-    let approvals_hashes = Box::new(ApprovalsHashes::new(
-        block.hash(),
-        approvals_hashes,
-        proof_of_checksum_registry,
-    ));
-
-    Ok(BlockAndExecutionResults {
-        block,
-        approvals_hashes,
+        deploy_ids,
+        maybe_simulated_proof_of_checksum_registry,
         execution_results,
         maybe_step_effect_and_upcoming_era_validators,
-    })
+        maybe_state_root_trace,
+        post_deploys_root,
+        captured_journals,
+        checksums,
+        metrics,
+    }))
+}
+
+/// The outcome of [`execute_or_simulate_finalized_block`].
+enum BlockExecution {
+    /// The block was executed in full.
+    Block(ExecutedBlock),
+    /// Only the deploys of the block were executed, as for a dry run.
+    DryRun(Vec<DeployExecutionInfo>),
+}
+
+impl BlockExecution {
+    /// Returns the executed block of an execution which was not a dry run.
+    fn into_executed_block(self) -> ExecutedBlock {
+        match self {
+            BlockExecution::Block(executed_block) => executed_block,
+            BlockExecution::DryRun(_) => unreachable!("only the deploys of a dry run are executed"),
+        }
+    }
+}
+
+/// A finalized block which has been executed, but whose block and approvals hashes have not been
+/// assembled yet.
+struct ExecutedBlock {
+    parent_hash: BlockHash,
+    parent_seed: Digest,
+    state_root_hash: Digest,
+    finalized_block: FinalizedBlock,
+    next_era_validator_weights: Option<BTreeMap<PublicKey, U512>>,
+    protocol_version: ProtocolVersion,
+    deploy_ids: Vec<DeployId>,
+    /// The proof of the checksum registry computed in memory, if the block was simulated.
+    maybe_simulated_proof_of_checksum_registry: Option<TrieMerkleProof<Key, StoredValue>>,
+    execution_results: Vec<DeployExecutionInfo>,
+    maybe_step_effect_and_upcoming_era_validators: Option<StepEffectAndUpcomingEraValidators>,
+    maybe_state_root_trace: Option<Vec<(StateRootCheckpoint, Digest)>>,
+    post_deploys_root: Digest,
+    captured_journals: Option<Vec<CapturedJournal>>,
+    checksums: Option<BlockChecksums>,
+    /// The metrics the size of the proof of the checksum registry is observed in.
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl ExecutedBlock {
+    /// Creates the block and its approvals hashes, reading the proof of the checksum registry from
+    /// LMDB unless the block was simulated.
+    fn assemble(
+        self,
+        engine_state: &EngineState<LmdbGlobalState>,
+        correlation_id: CorrelationId,
+    ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+        let ExecutedBlock {
+            parent_hash,
+            parent_seed,
+            state_root_hash,
+            finalized_block,
+            next_era_validator_weights,
+            protocol_version,
+            deploy_ids,
+            maybe_simulated_proof_of_checksum_registry,
+            execution_results,
+            maybe_step_effect_and_upcoming_era_validators,
+            maybe_state_root_trace,
+            post_deploys_root,
+            captured_journals,
+            checksums,
+            metrics,
+        } = self;
+        let start = Instant::now();
+        let block = Arc::new(Block::new(
+            parent_hash,
+            parent_seed,
+            state_root_hash,
+            finalized_block,
+            next_era_validator_weights,
+            protocol_version,
+        )?);
+
+        let approvals_hashes = deploy_ids
+            .into_iter()
+            .map(|id| id.destructure().1)
+            .collect();
+        let proof_of_checksum_registry = match maybe_simulated_proof_of_checksum_registry {
+            Some(proof_of_checksum_registry) => proof_of_checksum_registry,
+            None => checksum_registry_proof(
+                engine_state,
+                metrics.as_deref(),
+                correlation_id,
+                state_root_hash,
+            )?,
+        };
+        let approvals_hashes = Box::new(ApprovalsHashes::new(
+            block.hash(),
+            approvals_hashes,
+            proof_of_checksum_registry,
+        ));
+        // Each deploy's gas is converted at the price it declares, see
+        // `BlockAndExecutionResults::gas_price`.
+        let gas_price = execution_results
+            .iter()
+            .map(|deploy_execution_info| deploy_execution_info.header.gas_price())
+            .max();
+        if let Some(metrics) = metrics.as_ref() {
+            metrics
+                .block_assembly
+                .observe(start.elapsed().as_secs_f64());
+        }
+
+        Ok(BlockAndExecutionResults {
+            block,
+            approvals_hashes,
+            execution_results,
+            maybe_step_effect_and_upcoming_era_validators,
+            maybe_state_root_trace,
+            post_deploys_root,
+            final_root: state_root_hash,
+            execution_result_index: OnceCell::new(),
+            captured_journals,
+            checksums,
+            gas_price,
+        })
+    }
+}
+
+/// Writes the changes cached in the scratch state to LMDB, returning the resulting state root hash.
+fn write_scratch_to_db(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<&Metrics>,
+    state_root_hash: Digest,
+    scratch_state: EngineState<ScratchGlobalState>,
+) -> Result<Digest, engine_state::Error> {
+    let scratch_global_state = scratch_state.into_inner();
+    if let Some(metrics) = metrics {
+        metrics
+            .scratch_state_transform_count
+            .set(scratch_global_state.dirty_value_count() as i64);
+    }
+    let start = Instant::now();
+    let result = engine_state.write_scratch_to_db(state_root_hash, scratch_global_state);
+    if let Some(metrics) = metrics {
+        metrics
+            .scratch_write_to_db
+            .observe(start.elapsed().as_secs_f64());
+    }
+    result
+}
+
+/// Reads the era validators at the state root hash resulting from a switch block.
+///
+/// In this flow we execute using a recent state root hash where the system contract registry is
+/// expected to exist, so it is read from global state rather than passed in.  Should it be missing
+/// all the same, e.g. due to corrupted global state, a dedicated error naming the state root hash
+/// is returned.
+fn get_upcoming_era_validators<S>(
+    engine_state: &EngineState<S>,
+    correlation_id: CorrelationId,
+    protocol_version: ProtocolVersion,
+    state_root_hash: Digest,
+) -> Result<EraValidators, BlockExecutionError>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let system_contract_registry = None;
+    engine_state
+        .get_era_validators(
+            correlation_id,
+            system_contract_registry,
+            GetEraValidatorsRequest::new(state_root_hash, protocol_version),
+        )
+        .map_err(|error| match error {
+            GetEraValidatorsError::Other(engine_state::Error::MissingSystemContractRegistry) => {
+                BlockExecutionError::MissingSystemContractRegistry { state_root_hash }
+            }
+            error => BlockExecutionError::GetEraValidators(error),
+        })
+}
+
+/// Reads the era validators as [`get_upcoming_era_validators`] does, taking them from
+/// `era_validators_cache` if given and already read at the same state root hash.
+///
+/// Only state roots written to LMDB may be cached, as a scratch state holds different contents
+/// under the same state root hash.
+fn get_cached_upcoming_era_validators(
+    engine_state: &EngineState<LmdbGlobalState>,
+    correlation_id: CorrelationId,
+    metrics: Option<&Metrics>,
+    era_validators_cache: Option<&EraValidatorsCache>,
+    protocol_version: ProtocolVersion,
+    state_root_hash: Digest,
+) -> Result<EraValidators, BlockExecutionError> {
+    let era_validators_cache = match era_validators_cache {
+        Some(era_validators_cache) => era_validators_cache,
+        None => {
+            return get_upcoming_era_validators(
+                engine_state,
+                correlation_id,
+                protocol_version,
+                state_root_hash,
+            )
+        }
+    };
+    if let Some(era_validators) = era_validators_cache.get(state_root_hash, protocol_version) {
+        if let Some(metrics) = metrics {
+            metrics.era_validators_cache_hits.inc();
+        }
+        return Ok(era_validators);
+    }
+    let era_validators = get_upcoming_era_validators(
+        engine_state,
+        correlation_id,
+        protocol_version,
+        state_root_hash,
+    )?;
+    era_validators_cache.insert(state_root_hash, protocol_version, era_validators.clone());
+    Ok(era_validators)
+}
+
+/// Flushes the LMDB environment to disk.
+pub(super) fn flush_environment(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<&Metrics>,
+) -> Result<(), lmdb::Error> {
+    let start = Instant::now();
+    engine_state.flush_environment()?;
+    if let Some(metrics) = metrics {
+        metrics
+            .flush_environment
+            .observe(start.elapsed().as_secs_f64());
+    }
+    Ok(())
+}
+
+/// The time to wait before the first retry of a failed flush, doubled for each further retry.
+const FLUSH_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The maximum number of times the time to wait before a retry of a failed flush is doubled.
+const MAX_FLUSH_RETRY_BACKOFF_DOUBLINGS: u32 = 6;
+
+/// Calls `flush` until it succeeds, retrying up to `retries` times with exponential backoff
+/// starting at `backoff`.
+///
+/// Without retries, the error of the single attempt is returned as a
+/// [`BlockExecutionError::Lmdb`], otherwise that of the last attempt as a
+/// [`BlockExecutionError::FlushFailed`].
+fn retry_flush(
+    retries: u32,
+    backoff: Duration,
+    mut flush: impl FnMut() -> Result<(), lmdb::Error>,
+) -> Result<(), BlockExecutionError> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match flush() {
+            Ok(()) => return Ok(()),
+            Err(error) if retries == 0 => return Err(error.into()),
+            Err(error) if attempts <= retries => {
+                let delay =
+                    backoff * 2_u32.pow(cmp::min(attempts - 1, MAX_FLUSH_RETRY_BACKOFF_DOUBLINGS));
+                warn!(%error, attempts, ?delay, "failed to flush LMDB environment, retrying");
+                thread::sleep(delay);
+            }
+            Err(source) => {
+                error!(%source, attempts, "failed to flush LMDB environment, giving up");
+                return Err(BlockExecutionError::FlushFailed { attempts, source });
+            }
+        }
+    }
+}
+
+/// Checks that the proposer of the block is a validator of the block's era, unless it is the
+/// system.
+fn check_proposer(
+    engine_state: &EngineState<LmdbGlobalState>,
+    correlation_id: CorrelationId,
+    protocol_version: ProtocolVersion,
+    pre_state_root_hash: Digest,
+    finalized_block: &FinalizedBlock,
+) -> Result<(), BlockExecutionError> {
+    let proposer = finalized_block.proposer();
+    if *proposer == PublicKey::System {
+        return Ok(());
+    }
+    let era_validators = engine_state.get_era_validators(
+        correlation_id,
+        None,
+        GetEraValidatorsRequest::new(pre_state_root_hash, protocol_version),
+    )?;
+    let is_validator = era_validators
+        .get(&finalized_block.era_id())
+        .map_or(false, |validator_weights| {
+            validator_weights.contains_key(&*proposer)
+        });
+    if !is_validator {
+        return Err(BlockExecutionError::UnknownProposer { proposer });
+    }
+    Ok(())
+}
+
+/// Checks that `protocol_version` is not lower than the protocol version global state at
+/// `pre_state_root_hash` was produced under, i.e. that of the system mint contract, which is
+/// updated by every protocol upgrade.
+fn check_protocol_version(
+    engine_state: &EngineState<LmdbGlobalState>,
+    correlation_id: CorrelationId,
+    protocol_version: ProtocolVersion,
+    pre_state_root_hash: Digest,
+) -> Result<(), BlockExecutionError> {
+    let mint_hash = engine_state.get_system_mint_hash(correlation_id, pre_state_root_hash)?;
+    let query_request = QueryRequest::new(pre_state_root_hash, Key::from(mint_hash), vec![]);
+    let state_protocol_version = match engine_state.run_query(correlation_id, query_request)? {
+        QueryResult::Success { value, .. } => match *value {
+            StoredValue::Contract(contract) => contract.protocol_version(),
+            _ => {
+                return Err(engine_state::Error::MissingSystemContractHash(MINT.to_string()).into())
+            }
+        },
+        QueryResult::RootNotFound => {
+            return Err(engine_state::Error::RootNotFound(pre_state_root_hash).into())
+        }
+        _ => return Err(engine_state::Error::MissingSystemContractHash(MINT.to_string()).into()),
+    };
+    if protocol_version < state_protocol_version {
+        error!(
+            %protocol_version,
+            %state_protocol_version,
+            %pre_state_root_hash,
+            "refusing to execute block under a lower protocol version than its pre-state"
+        );
+        return Err(BlockExecutionError::ProtocolVersionRegression {
+            expected_min: state_protocol_version,
+            actual: protocol_version,
+        });
+    }
+    Ok(())
+}
+
+/// Writes the changes cached in the scratch state to LMDB unless `simulate` is set, returning the
+/// resulting state root hash.
+///
+/// When simulating, the state root hash is computed in memory on top of `pre_state_root_hash`, and
+/// returned along with the proof of the checksum registry, which cannot be read from LMDB.
+fn write_or_simulate_scratch_to_db(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<&Metrics>,
+    pre_state_root_hash: Digest,
+    state_root_hash: Digest,
+    scratch_state: EngineState<ScratchGlobalState>,
+    simulate: bool,
+) -> Result<(Digest, Option<TrieMerkleProof<Key, StoredValue>>), engine_state::Error> {
+    if simulate {
+        let (simulated_state_root_hash, proof_of_checksum_registry) = engine_state
+            .compute_scratch_checksum_registry_proof(pre_state_root_hash, &scratch_state)?;
+        Ok((simulated_state_root_hash, Some(proof_of_checksum_registry)))
+    } else {
+        let state_root_hash =
+            write_scratch_to_db(engine_state, metrics, state_root_hash, scratch_state)?;
+        Ok((state_root_hash, None))
+    }
+}
+
+/// Checks that the global state just written to LMDB can be read back, by reading the checksum
+/// registry at the given state root hash.
+fn verify_root_readable(
+    engine_state: &EngineState<LmdbGlobalState>,
+    correlation_id: CorrelationId,
+    state_root_hash: Digest,
+) -> Result<(), BlockExecutionError> {
+    match engine_state.get_checksum_registry(correlation_id, state_root_hash) {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => {
+            error!(%state_root_hash, "checksum registry missing at written state root");
+            Err(BlockExecutionError::RootNotReadable { state_root_hash })
+        }
+        Err(error) => {
+            error!(%state_root_hash, %error, "failed to read back written state root");
+            Err(BlockExecutionError::RootNotReadable { state_root_hash })
+        }
+    }
+}
+
+/// The maximum number of written keys whose trie depth is checked by [`check_trie_depth`].
+const TRIE_DEPTH_SAMPLE_SIZE: usize = 64;
+
+/// Checks that none of a sample of `written_keys` lies deeper than `max` in the trie which writing
+/// `scratch_state` on top of `pre_state_root_hash` would produce, without writing it.
+///
+/// If there are more than [`TRIE_DEPTH_SAMPLE_SIZE`] keys, evenly spaced ones in key order are
+/// sampled, so that all nodes check the same keys.
+fn check_trie_depth(
+    engine_state: &EngineState<LmdbGlobalState>,
+    pre_state_root_hash: Digest,
+    scratch_state: &EngineState<ScratchGlobalState>,
+    written_keys: &BTreeSet<Key>,
+    max: usize,
+) -> Result<(), BlockExecutionError> {
+    let step = cmp::max(
+        1,
+        (written_keys.len() + TRIE_DEPTH_SAMPLE_SIZE - 1) / TRIE_DEPTH_SAMPLE_SIZE,
+    );
+    let sampled_keys = written_keys.iter().step_by(step).copied().collect_vec();
+    let depths = engine_state.compute_scratch_trie_depths(
+        pre_state_root_hash,
+        scratch_state,
+        &sampled_keys,
+    )?;
+    for (key, maybe_depth) in sampled_keys.into_iter().zip(depths) {
+        // Keys pruned by the block are no longer in the trie.
+        if let Some(depth) = maybe_depth {
+            if depth > max {
+                error!(%key, depth, max, %pre_state_root_hash, "trie depth limit exceeded");
+                return Err(BlockExecutionError::TrieDepthExceeded { key, depth, max });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the Merkle proof of the checksum registry at the given state root hash.
+///
+/// If `metrics` are given, the serialized size of the proof is observed.
+fn checksum_registry_proof(
+    engine_state: &EngineState<LmdbGlobalState>,
+    metrics: Option<&Metrics>,
+    correlation_id: CorrelationId,
+    state_root_hash: Digest,
+) -> Result<TrieMerkleProof<Key, StoredValue>, BlockExecutionError> {
+    let proof = engine_state
+        .get_checksum_registry_proof(correlation_id, state_root_hash)
+        .map_err(|source| BlockExecutionError::ChecksumRegistryProof {
+            state_root_hash,
+            source,
+        })?;
+    if let Some(metrics) = metrics {
+        metrics
+            .checksum_registry_proof_size_bytes
+            .observe(proof.serialized_length() as f64);
+    }
+    Ok(proof)
 }
 
-/// Commits the execution results.
-fn commit_execution_results<S>(
+/// The error message of the execution result recorded for a deploy vetoed by the deploy filter.
+const VETOED_DEPLOY_ERROR_MESSAGE: &str = "deploy vetoed by the deploy filter";
+
+/// Returns the execution info recorded for a deploy vetoed by the deploy filter in place of
+/// executing it: a failure without effects or cost.
+fn vetoed_deploy_execution_info(
+    deploy_hash: DeployHash,
+    header: DeployHeader,
+) -> DeployExecutionInfo {
+    DeployExecutionInfo {
+        deploy_hash,
+        header,
+        effect_count: 0,
+        result: ExecutionResult::Failure {
+            effect: Default::default(),
+            transfers: vec![],
+            cost: U512::zero(),
+            error_message: VETOED_DEPLOY_ERROR_MESSAGE.to_string(),
+        },
+        outcome: DeployOutcome::Vetoed,
+        cost: U512::zero(),
+    }
+}
+
+/// Commits the execution result of a single deploy.
+///
+/// Returns the post-state root hash along with the JSON execution result and the outcome of the
+/// deploy. If given, `effect_observer` is called with each transform of the deploy's execution
+/// journal before it is committed.
+fn commit_execution_result<S>(
     engine_state: &EngineState<S>,
+    correlation_id: CorrelationId,
     metrics: Option<Arc<Metrics>>,
     state_root_hash: Digest,
     deploy_hash: DeployHash,
-    execution_results: ExecutionResults,
-) -> Result<(Digest, ExecutionResult), BlockExecutionError>
+    ee_execution_result: EngineExecutionResult,
+    effect_observer: Option<&dyn Fn(&DeployHash, &Key, &Transform)>,
+) -> Result<(Digest, ExecutionResult, DeployOutcome), BlockExecutionError>
 where
     S: StateProvider + CommitProvider,
     S::Error: Into<execution::Error>,
 {
-    let ee_execution_result = execution_results
-        .into_iter()
-        .exactly_one()
-        .map_err(|_| BlockExecutionError::MoreThanOneExecutionResult)?;
     let json_execution_result = ExecutionResult::from(&ee_execution_result);
+    if let Some(metrics) = metrics.as_ref() {
+        let gas = cmp::min(ee_execution_result.cost().value(), U512::from(u64::MAX)).as_u64();
+        metrics.total_gas_consumed.inc_by(gas as f64);
+        match ee_execution_result {
+            EngineExecutionResult::Success { .. } => metrics.deploys_succeeded_total.inc(),
+            EngineExecutionResult::Failure { .. } => metrics.deploys_failed_total.inc(),
+        }
+    }
 
-    let execution_effect: AdditiveMap<Key, Transform> = match ee_execution_result {
+    let (execution_journal, outcome) = match ee_execution_result {
         EngineExecutionResult::Success {
             execution_journal,
             cost,
@@ -362,7 +2140,7 @@ where
             // We do want to see the deploy hash and cost in the logs.
             // We don't need to see the effects in the logs.
             debug!(?deploy_hash, %cost, "execution succeeded");
-            execution_journal
+            (execution_journal, DeployOutcome::Success)
         }
         EngineExecutionResult::Failure {
             error,
@@ -374,17 +2152,95 @@ where
             // We do want to see the deploy hash, error, and cost in the logs.
             // We don't need to see the effects in the logs.
             debug!(?deploy_hash, ?error, %cost, "execution failure");
-            execution_journal
+            let error_message = error.to_string();
+            let outcome = if is_insufficient_balance(&error) {
+                DeployOutcome::InsufficientBalance { error_message }
+            } else {
+                DeployOutcome::Failure { error_message }
+            };
+            (execution_journal, outcome)
+        }
+    };
+    if let Some(effect_observer) = effect_observer {
+        for (key, transform) in execution_journal.iter() {
+            effect_observer(&deploy_hash, key, transform);
         }
     }
-    .into();
-    let new_state_root =
-        commit_transforms(engine_state, metrics, state_root_hash, execution_effect)?;
-    Ok((new_state_root, json_execution_result))
-}
-
+    let execution_effect: AdditiveMap<Key, Transform> = execution_journal.into();
+    // Writes cannot fail to apply, so only the other transforms are retained to identify the one
+    // which did.
+    let fallible_transforms = execution_effect
+        .iter()
+        .filter(|(_, transform)| !matches!(transform, Transform::Write(_)))
+        .map(|(key, transform)| (*key, transform.clone()))
+        .collect_vec();
+    let new_state_root = match commit_transforms(
+        engine_state,
+        correlation_id,
+        metrics,
+        state_root_hash,
+        execution_effect,
+    ) {
+        Ok(new_state_root) => new_state_root,
+        Err(error) => {
+            return Err(
+                match find_conflicting_key(
+                    engine_state,
+                    correlation_id,
+                    state_root_hash,
+                    fallible_transforms,
+                ) {
+                    Some(key) => BlockExecutionError::EffectApplyConflict {
+                        deploy_hash,
+                        key,
+                        error,
+                    },
+                    None => error.into(),
+                },
+            )
+        }
+    };
+    Ok((new_state_root, json_execution_result, outcome))
+}
+
+/// Returns the first key whose transform cannot be applied to the value it holds in the global
+/// state at `state_root_hash`, if any.
+fn find_conflicting_key<S>(
+    engine_state: &EngineState<S>,
+    correlation_id: CorrelationId,
+    state_root_hash: Digest,
+    transforms: Vec<(Key, Transform)>,
+) -> Option<Key>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let mut tracking_copy = engine_state.tracking_copy(state_root_hash).ok()??;
+    transforms.into_iter().find_map(|(key, transform)| {
+        match tracking_copy.read(correlation_id, &key) {
+            Ok(Some(value)) => transform.apply(value).is_err().then(|| key),
+            // Only writes can be applied to a key without a value.
+            Ok(None) => Some(key),
+            Err(_) => None,
+        }
+    })
+}
+
+/// Returns whether a deploy failed with `error` as its account could not pay for it, or could not
+/// afford the amount it transfers.
+fn is_insufficient_balance(error: &engine_state::Error) -> bool {
+    match error {
+        engine_state::Error::InsufficientPayment => true,
+        engine_state::Error::Exec(execution::Error::Revert(api_error)) => {
+            *api_error == ApiError::from(mint::Error::InsufficientFunds)
+        }
+        _ => false,
+    }
+}
+
 fn commit_transforms<S>(
     engine_state: &EngineState<S>,
+    correlation_id: CorrelationId,
     metrics: Option<Arc<Metrics>>,
     state_root_hash: Digest,
     effects: AdditiveMap<Key, Transform>,
@@ -393,8 +2249,7 @@ where
     S: StateProvider + CommitProvider,
     S::Error: Into<execution::Error>,
 {
-    trace!(?state_root_hash, ?effects, "commit");
-    let correlation_id = CorrelationId::new();
+    trace!(%correlation_id, ?state_root_hash, ?effects, "commit");
     let start = Instant::now();
     let result = engine_state.apply_effect(correlation_id, state_root_hash, effects);
     if let Some(metrics) = metrics {
@@ -404,15 +2259,480 @@ where
     result.map(Digest::from)
 }
 
+/// Reconstructs global state from recorded effects rather than by executing deploys, committing
+/// `journals` in order on top of `pre_state_root_hash` and returning the resulting state root hash.
+///
+/// This replays e.g. the [`CapturedJournal`]s of a block, which only cover its deploys: to reach
+/// the state root hash of the block, the effects of writing its checksum registry, and of the step
+/// at switch blocks, must be included too.
+pub fn apply_recorded_journals<S>(
+    engine_state: &EngineState<S>,
+    pre_state_root_hash: Digest,
+    journals: Vec<AdditiveMap<Key, Transform>>,
+) -> Result<Digest, engine_state::Error>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let correlation_id = CorrelationId::new();
+    journals
+        .into_iter()
+        .try_fold(pre_state_root_hash, |state_root_hash, effects| {
+            commit_transforms(engine_state, correlation_id, None, state_root_hash, effects)
+        })
+}
+
 /// Execute the transaction without commiting the effects.
 /// Intended to be used for discovery operations on read-only nodes.
 ///
+/// The deploy is rejected without being executed if it has expired by `current_time` or is not
+/// intended for the chain named `chain_name`, as it would be when executing a finalized block.
+///
+/// If `metrics` are given, the time to execute the deploy is observed as speculative execution.
+///
 /// Returns effects of the execution.
 pub fn execute_only<S>(
+    engine_state: &EngineState<S>,
+    metrics: Option<Arc<Metrics>>,
+    execution_state: SpeculativeExecutionState,
+    deploy: DeployItem,
+    deploy_header: &DeployHeader,
+    chain_name: &str,
+    current_time: Timestamp,
+) -> Result<Option<ExecutionResult>, SpeculativeExecutionError>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    if deploy_header.expired(current_time) {
+        return Err(SpeculativeExecutionError::InvalidDeploy(
+            InvalidSpeculativeDeploy::Expired {
+                expiry: deploy_header.expires(),
+                current_time,
+            },
+        ));
+    }
+    if deploy_header.chain_name() != chain_name {
+        return Err(SpeculativeExecutionError::InvalidDeploy(
+            InvalidSpeculativeDeploy::InvalidChainName {
+                expected: chain_name.to_string(),
+                got: deploy_header.chain_name().to_string(),
+            },
+        ));
+    }
+    let maybe_execution_result =
+        execute_single_deploy(engine_state, metrics, execution_state, deploy)?;
+    Ok(maybe_execution_result.map(Into::into))
+}
+
+/// Execute the transaction without commiting the effects, on top of the given (possibly
+/// historical) state root and with the given block proposer, which overrides the proposer of
+/// `execution_state`.
+///
+/// Returns `Error::RootNotFound` without executing the deploy if the state root is not present
+/// in global state.
+pub fn execute_only_at<S>(
     engine_state: &EngineState<S>,
     execution_state: SpeculativeExecutionState,
     deploy: DeployItem,
+    proposer: PublicKey,
 ) -> Result<Option<ExecutionResult>, engine_state::Error>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let execution_state = SpeculativeExecutionState {
+        proposer: Some(proposer),
+        ..execution_state
+    };
+    let maybe_execution_result =
+        execute_single_deploy(engine_state, None, execution_state, deploy)?;
+    // We need to transform the `engine_state::ExecutionResult` into
+    // `casper_types::ExecutionResult`.
+    Ok(maybe_execution_result.map(Into::into))
+}
+
+/// Execute several independent transactions without commiting their effects, all on top of the
+/// same state root.
+///
+/// Each deploy is executed on its own, so none sees the effects of the others. The state root is
+/// only checked to be present once; if it is missing, `Error::RootNotFound` is returned for every
+/// deploy. The results are in the order of `deploys`.
+pub fn execute_only_batch<S>(
+    engine_state: &EngineState<S>,
+    execution_state: SpeculativeExecutionState,
+    deploys: Vec<DeployItem>,
+) -> Vec<Result<Option<ExecutionResult>, engine_state::Error>>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let state_root_hash = execution_state.state_root_hash;
+    let maybe_root_error = match engine_state.tracking_copy(state_root_hash) {
+        Ok(Some(_)) => None,
+        Ok(None) => Some(engine_state::Error::RootNotFound(state_root_hash)),
+        Err(error) => Some(error),
+    };
+    deploys
+        .into_iter()
+        .map(|deploy| match &maybe_root_error {
+            Some(error) => Err(error.clone()),
+            None => {
+                execute_single_deploy_at_present_root(engine_state, None, &execution_state, deploy)
+                    .map(|maybe_execution_result| maybe_execution_result.map(Into::into))
+            }
+        })
+        .collect()
+}
+
+/// Execute the transaction without commiting the effects.
+/// Intended to be used for previewing the state changes a deploy would make.
+///
+/// Returns effects of the execution along with the execution journal, i.e. the transforms which
+/// would be applied to global state if the effects were committed.
+pub fn execute_only_with_effects<S>(
+    engine_state: &EngineState<S>,
+    execution_state: SpeculativeExecutionState,
+    deploy: DeployItem,
+) -> Result<Option<(ExecutionResult, AdditiveMap<Key, Transform>)>, engine_state::Error>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let maybe_execution_result =
+        execute_single_deploy(engine_state, None, execution_state, deploy)?;
+    Ok(maybe_execution_result.map(|ee_execution_result| {
+        let json_execution_result = ExecutionResult::from(&ee_execution_result);
+        let execution_journal = match ee_execution_result {
+            EngineExecutionResult::Success {
+                execution_journal, ..
+            }
+            | EngineExecutionResult::Failure {
+                execution_journal, ..
+            } => execution_journal,
+        };
+        (json_execution_result, execution_journal.into())
+    }))
+}
+
+/// Execute the transaction without commiting the effects to global state, returning the state root
+/// hash global state would have if they were committed.
+///
+/// The effects are committed to a scratch state which is discarded afterwards, so nothing is
+/// written to LMDB.
+pub fn execute_only_with_root(
+    engine_state: &EngineState<LmdbGlobalState>,
+    execution_state: SpeculativeExecutionState,
+    deploy: DeployItem,
+) -> Result<Option<(ExecutionResult, Digest)>, engine_state::Error> {
+    let state_root_hash = execution_state.state_root_hash;
+    let scratch_state = engine_state.get_scratch_engine_state();
+    let (execution_result, effects) =
+        match execute_only_with_effects(&scratch_state, execution_state, deploy)? {
+            Some(execution_result_and_effects) => execution_result_and_effects,
+            None => return Ok(None),
+        };
+    // The scratch state does not compute state roots, so derive the one LMDB would have.
+    commit_transforms(
+        &scratch_state,
+        CorrelationId::new(),
+        None,
+        state_root_hash,
+        effects,
+    )?;
+    let post_state_hash =
+        engine_state.compute_scratch_state_root_hash(state_root_hash, &scratch_state)?;
+    Ok(Some((execution_result, post_state_hash)))
+}
+
+/// Checks that the execution engine can execute deploys against the given state root, e.g. for a
+/// readiness probe.
+///
+/// A zero-amount transfer from the system account is executed without committing its effects. The
+/// check passes if the execution engine returns exactly one execution result for it, regardless of
+/// whether the deploy itself succeeds.
+pub fn engine_self_check<S>(
+    engine_state: &EngineState<S>,
+    state_root_hash: Digest,
+    protocol_version: ProtocolVersion,
+) -> Result<(), SelfCheckError>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    if engine_state.tracking_copy(state_root_hash)?.is_none() {
+        return Err(SelfCheckError::MissingRoot { state_root_hash });
+    }
+    let account_hash = PublicKey::System.to_account_hash();
+    let payment = ExecutableDeployItem::ModuleBytes {
+        module_bytes: Bytes::new(),
+        args: runtime_args! { "amount" => U512::zero() },
+    };
+    let session = ExecutableDeployItem::Transfer {
+        args: runtime_args! {
+            "amount" => U512::zero(),
+            "target" => account_hash,
+            "id" => Option::<u64>::None,
+        },
+    };
+    let deploy_item = DeployItem::new(
+        account_hash,
+        session,
+        payment,
+        1,
+        BTreeSet::from([account_hash]),
+        casper_types::DeployHash::new([0; 32]),
+    );
+    let execute_request = ExecuteRequest::new(
+        state_root_hash,
+        Timestamp::now().millis(),
+        vec![deploy_item],
+        protocol_version,
+        PublicKey::System,
+    );
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        execute(
+            engine_state,
+            CorrelationId::new(),
+            None,
+            execute_request,
+            ExecutionKind::Speculative,
+        )
+    }));
+    match result {
+        Ok(Ok(execution_results)) if execution_results.len() == 1 => Ok(()),
+        Ok(Ok(execution_results)) => Err(SelfCheckError::UnexpectedResultCount {
+            count: execution_results.len(),
+        }),
+        Ok(Err(engine_state::Error::RootNotFound(_))) => {
+            Err(SelfCheckError::MissingRoot { state_root_hash })
+        }
+        Ok(Err(error)) => Err(error.into()),
+        Err(payload) => {
+            let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                message.to_string()
+            } else if let Some(message) = payload.downcast_ref::<String>() {
+                message.clone()
+            } else {
+                String::from("unknown panic payload")
+            };
+            Err(SelfCheckError::Panicked { message })
+        }
+    }
+}
+
+/// Executes deploys speculatively, reusing the scratch state created for a state root across calls.
+///
+/// Scratch states cache the values read from global state, so speculative executions on top of the
+/// same state root avoid re-reading them from the trie. Up to `capacity` scratch states are kept,
+/// evicting the least recently used one when full.
+pub struct SpeculativeExecutor {
+    engine_state: Arc<EngineState<LmdbGlobalState>>,
+    capacity: NonZeroUsize,
+    scratch_states: Mutex<LinkedHashMap<Digest, Arc<EngineState<ScratchGlobalState>>>>,
+}
+
+impl SpeculativeExecutor {
+    /// Creates a speculative executor caching up to `capacity` scratch states.
+    pub fn new(engine_state: Arc<EngineState<LmdbGlobalState>>, capacity: NonZeroUsize) -> Self {
+        SpeculativeExecutor {
+            engine_state,
+            capacity,
+            scratch_states: Mutex::new(LinkedHashMap::new()),
+        }
+    }
+
+    /// Executes the deploy without committing the effects, as [`execute_only`] does.
+    pub fn execute_only(
+        &self,
+        execution_state: SpeculativeExecutionState,
+        deploy: DeployItem,
+        deploy_header: &DeployHeader,
+        chain_name: &str,
+        current_time: Timestamp,
+    ) -> Result<Option<ExecutionResult>, SpeculativeExecutionError> {
+        let scratch_state = self.scratch_state(execution_state.state_root_hash)?;
+        execute_only(
+            &*scratch_state,
+            None,
+            execution_state,
+            deploy,
+            deploy_header,
+            chain_name,
+            current_time,
+        )
+    }
+
+    /// Returns the cached scratch state for the given state root, creating it if necessary.
+    fn scratch_state(
+        &self,
+        state_root_hash: Digest,
+    ) -> Result<Arc<EngineState<ScratchGlobalState>>, engine_state::Error> {
+        let mut scratch_states = self
+            .scratch_states
+            .lock()
+            .expect("scratch states mutex poisoned");
+        if let Some(scratch_state) = scratch_states.get_refresh(&state_root_hash) {
+            return Ok(Arc::clone(scratch_state));
+        }
+        // Don't cache scratch states for unknown roots.
+        if self.engine_state.tracking_copy(state_root_hash)?.is_none() {
+            return Err(engine_state::Error::RootNotFound(state_root_hash));
+        }
+        let scratch_state = Arc::new(self.engine_state.get_scratch_engine_state());
+        scratch_states.insert(state_root_hash, Arc::clone(&scratch_state));
+        while scratch_states.len() > self.capacity.get() {
+            scratch_states.pop_front();
+        }
+        Ok(scratch_state)
+    }
+}
+
+/// Global state which can be read and executed against, but refuses any write with a
+/// [`CommitError::ReadOnly`].
+///
+/// The execution engine requires global state to be committable, which read replicas only serving
+/// queries have no use for.
+pub struct ReadOnlyState<S>(S);
+
+impl<S> StateProvider for ReadOnlyState<S>
+where
+    S: StateProvider,
+    S::Error: From<CommitError>,
+{
+    type Error = S::Error;
+
+    type Reader = S::Reader;
+
+    fn checkout(&self, state_hash: Digest) -> Result<Option<Self::Reader>, Self::Error> {
+        self.0.checkout(state_hash)
+    }
+
+    fn empty_root(&self) -> Digest {
+        self.0.empty_root()
+    }
+
+    fn get_trie_full(
+        &self,
+        correlation_id: CorrelationId,
+        trie_key: &Digest,
+    ) -> Result<Option<TrieRaw>, Self::Error> {
+        self.0.get_trie_full(correlation_id, trie_key)
+    }
+
+    fn put_trie(
+        &self,
+        _correlation_id: CorrelationId,
+        _trie: &[u8],
+    ) -> Result<Digest, Self::Error> {
+        Err(CommitError::ReadOnly.into())
+    }
+
+    fn missing_children(
+        &self,
+        correlation_id: CorrelationId,
+        trie_raw: &[u8],
+    ) -> Result<Vec<Digest>, Self::Error> {
+        self.0.missing_children(correlation_id, trie_raw)
+    }
+
+    fn delete_keys(
+        &self,
+        _correlation_id: CorrelationId,
+        _root: Digest,
+        _keys_to_delete: &[Key],
+    ) -> Result<DeleteResult, Self::Error> {
+        Err(CommitError::ReadOnly.into())
+    }
+}
+
+impl<S> CommitProvider for ReadOnlyState<S>
+where
+    S: StateProvider,
+    S::Error: From<CommitError>,
+{
+    fn commit(
+        &self,
+        _correlation_id: CorrelationId,
+        _state_hash: Digest,
+        _effects: AdditiveMap<Key, Transform>,
+    ) -> Result<Digest, Self::Error> {
+        Err(CommitError::ReadOnly.into())
+    }
+}
+
+/// Executes deploys speculatively against global state which only needs to be readable, e.g. on a
+/// read replica.
+///
+/// The global state is wrapped in a [`ReadOnlyState`], so none of the executions can write to it.
+pub struct ReadOnlyExecutor<S> {
+    engine_state: EngineState<ReadOnlyState<S>>,
+}
+
+impl<S> ReadOnlyExecutor<S>
+where
+    S: StateProvider,
+    S::Error: From<CommitError> + Into<execution::Error>,
+{
+    /// Creates a read-only executor over `state`, executing deploys as configured by `config`.
+    pub fn new(state: S, config: EngineConfig) -> Self {
+        ReadOnlyExecutor {
+            engine_state: EngineState::new(ReadOnlyState(state), config),
+        }
+    }
+
+    /// Executes the deploy without committing the effects, as [`execute_only`] does.
+    pub fn execute_only(
+        &self,
+        execution_state: SpeculativeExecutionState,
+        deploy: DeployItem,
+        deploy_header: &DeployHeader,
+        chain_name: &str,
+        current_time: Timestamp,
+    ) -> Result<Option<ExecutionResult>, SpeculativeExecutionError> {
+        execute_only(
+            &self.engine_state,
+            None,
+            execution_state,
+            deploy,
+            deploy_header,
+            chain_name,
+            current_time,
+        )
+    }
+}
+
+/// Executes a single deploy without committing the effects, returning `None` if the execution
+/// engine did not return exactly one execution result.
+fn execute_single_deploy<S>(
+    engine_state: &EngineState<S>,
+    metrics: Option<Arc<Metrics>>,
+    execution_state: SpeculativeExecutionState,
+    deploy: DeployItem,
+) -> Result<Option<EngineExecutionResult>, engine_state::Error>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    if engine_state
+        .tracking_copy(execution_state.state_root_hash)?
+        .is_none()
+    {
+        return Err(engine_state::Error::RootNotFound(
+            execution_state.state_root_hash,
+        ));
+    }
+    execute_single_deploy_at_present_root(engine_state, metrics, &execution_state, deploy)
+}
+
+/// Executes a single deploy as [`execute_single_deploy`] does, assuming the state root has already
+/// been checked to be present in global state.
+fn execute_single_deploy_at_present_root<S>(
+    engine_state: &EngineState<S>,
+    metrics: Option<Arc<Metrics>>,
+    execution_state: &SpeculativeExecutionState,
+    deploy: DeployItem,
+) -> Result<Option<EngineExecutionResult>, engine_state::Error>
 where
     S: StateProvider + CommitProvider,
     S::Error: Into<execution::Error>,
@@ -421,16 +2741,25 @@ where
         state_root_hash,
         block_time,
         protocol_version,
-    } = execution_state;
+        ref proposer,
+        era_id,
+    } = *execution_state;
     let deploy_hash = deploy.deploy_hash;
+    trace!(?deploy_hash, ?era_id, "executing speculatively");
     let execute_request = ExecuteRequest::new(
         state_root_hash,
         block_time.millis(),
         vec![deploy],
         protocol_version,
-        PublicKey::System,
+        proposer.clone().unwrap_or(PublicKey::System),
+    );
+    let results = execute(
+        engine_state,
+        CorrelationId::new(),
+        metrics,
+        execute_request,
+        ExecutionKind::Speculative,
     );
-    let results = execute(engine_state, None, execute_request);
     results.map(|mut execution_results| {
         let len = execution_results.len();
         if len != 1 {
@@ -442,105 +2771,5005 @@ where
         } else {
             // We know it must be 1, we could unwrap and then wrap
             // with `Some(_)` but `pop_front` already returns an `Option`.
-            // We need to transform the `engine_state::ExecutionResult` into
-            // `casper_types::ExecutionResult` as well.
-            execution_results.pop_front().map(Into::into)
+            execution_results.pop_front()
+        }
+    })
+}
+
+fn execute<S>(
+    engine_state: &EngineState<S>,
+    correlation_id: CorrelationId,
+    metrics: Option<Arc<Metrics>>,
+    execute_request: ExecuteRequest,
+    kind: ExecutionKind,
+) -> Result<ExecutionResults, engine_state::Error>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    trace!(%correlation_id, ?execute_request, "execute");
+    let start = Instant::now();
+    let result = engine_state.run_execute(correlation_id, execute_request);
+    if let Some(metrics) = metrics {
+        metrics
+            .run_execute(kind)
+            .observe(start.elapsed().as_secs_f64());
+    }
+    trace!(?result, "execute result");
+    result
+}
+
+/// Executes the deploys of the batch, pairing each execution result with the hash of the deploy
+/// which produced it.
+fn execute_batch<S>(
+    engine_state: &EngineState<S>,
+    correlation_id: CorrelationId,
+    metrics: Option<Arc<Metrics>>,
+    batch_execute_request: BatchExecuteRequest,
+) -> Result<Vec<(DeployHash, EngineExecutionResult)>, BlockExecutionError>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let (execute_request, deploy_hashes) = batch_execute_request.destructure();
+    let execution_results = execute(
+        engine_state,
+        correlation_id,
+        metrics,
+        execute_request,
+        ExecutionKind::Block,
+    )?;
+    pair_execution_results(deploy_hashes, execution_results)
+}
+
+/// Pairs each execution result returned by the EE with the hash of the deploy which produced it.
+fn pair_execution_results(
+    deploy_hashes: Vec<DeployHash>,
+    execution_results: ExecutionResults,
+) -> Result<Vec<(DeployHash, EngineExecutionResult)>, BlockExecutionError> {
+    match (deploy_hashes.as_slice(), execution_results.len()) {
+        ([deploy_hash], 0) => {
+            return Err(BlockExecutionError::NoExecutionResult {
+                deploy_hash: *deploy_hash,
+            })
+        }
+        ([deploy_hash], count) if count > 1 => {
+            return Err(BlockExecutionError::MoreThanOneExecutionResult {
+                deploy_hash: *deploy_hash,
+                count,
+            })
+        }
+        (_, count) if count != deploy_hashes.len() => {
+            return Err(BlockExecutionError::ExecutionResultCountMismatch {
+                expected: deploy_hashes.len(),
+                actual: count,
+            })
+        }
+        _ => (),
+    }
+    // The EE returns the execution results in the same order as the deploys in the request.
+    Ok(deploy_hashes.into_iter().zip(execution_results).collect())
+}
+
+/// Concurrently executes up to one deploy per available CPU from the front of `deploy_items`,
+/// each against the current state of `scratch_state`, without committing their effects.
+///
+/// Returns the execution results of the longest run of leading deploys which do not conflict with
+/// each other, which is never empty. Only the first of these may have failed, as what the session
+/// of a failed deploy read is discarded by the EE, so its conflicts cannot be detected.
+#[allow(clippy::too_many_arguments)]
+fn execute_non_conflicting_deploys<'a>(
+    scratch_state: &EngineState<ScratchGlobalState>,
+    correlation_id: CorrelationId,
+    metrics: Option<Arc<Metrics>>,
+    state_root_hash: Digest,
+    block_time: u64,
+    protocol_version: ProtocolVersion,
+    proposer: &PublicKey,
+    deploy_items: impl Iterator<Item = &'a DeployItem>,
+    address_seed: Option<[u8; 32]>,
+    refund_account: Option<AccountHash>,
+) -> Result<Vec<(DeployHash, EngineExecutionResult)>, BlockExecutionError> {
+    let max_concurrency = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let window_results = thread::scope(|scope| {
+        let handles = deploy_items
+            .take(max_concurrency)
+            .map(|deploy_item| {
+                let batch_execute_request = BatchExecuteRequest::new(
+                    state_root_hash,
+                    block_time,
+                    vec![deploy_item.clone()],
+                    protocol_version,
+                    proposer.clone(),
+                )
+                .with_address_seed(address_seed)
+                .with_refund_account(refund_account);
+                let metrics = metrics.clone();
+                scope.spawn(move || {
+                    execute_batch(
+                        scratch_state,
+                        correlation_id,
+                        metrics,
+                        batch_execute_request,
+                    )
+                })
+            })
+            .collect_vec();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|error| panic::resume_unwind(error))
+            })
+            .collect_vec()
+    });
+
+    let mut tracking_copy = scratch_state
+        .tracking_copy(state_root_hash)?
+        .ok_or(engine_state::Error::RootNotFound(state_root_hash))?;
+    let mut non_conflicting_footprint = DeployFootprint::default();
+    let mut non_conflicting_results = vec![];
+    for window_result in window_results {
+        // Only the first deploy is guaranteed to have been executed against the state its
+        // predecessors leave behind, so an error from any other deploy is not conclusive.
+        let results = match window_result {
+            Ok(results) => results,
+            Err(error) if non_conflicting_results.is_empty() => return Err(error),
+            Err(_) => break,
+        };
+        for (deploy_hash, ee_execution_result) in results {
+            // A failed deploy is executed again once its predecessors' effects are committed.
+            if !non_conflicting_results.is_empty() && ee_execution_result.is_failure() {
+                return Ok(non_conflicting_results);
+            }
+            let effects = AdditiveMap::from(ee_execution_result.execution_journal().clone());
+            let footprint = DeployFootprint::new(&effects, |key| {
+                tracking_copy
+                    .read(correlation_id, key)
+                    .map_err(engine_state::Error::from)
+            })?;
+            if !non_conflicting_results.is_empty()
+                && footprint.conflicts_with(&non_conflicting_footprint)
+            {
+                return Ok(non_conflicting_results);
+            }
+            non_conflicting_footprint.merge(footprint);
+            non_conflicting_results.push((deploy_hash, ee_execution_result));
+        }
+    }
+    Ok(non_conflicting_results)
+}
+
+/// Commits the step at the end of an era, returning the step's result along with a summary of the
+/// validators it evicted, slashed and rewarded.
+#[allow(clippy::too_many_arguments)]
+fn commit_step<S>(
+    engine_state: &EngineState<S>,
+    correlation_id: CorrelationId,
+    maybe_metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    pre_state_root_hash: Digest,
+    era_report: &EraReport<PublicKey>,
+    era_end_timestamp_millis: u64,
+    next_era_id: EraId,
+    slash_equivocators: bool,
+    reward_strategy: Option<&dyn RewardStrategy>,
+    minimum_reward: Option<&MinimumReward>,
+    era_report_transform: Option<&dyn Fn(&EraReport<PublicKey>) -> EraReport<PublicKey>>,
+) -> Result<(StepSuccess, StepOutcomeSummary), StepError>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let era_report = transform_era_report(era_report, protocol_version, era_report_transform);
+    let mut step_request = create_step_request(
+        protocol_version,
+        pre_state_root_hash,
+        &era_report,
+        era_end_timestamp_millis,
+        next_era_id,
+        slash_equivocators,
+        reward_strategy,
+    );
+    let withheld = withhold_rewards_below_minimum(&mut step_request, minimum_reward);
+    let step_outcome_summary = StepOutcomeSummary {
+        evicted: step_request
+            .evict_items
+            .iter()
+            .map(|item| item.validator_id.clone())
+            .collect(),
+        slashed: step_request
+            .slash_items
+            .iter()
+            .map(|item| item.validator_id.clone())
+            .collect(),
+        rewarded: step_request
+            .reward_items
+            .iter()
+            .map(|item| (item.validator_id.clone(), U512::from(item.value)))
+            .collect(),
+        withheld,
+    };
+    let step_success = run_step(engine_state, correlation_id, maybe_metrics, step_request)?;
+    Ok((step_success, step_outcome_summary))
+}
+
+/// Commits the steps ending `era_id` and the eras following it in turn, one for each of the
+/// `era_reports` in order, returning the combined journal of the steps along with a summary of
+/// their outcomes.
+///
+/// Each step is committed on top of the post-state root hash of the previous one, with the rewards
+/// withheld by the previous step carried over.
+#[allow(clippy::too_many_arguments)]
+fn commit_steps<'a, S>(
+    engine_state: &EngineState<S>,
+    correlation_id: CorrelationId,
+    maybe_metrics: Option<Arc<Metrics>>,
+    protocol_version: ProtocolVersion,
+    pre_state_root_hash: Digest,
+    era_id: EraId,
+    era_reports: impl IntoIterator<Item = &'a EraReport<PublicKey>>,
+    era_end_timestamp_millis: u64,
+    slash_equivocators: bool,
+    reward_strategy: Option<&dyn RewardStrategy>,
+    minimum_reward: Option<&MinimumReward>,
+    era_report_transform: Option<&dyn Fn(&EraReport<PublicKey>) -> EraReport<PublicKey>>,
+) -> Result<(ExecutionJournal, StepOutcomeSummary), StepError>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let mut state_root_hash = pre_state_root_hash;
+    let mut ended_era_id = era_id;
+    let mut minimum_reward = minimum_reward.cloned();
+    let mut execution_journal = ExecutionJournal::default();
+    let mut maybe_step_outcome_summary: Option<StepOutcomeSummary> = None;
+    for era_report in era_reports {
+        let (step_success, step_outcome_summary) = commit_step(
+            engine_state,
+            correlation_id,
+            maybe_metrics.clone(),
+            protocol_version,
+            state_root_hash,
+            era_report,
+            era_end_timestamp_millis,
+            ended_era_id.successor(),
+            slash_equivocators,
+            reward_strategy,
+            minimum_reward.as_ref(),
+            era_report_transform,
+        )?;
+        state_root_hash = step_success.post_state_hash;
+        ended_era_id = ended_era_id.successor();
+        for entry in step_success.execution_journal {
+            execution_journal.push(entry);
+        }
+        if let Some(minimum_reward) = minimum_reward.as_mut() {
+            *minimum_reward = MinimumReward::new(
+                minimum_reward.minimum_reward(),
+                step_outcome_summary.withheld().clone(),
+            );
+        }
+        match maybe_step_outcome_summary.as_mut() {
+            Some(summary) => summary.merge(step_outcome_summary),
+            None => maybe_step_outcome_summary = Some(step_outcome_summary),
+        }
+    }
+    Ok((
+        execution_journal,
+        maybe_step_outcome_summary.unwrap_or_default(),
+    ))
+}
+
+/// Commits a step outside a switch block, evicting and slashing the given validators without
+/// rewarding any, e.g. to force the eviction of a misbehaving validator.
+///
+/// As in a step at the end of an era, the auction is run, determining the validators of
+/// `next_era_id` plus the auction delay. Committing maintenance steps must be allowed by the
+/// chainspec, which is checked by the [`ContractRuntime`](super::ContractRuntime), not here.
+pub fn commit_maintenance_step<S>(
+    engine_state: &EngineState<S>,
+    protocol_version: ProtocolVersion,
+    state_root_hash: Digest,
+    evict: Vec<PublicKey>,
+    slash: Vec<PublicKey>,
+    next_era_id: EraId,
+    era_end_timestamp: Timestamp,
+) -> Result<StepSuccess, StepError>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    let step_request = StepRequest {
+        pre_state_hash: state_root_hash,
+        protocol_version,
+        reward_items: vec![],
+        slash_items: slash.into_iter().map(SlashItem::new).collect(),
+        evict_items: evict.into_iter().map(EvictItem::new).collect(),
+        next_era_id,
+        era_end_timestamp_millis: era_end_timestamp.millis(),
+    };
+    run_step(engine_state, CorrelationId::new(), None, step_request)
+}
+
+/// Has the execution engine commit the step.
+fn run_step<S>(
+    engine_state: &EngineState<S>,
+    correlation_id: CorrelationId,
+    maybe_metrics: Option<Arc<Metrics>>,
+    step_request: StepRequest,
+) -> Result<StepSuccess, StepError>
+where
+    S: StateProvider + CommitProvider,
+    S::Error: Into<execution::Error>,
+{
+    trace!(%correlation_id, ?step_request, "step");
+    let start = Instant::now();
+    let result = engine_state.commit_step(correlation_id, step_request);
+    if let Some(metrics) = maybe_metrics {
+        let elapsed = start.elapsed().as_secs_f64();
+        metrics.commit_step.observe(elapsed);
+        metrics.latest_commit_step.set(elapsed);
+    }
+    trace!(?result, "step response");
+    result
+}
+
+/// Removes the reward items of the step request which, together with the reward carried over from
+/// the previous era, fall below the minimum reward, returning the withheld rewards.
+///
+/// Rewards are only withheld from protocol version 2.0.0. Carried over rewards are added to the
+/// rewards of the era, including those of validators which are not rewarded in this era.
+fn withhold_rewards_below_minimum(
+    step_request: &mut StepRequest,
+    minimum_reward: Option<&MinimumReward>,
+) -> BTreeMap<PublicKey, U512> {
+    let minimum_reward = match minimum_reward {
+        Some(minimum_reward)
+            if step_request.protocol_version >= MINIMUM_REWARD_PROTOCOL_VERSION =>
+        {
+            minimum_reward
+        }
+        _ => return BTreeMap::new(),
+    };
+
+    let mut rewards = minimum_reward.carried_over().clone();
+    for reward_item in step_request.reward_items.drain(..) {
+        *rewards.entry(reward_item.validator_id).or_default() += U512::from(reward_item.value);
+    }
+    let mut withheld = BTreeMap::new();
+    for (validator_id, reward) in rewards {
+        if reward < minimum_reward.minimum_reward() {
+            withheld.insert(validator_id, reward);
+        } else {
+            // A reward item can't hold more than `u64::MAX`, which only a carried over reward
+            // close to `u64::MAX` could exceed.
+            let value = cmp::min(reward, U512::from(u64::MAX)).as_u64();
+            step_request
+                .reward_items
+                .push(RewardItem::new(validator_id, value));
+        }
+    }
+    withheld
+}
+
+/// Returns the era report to create the step from, which is the one of the switch block unless
+/// rewritten by `era_report_transform`.
+fn transform_era_report<'a>(
+    era_report: &'a EraReport<PublicKey>,
+    protocol_version: ProtocolVersion,
+    era_report_transform: Option<&dyn Fn(&EraReport<PublicKey>) -> EraReport<PublicKey>>,
+) -> Cow<'a, EraReport<PublicKey>> {
+    match era_report_transform {
+        Some(era_report_transform) if protocol_version >= ERA_REPORT_TRANSFORM_PROTOCOL_VERSION => {
+            Cow::Owned(era_report_transform(era_report))
+        }
+        _ => Cow::Borrowed(era_report),
+    }
+}
+
+/// Returns the end of the era of the switch block `finalized_block` to pass to the step, which is
+/// the timestamp of the block unless overridden by `era_end_timestamp`.
+fn era_end_timestamp_millis(
+    finalized_block: &FinalizedBlock,
+    protocol_version: ProtocolVersion,
+    era_end_timestamp: Option<Timestamp>,
+) -> u64 {
+    match era_end_timestamp {
+        Some(era_end_timestamp)
+            if protocol_version >= ERA_END_TIMESTAMP_OVERRIDE_PROTOCOL_VERSION =>
+        {
+            era_end_timestamp.millis()
+        }
+        _ => finalized_block.timestamp().millis(),
+    }
+}
+
+/// Creates the step request for the end of an era.
+///
+/// If `slash_equivocators` is set, equivocators are slashed rather than evicted.  If a
+/// `reward_strategy` is given, it determines the reward of each validator in the era report.
+fn create_step_request(
+    protocol_version: ProtocolVersion,
+    pre_state_root_hash: Digest,
+    era_report: &EraReport<PublicKey>,
+    era_end_timestamp_millis: u64,
+    next_era_id: EraId,
+    slash_equivocators: bool,
+    reward_strategy: Option<&dyn RewardStrategy>,
+) -> StepRequest {
+    // Extract the rewards and the inactive validators if this is a switch block
+    let EraReport {
+        equivocators,
+        rewards,
+        inactive_validators,
+    } = era_report;
+
+    // The reward items are sorted by public key regardless of the iteration order of the rewards,
+    // as their order affects the step.
+    let mut reward_items: Vec<RewardItem> = rewards
+        .iter()
+        .map(|(vid, value)| {
+            let value = reward_strategy.map_or(*value, |strategy| strategy.reward(vid, *value));
+            RewardItem::new(vid.clone(), value)
+        })
+        .collect();
+    reward_items.sort_by(|lhs, rhs| lhs.validator_id.cmp(&rhs.validator_id));
+
+    // Note: The Casper Network does not slash, but another network could.  Unless equivocators
+    // are slashed, both inactive validators and equivocators are evicted.
+    let (slash_items, evicted_validators) = if slash_equivocators {
+        let slash_items = equivocators.iter().cloned().map(SlashItem::new).collect();
+        (
+            slash_items,
+            inactive_validators.iter().collect::<BTreeSet<_>>(),
+        )
+    } else {
+        let evicted_validators = inactive_validators.iter().chain(equivocators).collect();
+        (vec![], evicted_validators)
+    };
+    // A validator which is both inactive and an equivocator is evicted only once, and validators
+    // are evicted in the order of their public keys.
+    let evict_items = evicted_validators
+        .into_iter()
+        .cloned()
+        .map(EvictItem::new)
+        .collect();
+
+    StepRequest {
+        pre_state_hash: pre_state_root_hash,
+        protocol_version,
+        reward_items,
+        slash_items,
+        evict_items,
+        next_era_id,
+        era_end_timestamp_millis,
+    }
+}
+
+/// Computes the root hash for a Merkle tree constructed from the hashes of execution results.
+///
+/// NOTE: We're hashing vector of execution results, instead of just their hashes, b/c when a joiner
+/// node receives the chunks of *full data* it has to be able to verify it against the Merkle root.
+///
+/// The results are serialized exactly as a `Vec<ExecutionResult>` would be, without requiring them
+/// to be collected into one, and hashed with `scheme`.
+pub(super) fn compute_execution_results_checksum<'a>(
+    scheme: &dyn ExecutionResultsChecksum,
+    execution_results: impl ExactSizeIterator<Item = &'a ExecutionResult>,
+) -> Result<Digest, BlockCreationError> {
+    let mut builder = VecChecksumBuilder::new();
+    for execution_result in execution_results {
+        builder
+            .push(execution_result)
+            .map_err(BlockCreationError::BytesRepr)?;
+    }
+    Ok(builder.finalize_with(|bytes| scheme.checksum(bytes)))
+}
+
+/// The deploys of a block yet to be executed, converted into deploy items as they are pulled from
+/// their source.
+struct PendingDeploys<I> {
+    buffered: VecDeque<(DeployHeader, DeployItem)>,
+    source: I,
+}
+
+impl<I: Iterator<Item = Deploy>> PendingDeploys<I> {
+    fn new(source: I) -> Self {
+        PendingDeploys {
+            buffered: VecDeque::new(),
+            source,
+        }
+    }
+
+    /// Returns `true` if all deploys have been taken.
+    fn is_empty(&mut self) -> bool {
+        self.buffer(1);
+        self.buffered.is_empty()
+    }
+
+    /// Takes the next deploy.
+    fn pop_front(&mut self) -> Option<(DeployHeader, DeployItem)> {
+        self.buffer(1);
+        self.buffered.pop_front()
+    }
+
+    /// Drops the next `count` deploys, returning their hashes.
+    fn skip(&mut self, count: usize) -> Vec<DeployHash> {
+        let mut skipped = Vec::with_capacity(count);
+        for _ in 0..count {
+            match self.pop_front() {
+                Some((_, deploy_item)) => skipped.push(DeployHash::from(deploy_item.deploy_hash)),
+                None => break,
+            }
+        }
+        skipped
+    }
+
+    /// Pulls all remaining deploys from the source, returning them.
+    fn buffer_all(&mut self) -> &mut VecDeque<(DeployHeader, DeployItem)> {
+        self.buffer(usize::MAX);
+        &mut self.buffered
+    }
+
+    fn buffer(&mut self, count: usize) {
+        while self.buffered.len() < count {
+            match self.source.next() {
+                Some(deploy) => self
+                    .buffered
+                    .push_back((deploy.header().clone(), DeployItem::from(deploy))),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        fmt,
+        rc::Rc,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use prometheus::Registry;
+    use tempfile::TempDir;
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        registry::LookupSpan,
+        Layer,
+    };
+
+    use num_rational::Ratio;
+    use rand::{seq::SliceRandom, Rng};
+
+    use casper_execution_engine::{
+        core::engine_state::{
+            engine_config::RefundHandling, ChainspecRegistry, EngineConfigBuilder, ExecConfig,
+            ExecutableDeployItem, GetBidsRequest,
+        },
+        storage::{
+            error::Error as StorageError,
+            global_state::{in_memory::InMemoryGlobalState, StateReader},
+        },
+    };
+    use casper_hashing::ChunkWithProof;
+    use casper_types::{
+        bytesrepr::Bytes, runtime_args, system::AUCTION, testing::TestRng, RuntimeArgs, SecretKey,
+        TimeDiff,
+    };
+
+    use super::*;
+    use crate::{
+        components::contract_runtime::{
+            BatchedFlushes, BlockCheckpointing, Config, ContractRuntime,
+            DefaultExecutionResultsChecksum, DeployOrdering, ExecutionResultCache,
+            ExecutionResultsAccumulator, GasPriceOrdering, MaintenanceStepError, ReadThroughCache,
+            RefundDestination, StepPhase, TreasuryRefunds,
+        },
+        types::{BlockPayload, Chainspec, ChainspecRawBytes, Chunkable, DeployHashWithApprovals},
+        utils::{Loadable, RESOURCES_PATH},
+    };
+
+    /// A contract runtime with genesis committed, used to exercise deploy execution.
+    struct TestFixture {
+        contract_runtime: ContractRuntime,
+        chainspec: Chainspec,
+        post_genesis_state_root_hash: Digest,
+        _storage_dir: TempDir,
+    }
+
+    impl TestFixture {
+        fn new() -> Self {
+            Self::with_chainspec(|_| {})
+        }
+
+        /// Creates a fixture from the local chainspec as modified by `configure`.
+        fn with_chainspec(configure: impl FnOnce(&mut Chainspec)) -> Self {
+            let (mut chainspec, chainspec_raw_bytes) =
+                <(Chainspec, ChainspecRawBytes)>::from_resources("local");
+            configure(&mut chainspec);
+            let storage_dir = tempfile::tempdir().unwrap();
+            let config = Config {
+                max_global_state_size: Some(100 * 1024 * 1024),
+                ..Config::default()
+            };
+            let contract_runtime = ContractRuntime::new(
+                chainspec.protocol_version(),
+                storage_dir.path(),
+                &config,
+                chainspec.wasm_config,
+                chainspec.system_costs_config,
+                chainspec.core_config.max_associated_keys,
+                chainspec.core_config.max_runtime_call_stack_height,
+                chainspec.core_config.minimum_delegation_amount,
+                chainspec.protocol_config.activation_point,
+                chainspec.core_config.prune_batch_size,
+                chainspec.core_config.strict_argument_checking,
+                chainspec.core_config.vesting_schedule_period.millis(),
+                Some(chainspec.core_config.max_delegators_per_validator),
+                &Registry::new(),
+                chainspec.core_config.administrators.clone(),
+                chainspec.core_config.allow_auction_bids,
+                chainspec.core_config.allow_unrestricted_transfers,
+                chainspec.core_config.refund_handling,
+                chainspec.core_config.fee_handling,
+                chainspec.core_config.slash_equivocators,
+                chainspec.core_config.block_execution_gas_limit,
+                chainspec.core_config.max_execution_result_bytes,
+                chainspec.core_config.validator_floor,
+                chainspec.core_config.strict_block_execution,
+                Some(chainspec.deploy_config.max_deploys_per_block()),
+                chainspec.core_config.allow_maintenance_steps,
+                chainspec.core_config.validate_proposer,
+                chainspec.core_config.max_trie_depth,
+                chainspec.network_config.name.clone(),
+            )
+            .unwrap();
+            let post_genesis_state_root_hash = contract_runtime
+                .commit_genesis(&chainspec, &chainspec_raw_bytes)
+                .unwrap()
+                .post_state_hash;
+            TestFixture {
+                contract_runtime,
+                chainspec,
+                post_genesis_state_root_hash,
+                _storage_dir: storage_dir,
+            }
+        }
+
+        fn engine_state(&self) -> &EngineState<LmdbGlobalState> {
+            self.contract_runtime.engine_state()
+        }
+
+        /// Returns the secret key of the given genesis validator.
+        fn validator_secret_key(node_index: usize) -> SecretKey {
+            SecretKey::from_file(
+                RESOURCES_PATH
+                    .join("local")
+                    .join("secret_keys")
+                    .join(format!("node-{}.pem", node_index)),
+            )
+            .unwrap()
+        }
+
+        /// Returns the public key of the given genesis validator.
+        fn validator_public_key(node_index: usize) -> PublicKey {
+            PublicKey::from(&Self::validator_secret_key(node_index))
+        }
+
+        /// Creates a native transfer of the minimum amount from the main account of the given
+        /// genesis validator to a random public key.
+        fn transfer(&self, rng: &mut TestRng, node_index: usize) -> Deploy {
+            let amount = U512::from(self.chainspec.deploy_config.native_transfer_minimum_motes);
+            self.transfer_amount(rng, node_index, amount)
+        }
+
+        /// Creates a transfer of `amount` motes from the given validator to a new account.
+        fn transfer_amount(&self, rng: &mut TestRng, node_index: usize, amount: U512) -> Deploy {
+            self.transfer_with_gas_price(rng, node_index, amount, 1)
+        }
+
+        /// Creates a transfer of `amount` motes from the given validator to a new account, paying
+        /// the given gas price.
+        fn transfer_with_gas_price(
+            &self,
+            rng: &mut TestRng,
+            node_index: usize,
+            amount: U512,
+            gas_price: u64,
+        ) -> Deploy {
+            let secret_key = Self::validator_secret_key(node_index);
+            self.transfer_from(&secret_key, PublicKey::random(rng), amount, gas_price)
+        }
+
+        /// Creates a transfer of `amount` motes from the account of `secret_key` to `target`,
+        /// paying the given gas price.
+        fn transfer_from(
+            &self,
+            secret_key: &SecretKey,
+            target: PublicKey,
+            amount: U512,
+            gas_price: u64,
+        ) -> Deploy {
+            native_transfer(&self.chainspec, secret_key, target, amount, gas_price)
+        }
+
+        /// Executes the first block on top of the genesis state.
+        fn execute_block(
+            &self,
+            deploys: Vec<Deploy>,
+            era_report: Option<EraReport<PublicKey>>,
+        ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+            self.execute_block_with_options(deploys, era_report, BlockExecutionOptions::default())
+        }
+
+        /// Executes the first block on top of the genesis state using the given options.
+        fn execute_block_with_options(
+            &self,
+            deploys: Vec<Deploy>,
+            era_report: Option<EraReport<PublicKey>>,
+            options: BlockExecutionOptions<'_>,
+        ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+            self.execute_proposed_block(deploys, era_report, PublicKey::System, None, options)
+        }
+
+        /// Executes the first block on top of the genesis state using the given options, recording
+        /// it in `metrics`.
+        fn execute_block_with_metrics(
+            &self,
+            deploys: Vec<Deploy>,
+            era_report: Option<EraReport<PublicKey>>,
+            metrics: &Arc<Metrics>,
+            options: BlockExecutionOptions<'_>,
+        ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+            let metrics = Some(Arc::clone(metrics));
+            self.execute_proposed_block(deploys, era_report, PublicKey::System, metrics, options)
+        }
+
+        /// Executes the first block on top of the genesis state as proposed by `proposer`.
+        fn execute_proposed_block(
+            &self,
+            deploys: Vec<Deploy>,
+            era_report: Option<EraReport<PublicKey>>,
+            proposer: PublicKey,
+            metrics: Option<Arc<Metrics>>,
+            options: BlockExecutionOptions<'_>,
+        ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+            let (execution_pre_state, finalized_block) =
+                self.first_block(&deploys, era_report, proposer);
+            execute_finalized_block(
+                self.engine_state(),
+                metrics,
+                self.chainspec.protocol_version(),
+                execution_pre_state,
+                finalized_block,
+                deploys,
+                EraId::new(0),
+                0,
+                0,
+                options,
+            )
+        }
+
+        /// Simulates the first block on top of the genesis state using the given options.
+        fn simulate_block(
+            &self,
+            deploys: Vec<Deploy>,
+            options: BlockExecutionOptions<'_>,
+        ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+            let (execution_pre_state, finalized_block) =
+                self.first_block(&deploys, None, PublicKey::System);
+            simulate_finalized_block(
+                self.engine_state(),
+                None,
+                self.chainspec.protocol_version(),
+                execution_pre_state,
+                finalized_block,
+                deploys,
+                options,
+            )
+        }
+
+        /// Executes the first block on top of the genesis state, streaming the deploy results.
+        fn execute_block_streaming(
+            &self,
+            deploys: Vec<Deploy>,
+            options: BlockExecutionOptions<'_>,
+            on_deploy_result: &mut dyn FnMut(DeployExecutionInfo),
+        ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+            let (execution_pre_state, finalized_block) =
+                self.first_block(&deploys, None, PublicKey::System);
+            execute_finalized_block_streaming(
+                self.engine_state(),
+                None,
+                self.chainspec.protocol_version(),
+                execution_pre_state,
+                finalized_block,
+                deploys,
+                EraId::new(0),
+                0,
+                0,
+                options,
+                on_deploy_result,
+            )
+        }
+
+        /// Executes a non-switch block on top of `parent` under the given protocol version.
+        fn execute_child_block(
+            &self,
+            parent: &Block,
+            deploys: Vec<Deploy>,
+            protocol_version: ProtocolVersion,
+        ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+            self.execute_child_block_with_flushes(
+                parent,
+                deploys,
+                protocol_version,
+                None,
+                None,
+                None,
+            )
+        }
+
+        /// Executes a non-switch block on top of `parent`, flushing LMDB as `batched_flushes`
+        /// dictates and reading through `read_through_cache`.
+        fn execute_child_block_with_flushes(
+            &self,
+            parent: &Block,
+            deploys: Vec<Deploy>,
+            protocol_version: ProtocolVersion,
+            metrics: Option<Arc<Metrics>>,
+            batched_flushes: Option<&BatchedFlushes>,
+            read_through_cache: Option<&ReadThroughCache>,
+        ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+            let block_payload = BlockPayload::new(
+                vec![],
+                deploys.iter().map(DeployHashWithApprovals::from).collect(),
+                vec![],
+                true,
+            );
+            let finalized_block = FinalizedBlock::new(
+                block_payload,
+                None,
+                Timestamp::now(),
+                parent.header().era_id(),
+                parent.height() + 1,
+                PublicKey::System,
+            );
+            execute_finalized_block(
+                self.engine_state(),
+                metrics,
+                protocol_version,
+                ExecutionPreState::from_block_header(parent.header()),
+                finalized_block,
+                deploys,
+                EraId::new(0),
+                0,
+                0,
+                BlockExecutionOptions {
+                    slash_equivocators: self.chainspec.core_config.slash_equivocators,
+                    batched_flushes,
+                    read_through_cache,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+        }
+
+        /// Verifies the first block on top of the genesis state against the expected state root.
+        fn verify_block(
+            &self,
+            deploys: Vec<Deploy>,
+            expected_root: Digest,
+            reference_state_root_trace: Option<&[(StateRootCheckpoint, Digest)]>,
+        ) -> Result<BlockAndExecutionResults, BlockExecutionError> {
+            let (execution_pre_state, finalized_block) =
+                self.first_block(&deploys, None, PublicKey::System);
+            verify_finalized_block(
+                self.engine_state(),
+                None,
+                self.chainspec.protocol_version(),
+                execution_pre_state,
+                finalized_block,
+                deploys,
+                BlockExecutionOptions::default(),
+                expected_root,
+                reference_state_root_trace,
+            )
+        }
+
+        /// Returns the pre-state and the finalized block of the first block on top of the genesis
+        /// state.
+        fn first_block(
+            &self,
+            deploys: &[Deploy],
+            era_report: Option<EraReport<PublicKey>>,
+            proposer: PublicKey,
+        ) -> (ExecutionPreState, FinalizedBlock) {
+            let execution_pre_state = ExecutionPreState::new(
+                0,
+                self.post_genesis_state_root_hash,
+                BlockHash::default(),
+                Digest::default(),
+            );
+            let block_payload = BlockPayload::new(
+                vec![],
+                deploys.iter().map(DeployHashWithApprovals::from).collect(),
+                vec![],
+                true,
+            );
+            let finalized_block = FinalizedBlock::new(
+                block_payload,
+                era_report,
+                Timestamp::now(),
+                EraId::new(0),
+                0,
+                proposer,
+            );
+            (execution_pre_state, finalized_block)
+        }
+
+        /// Creates a request executing the given deploy items on top of `state_root_hash`.
+        fn batch_execute_request(
+            &self,
+            state_root_hash: Digest,
+            deploy_items: Vec<DeployItem>,
+        ) -> BatchExecuteRequest {
+            BatchExecuteRequest::new(
+                state_root_hash,
+                Timestamp::now().millis(),
+                deploy_items,
+                self.chainspec.protocol_version(),
+                PublicKey::System,
+            )
+        }
+    }
+
+    /// Creates a native transfer of `amount` motes from the account of `secret_key` to `target`
+    /// on the chain of `chainspec`, paying the given gas price.
+    fn native_transfer(
+        chainspec: &Chainspec,
+        secret_key: &SecretKey,
+        target: PublicKey,
+        amount: U512,
+        gas_price: u64,
+    ) -> Deploy {
+        let payment = ExecutableDeployItem::ModuleBytes {
+            module_bytes: Bytes::new(),
+            args: runtime_args! {
+              "amount" => U512::from(chainspec.system_costs_config.wasmless_transfer_cost()),
+            },
+        };
+        let session = ExecutableDeployItem::Transfer {
+            args: runtime_args! {
+              "amount" => amount,
+              "target" => target,
+              "id" => Some(9_u64),
+            },
+        };
+        Deploy::new(
+            Timestamp::now(),
+            TimeDiff::from_seconds(100),
+            gas_price,
+            vec![],
+            chainspec.network_config.name.clone(),
+            payment,
+            session,
+            secret_key,
+            None,
+        )
+    }
+
+    /// A fixture executing deploys against an in-memory global state, so that block execution can
+    /// be tested without an LMDB environment on disk.
+    ///
+    /// As `execute_finalized_block` writes the block to LMDB, the fixture instead executes and
+    /// commits the deploys one after the other through the functions it uses for that, which are
+    /// generic over the global state.
+    struct InMemoryFixture {
+        engine_state: EngineState<InMemoryGlobalState>,
+        chainspec: Chainspec,
+        post_genesis_state_root_hash: Digest,
+    }
+
+    impl InMemoryFixture {
+        fn new() -> Self {
+            let (chainspec, chainspec_raw_bytes) =
+                <(Chainspec, ChainspecRawBytes)>::from_resources("local");
+            let core_config = &chainspec.core_config;
+            let engine_config = EngineConfigBuilder::new()
+                .with_max_associated_keys(core_config.max_associated_keys)
+                .with_max_runtime_call_stack_height(core_config.max_runtime_call_stack_height)
+                .with_minimum_delegation_amount(core_config.minimum_delegation_amount)
+                .with_strict_argument_checking(core_config.strict_argument_checking)
+                .with_vesting_schedule_period_millis(core_config.vesting_schedule_period.millis())
+                .with_max_delegators_per_validator(Some(core_config.max_delegators_per_validator))
+                .with_wasm_config(chainspec.wasm_config)
+                .with_system_config(chainspec.system_costs_config)
+                .with_administrative_accounts(core_config.administrators.clone())
+                .with_allow_auction_bids(core_config.allow_auction_bids)
+                .with_allow_unrestricted_transfers(core_config.allow_unrestricted_transfers)
+                .with_refund_handling(core_config.refund_handling)
+                .with_fee_handling(core_config.fee_handling)
+                .build();
+            let engine_state =
+                EngineState::new(InMemoryGlobalState::empty().unwrap(), engine_config);
+
+            let chainspec_registry = ChainspecRegistry::new_with_genesis(
+                chainspec_raw_bytes.chainspec_bytes(),
+                chainspec_raw_bytes.maybe_genesis_accounts_bytes().unwrap(),
+            );
+            let post_genesis_state_root_hash = engine_state
+                .commit_genesis(
+                    CorrelationId::new(),
+                    chainspec.hash(),
+                    chainspec.protocol_version(),
+                    &ExecConfig::from(&chainspec),
+                    chainspec_registry,
+                )
+                .unwrap()
+                .post_state_hash;
+            InMemoryFixture {
+                engine_state,
+                chainspec,
+                post_genesis_state_root_hash,
+            }
+        }
+
+        /// Executes the deploys of a block on top of the genesis state, each against the effects
+        /// of those preceding it, as `execute_finalized_block` does.
+        ///
+        /// Returns the resulting state root hash along with the JSON execution result, the outcome
+        /// and the cost of each deploy.
+        fn execute_deploys(
+            &self,
+            deploys: Vec<Deploy>,
+        ) -> Result<
+            (
+                Digest,
+                Vec<(DeployHash, ExecutionResult, DeployOutcome, U512)>,
+            ),
+            BlockExecutionError,
+        > {
+            let block_time = Timestamp::now().millis();
+            let mut state_root_hash = self.post_genesis_state_root_hash;
+            let mut results = Vec::with_capacity(deploys.len());
+            for deploy in deploys {
+                let batch_execute_request = BatchExecuteRequest::new(
+                    state_root_hash,
+                    block_time,
+                    vec![DeployItem::from(deploy)],
+                    self.chainspec.protocol_version(),
+                    PublicKey::System,
+                );
+                let execution_results = execute_batch(
+                    &self.engine_state,
+                    CorrelationId::new(),
+                    None,
+                    batch_execute_request,
+                )?;
+                for (deploy_hash, ee_execution_result) in execution_results {
+                    let cost = ee_execution_result.cost().value();
+                    let (post_state_root_hash, result, outcome) = commit_execution_result(
+                        &self.engine_state,
+                        CorrelationId::new(),
+                        None,
+                        state_root_hash,
+                        deploy_hash,
+                        ee_execution_result,
+                        None,
+                    )?;
+                    state_root_hash = post_state_root_hash;
+                    results.push((deploy_hash, result, outcome, cost));
+                }
+            }
+            Ok((state_root_hash, results))
+        }
+    }
+
+    #[test]
+    fn should_execute_transfer_in_memory() {
+        let fixture = InMemoryFixture::new();
+        let target = PublicKey::random(&mut crate::new_rng());
+        let amount = U512::from(
+            fixture
+                .chainspec
+                .deploy_config
+                .native_transfer_minimum_motes,
+        );
+        let deploy = native_transfer(
+            &fixture.chainspec,
+            &TestFixture::validator_secret_key(1),
+            target.clone(),
+            amount,
+            1,
+        );
+        let deploy_hash = *deploy.hash();
+
+        let (state_root_hash, results) = fixture.execute_deploys(vec![deploy]).unwrap();
+        assert_ne!(state_root_hash, fixture.post_genesis_state_root_hash);
+        assert_eq!(results.len(), 1);
+        let (executed_deploy_hash, _, outcome, cost) = &results[0];
+        assert_eq!(*executed_deploy_hash, deploy_hash);
+        assert_eq!(*outcome, DeployOutcome::Success);
+        assert!(!cost.is_zero());
+
+        // The transfer created the target's account.
+        let query_request = QueryRequest::new(
+            state_root_hash,
+            Key::Account(target.to_account_hash()),
+            vec![],
+        );
+        assert!(matches!(
+            fixture
+                .engine_state
+                .run_query(CorrelationId::new(), query_request)
+                .unwrap(),
+            QueryResult::Success { .. }
+        ));
+    }
+
+    #[test]
+    fn should_map_batched_execution_results_to_deploy_hashes() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let pre_state_root_hash = fixture.post_genesis_state_root_hash;
+
+        // Transfers from distinct accounts are independent of each other, so executing them
+        // against the same parent state is equivalent to executing them one after another.
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let deploy_hashes = deploys.iter().map(|deploy| *deploy.hash()).collect_vec();
+        let deploy_items = deploys.into_iter().map(DeployItem::from).collect_vec();
+
+        let batch_execute_request =
+            fixture.batch_execute_request(pre_state_root_hash, deploy_items.clone());
+        let results = execute_batch(
+            fixture.engine_state(),
+            CorrelationId::new(),
+            None,
+            batch_execute_request,
+        )
+        .unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|(deploy_hash, _)| *deploy_hash)
+                .collect_vec(),
+            deploy_hashes
+        );
+        let mut batched_state_root_hash = pre_state_root_hash;
+        let mut batched_results = vec![];
+        for (deploy_hash, ee_execution_result) in results {
+            let (post_state_root_hash, result, _) = commit_execution_result(
+                fixture.engine_state(),
+                CorrelationId::new(),
+                None,
+                batched_state_root_hash,
+                deploy_hash,
+                ee_execution_result,
+                None,
+            )
+            .unwrap();
+            batched_results.push((deploy_hash, result));
+            batched_state_root_hash = post_state_root_hash;
+        }
+
+        let mut state_root_hash = pre_state_root_hash;
+        let mut sequential_results = vec![];
+        for deploy_item in deploy_items {
+            let batch_execute_request =
+                fixture.batch_execute_request(state_root_hash, vec![deploy_item]);
+            let results = execute_batch(
+                fixture.engine_state(),
+                CorrelationId::new(),
+                None,
+                batch_execute_request,
+            )
+            .unwrap();
+            for (deploy_hash, ee_execution_result) in results {
+                let (post_state_root_hash, result, _) = commit_execution_result(
+                    fixture.engine_state(),
+                    CorrelationId::new(),
+                    None,
+                    state_root_hash,
+                    deploy_hash,
+                    ee_execution_result,
+                    None,
+                )
+                .unwrap();
+                sequential_results.push((deploy_hash, result));
+                state_root_hash = post_state_root_hash;
+            }
+        }
+
+        assert_eq!(batched_results, sequential_results);
+        assert_eq!(batched_state_root_hash, state_root_hash);
+    }
+
+    #[test]
+    fn should_execute_failing_deploy_against_effects_of_preceding_deploys() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let amount = U512::from(
+            fixture
+                .chainspec
+                .deploy_config
+                .native_transfer_minimum_motes,
+        );
+        let secret_key = SecretKey::random(&mut rng);
+        // The session of the second deploy reads the balance written by the first, and fails as
+        // the balance left after paying for the transfer does not cover it.
+        let deploys = vec![
+            fixture.transfer_from(
+                &TestFixture::validator_secret_key(1),
+                PublicKey::from(&secret_key),
+                amount,
+                1,
+            ),
+            fixture.transfer_from(&secret_key, PublicKey::random(&mut rng), amount, 1),
+        ];
+
+        let block_run = fixture
+            .execute_block_with_options(
+                deploys.clone(),
+                None,
+                BlockExecutionOptions {
+                    trace_state_roots: true,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            block_run.execution_results[0].outcome,
+            DeployOutcome::Success
+        );
+        assert!(matches!(
+            block_run.execution_results[1].outcome,
+            DeployOutcome::InsufficientBalance { .. }
+        ));
+
+        // Each deploy is executed on top of the effects of its predecessor.
+        let mut state_root_hash = fixture.post_genesis_state_root_hash;
+        let mut sequential_results = vec![];
+        for deploy in deploys {
+            let batch_execute_request =
+                fixture.batch_execute_request(state_root_hash, vec![DeployItem::from(deploy)]);
+            let results = execute_batch(
+                fixture.engine_state(),
+                CorrelationId::new(),
+                None,
+                batch_execute_request,
+            )
+            .unwrap();
+            for (deploy_hash, ee_execution_result) in results {
+                let (post_state_root_hash, result, _) = commit_execution_result(
+                    fixture.engine_state(),
+                    CorrelationId::new(),
+                    None,
+                    state_root_hash,
+                    deploy_hash,
+                    ee_execution_result,
+                    None,
+                )
+                .unwrap();
+                sequential_results.push((deploy_hash, result));
+                state_root_hash = post_state_root_hash;
+            }
+        }
+        assert_eq!(
+            block_run
+                .execution_results
+                .iter()
+                .map(|info| (info.deploy_hash, info.result.clone()))
+                .collect_vec(),
+            sequential_results
+        );
+        let post_deploys_root = block_run
+            .maybe_state_root_trace
+            .iter()
+            .flatten()
+            .filter(|(checkpoint, _)| matches!(checkpoint, StateRootCheckpoint::Deploy(_)))
+            .map(|(_, root)| *root)
+            .last();
+        assert_eq!(post_deploys_root, Some(state_root_hash));
+    }
+
+    #[test]
+    fn should_pass_each_committed_transform_to_effect_observer() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let pre_state_root_hash = fixture.post_genesis_state_root_hash;
+        let deploy = fixture.transfer(&mut rng, 1);
+        let deploy_hash = *deploy.hash();
+
+        let batch_execute_request =
+            fixture.batch_execute_request(pre_state_root_hash, vec![DeployItem::from(deploy)]);
+        let results = execute_batch(
+            fixture.engine_state(),
+            CorrelationId::new(),
+            None,
+            batch_execute_request,
+        )
+        .unwrap();
+        let expected_transforms = results[0]
+            .1
+            .execution_journal()
+            .iter()
+            .cloned()
+            .collect_vec();
+        assert!(!expected_transforms.is_empty());
+
+        let observed_transforms = RefCell::new(vec![]);
+        let effect_observer =
+            |observed_deploy_hash: &DeployHash, key: &Key, transform: &Transform| {
+                assert_eq!(*observed_deploy_hash, deploy_hash);
+                observed_transforms
+                    .borrow_mut()
+                    .push((*key, transform.clone()));
+            };
+        let (deploy_hash, ee_execution_result) = results.into_iter().next().unwrap();
+        commit_execution_result(
+            fixture.engine_state(),
+            CorrelationId::new(),
+            None,
+            pre_state_root_hash,
+            deploy_hash,
+            ee_execution_result,
+            Some(&effect_observer),
+        )
+        .unwrap();
+        assert_eq!(observed_transforms.into_inner(), expected_transforms);
+    }
+
+    #[test]
+    fn should_report_deploy_and_key_of_conflicting_effect() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploy_hash = DeployHash::random(&mut rng);
+        // The main account of a genesis validator cannot be added to.
+        let account_key = Key::Account(TestFixture::validator_public_key(1).to_account_hash());
+        let ee_execution_result = EngineExecutionResult::Success {
+            transfers: vec![],
+            cost: Gas::default(),
+            execution_journal: ExecutionJournal::new(vec![
+                (
+                    Key::Hash([1; 32]),
+                    Transform::Write(StoredValue::CLValue(CLValue::unit())),
+                ),
+                (account_key, Transform::AddInt32(1)),
+            ]),
+        };
+
+        let error = commit_execution_result(
+            fixture.engine_state(),
+            CorrelationId::new(),
+            None,
+            fixture.post_genesis_state_root_hash,
+            deploy_hash,
+            ee_execution_result,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::EffectApplyConflict {
+                deploy_hash: conflicting_deploy_hash,
+                key,
+                ..
+            } if conflicting_deploy_hash == deploy_hash && key == account_key
+        ));
+    }
+
+    #[test]
+    fn should_abort_block_on_failed_deploy_in_strict_mode() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        // No validator can afford to transfer this much.
+        let failing_deploy = fixture.transfer_amount(&mut rng, 2, U512::MAX);
+        let deploys = vec![
+            fixture.transfer(&mut rng, 1),
+            failing_deploy.clone(),
+            fixture.transfer(&mut rng, 3),
+        ];
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+
+        let error = fixture
+            .execute_block_with_metrics(
+                deploys.clone(),
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    strict: true,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::DeployFailedInStrictMode { deploy_hash, .. }
+                if deploy_hash == *failing_deploy.hash()
+        ));
+        // The deploys following the failed one were not executed, and nothing was written.
+        assert_eq!(metrics.deploys_executed_total.get(), 1);
+        assert_eq!(metrics.scratch_write_to_db.get_sample_count(), 0);
+
+        // Without strict mode, the failed deploy is included in the block.
+        let BlockAndExecutionResults {
+            execution_results, ..
+        } = fixture.execute_block(deploys, None).unwrap();
+        assert_eq!(execution_results.len(), 3);
+        assert!(matches!(
+            execution_results[1].outcome,
+            DeployOutcome::InsufficientBalance { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_execute_block_asynchronously() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = vec![fixture.transfer(&mut rng, 1), fixture.transfer(&mut rng, 2)];
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+        let execute_async = |execution_pre_state| {
+            execute_finalized_block_async(
+                Arc::clone(fixture.contract_runtime.engine_state()),
+                None,
+                fixture.chainspec.protocol_version(),
+                execution_pre_state,
+                finalized_block.clone(),
+                deploys.clone(),
+                EraId::new(0),
+                0,
+                0,
+                BlockExecutionOptions::default,
+            )
+        };
+
+        let async_results = execute_async(execution_pre_state.clone()).await.unwrap();
+        let sync_results = execute_finalized_block(
+            fixture.engine_state(),
+            None,
+            fixture.chainspec.protocol_version(),
+            execution_pre_state.clone(),
+            finalized_block.clone(),
+            deploys.clone(),
+            EraId::new(0),
+            0,
+            0,
+            BlockExecutionOptions {
+                slash_equivocators: fixture.chainspec.core_config.slash_equivocators,
+                ..BlockExecutionOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(async_results.block, sync_results.block);
+        assert_eq!(
+            async_results.execution_results.to_bytes().unwrap(),
+            sync_results.execution_results.to_bytes().unwrap()
+        );
+
+        // Errors are returned to the calling task.
+        let wrong_height_pre_state = ExecutionPreState::new(
+            1,
+            execution_pre_state.pre_state_root_hash,
+            execution_pre_state.parent_hash,
+            execution_pre_state.parent_seed,
+        );
+        assert!(matches!(
+            execute_async(wrong_height_pre_state).await,
+            Err(BlockExecutionError::WrongBlockHeight { .. })
+        ));
+    }
+
+    #[test]
+    fn should_reject_block_with_too_many_deploys() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|validator_index| fixture.transfer(&mut rng, validator_index))
+            .collect_vec();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+
+        // The block is rejected before any deploy is executed.
+        let error = fixture
+            .execute_block_with_metrics(
+                deploys.clone(),
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    max_deploys_per_block: Some(2),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::TooManyDeploys { count: 3, max: 2 }
+        ));
+        assert_eq!(metrics.deploys_executed_total.get(), 0);
+
+        // A block with exactly the maximum number of deploys is executed.
+        let BlockAndExecutionResults {
+            execution_results, ..
+        } = fixture
+            .execute_block_with_options(
+                deploys,
+                None,
+                BlockExecutionOptions {
+                    max_deploys_per_block: Some(3),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(execution_results.len(), 3);
+    }
+
+    #[test]
+    fn should_expose_outcome_of_each_deploy() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let succeeding_deploy = fixture.transfer(&mut rng, 1);
+        // No validator can afford to transfer this much.
+        let failing_deploy = fixture.transfer_amount(&mut rng, 2, U512::MAX);
+
+        let BlockAndExecutionResults {
+            execution_results, ..
+        } = fixture
+            .execute_block(vec![succeeding_deploy, failing_deploy], None)
+            .unwrap();
+        assert_eq!(execution_results.len(), 2);
+        assert_eq!(execution_results[0].outcome, DeployOutcome::Success);
+        assert!(matches!(
+            execution_results[0].result,
+            ExecutionResult::Success { .. }
+        ));
+        match (&execution_results[1].outcome, &execution_results[1].result) {
+            (
+                DeployOutcome::InsufficientBalance { error_message },
+                ExecutionResult::Failure {
+                    error_message: json_error_message,
+                    ..
+                },
+            ) => assert_eq!(error_message, json_error_message),
+            other => panic!("expected the second deploy to fail, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_classify_deploy_from_underfunded_account() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let amount = U512::from(
+            fixture
+                .chainspec
+                .deploy_config
+                .native_transfer_minimum_motes,
+        );
+        let underfunded_secret_key = SecretKey::random(&mut rng);
+        // The new account receives the minimum transfer amount, so after paying for its own
+        // transfer it cannot afford to pass it on.
+        let funding_deploy = fixture.transfer_from(
+            &TestFixture::validator_secret_key(1),
+            PublicKey::from(&underfunded_secret_key),
+            amount,
+            1,
+        );
+        let underfunded_deploy = fixture.transfer_from(
+            &underfunded_secret_key,
+            PublicKey::random(&mut rng),
+            amount,
+            1,
+        );
+        // Deploys from accounts absent from global state fail for another reason.
+        let unauthorized_deploy = Deploy::random_valid_native_transfer(&mut rng);
+
+        let BlockAndExecutionResults {
+            execution_results, ..
+        } = fixture
+            .execute_block(
+                vec![funding_deploy, underfunded_deploy, unauthorized_deploy],
+                None,
+            )
+            .unwrap();
+        assert_eq!(execution_results.len(), 3);
+        assert_eq!(execution_results[0].outcome, DeployOutcome::Success);
+        match (&execution_results[1].outcome, &execution_results[1].result) {
+            (
+                DeployOutcome::InsufficientBalance { error_message },
+                ExecutionResult::Failure {
+                    error_message: json_error_message,
+                    ..
+                },
+            ) => assert_eq!(error_message, json_error_message),
+            other => panic!("expected insufficient balance, got {:?}", other),
+        }
+        assert!(matches!(
+            execution_results[2].outcome,
+            DeployOutcome::Failure { .. }
+        ));
+    }
+
+    #[test]
+    fn should_count_effects_of_each_deploy() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        // Deploys from accounts absent from global state only read it, and are not charged.
+        let deploys = vec![
+            fixture.transfer(&mut rng, 1),
+            Deploy::random_valid_native_transfer(&mut rng),
+        ];
+
+        let results = fixture
+            .execute_block_with_options(
+                deploys,
+                None,
+                BlockExecutionOptions {
+                    capture_journals: true,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        let execution_results = &results.execution_results;
+        assert_eq!(execution_results.len(), 2);
+        assert!(execution_results[0].effect_count() > 0);
+        assert_eq!(execution_results[1].effect_count(), 0);
+
+        // The effect count covers the keys committed for the deploy, except for those only read.
+        for (deploy_execution_info, captured_journal) in execution_results
+            .iter()
+            .zip(results.captured_journals().unwrap())
+        {
+            let committed_effect_count = captured_journal
+                .effects()
+                .iter()
+                .filter(|(_, transform)| **transform != Transform::Identity)
+                .count();
+            assert_eq!(deploy_execution_info.effect_count(), committed_effect_count);
+        }
+    }
+
+    #[test]
+    fn should_expose_cost_of_each_deploy() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+
+        let BlockAndExecutionResults {
+            execution_results, ..
+        } = fixture.execute_block(deploys, None).unwrap();
+        assert_eq!(execution_results.len(), 3);
+
+        let total_cost = execution_results
+            .iter()
+            .fold(U512::zero(), |total, info| total + info.cost);
+        let expected_total_cost =
+            execution_results
+                .iter()
+                .fold(U512::zero(), |total, info| match &info.result {
+                    ExecutionResult::Success { cost, .. }
+                    | ExecutionResult::Failure { cost, .. } => total + *cost,
+                });
+        assert!(!total_cost.is_zero());
+        assert_eq!(total_cost, expected_total_cost);
+    }
+
+    #[test]
+    fn should_trace_state_roots_if_enabled() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let deploy_hashes = deploys.iter().map(|deploy| *deploy.hash()).collect_vec();
+
+        let untraced = fixture.execute_block(deploys.clone(), None).unwrap();
+        assert!(untraced.maybe_state_root_trace.is_none());
+
+        for era_report in [None, Some(EraReport::default())] {
+            let is_switch_block = era_report.is_some();
+            let BlockAndExecutionResults {
+                block,
+                maybe_state_root_trace,
+                ..
+            } = fixture
+                .execute_block_with_options(
+                    deploys.clone(),
+                    era_report,
+                    BlockExecutionOptions {
+                        trace_state_roots: true,
+                        ..BlockExecutionOptions::default()
+                    },
+                )
+                .unwrap();
+            let state_root_trace = maybe_state_root_trace.unwrap();
+
+            let mut expected_checkpoints = deploy_hashes
+                .iter()
+                .map(|deploy_hash| StateRootCheckpoint::Deploy(*deploy_hash))
+                .collect_vec();
+            expected_checkpoints.push(StateRootCheckpoint::ChecksumRegistry);
+            if is_switch_block {
+                expected_checkpoints.push(StateRootCheckpoint::Step);
+            }
+            assert_eq!(
+                state_root_trace
+                    .iter()
+                    .map(|(checkpoint, _)| *checkpoint)
+                    .collect_vec(),
+                expected_checkpoints
+            );
+
+            // Every deploy changes global state.
+            let state_roots = state_root_trace
+                .iter()
+                .map(|(_, state_root_hash)| *state_root_hash)
+                .collect_vec();
+            assert!(state_roots.iter().all_unique());
+            assert_eq!(state_roots.last(), Some(block.state_root_hash()));
+        }
+    }
+
+    #[test]
+    fn should_capture_journals_if_requested() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        // No validator can afford to transfer this much.
+        let deploys = vec![
+            fixture.transfer(&mut rng, 1),
+            fixture.transfer_amount(&mut rng, 2, U512::MAX),
+        ];
+
+        let uncaptured = fixture.execute_block(deploys.clone(), None).unwrap();
+        assert!(uncaptured.captured_journals().is_none());
+
+        let results = fixture
+            .execute_block_with_options(
+                deploys.clone(),
+                None,
+                BlockExecutionOptions {
+                    trace_state_roots: true,
+                    capture_journals: true,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        let captured_journals = results.captured_journals().unwrap();
+        assert_eq!(
+            captured_journals
+                .iter()
+                .map(CapturedJournal::deploy_hash)
+                .collect_vec(),
+            deploys.iter().map(Deploy::hash).collect_vec()
+        );
+        assert_eq!(*captured_journals[0].outcome(), DeployOutcome::Success);
+        assert!(matches!(
+            captured_journals[1].outcome(),
+            DeployOutcome::InsufficientBalance { .. }
+        ));
+
+        // Committing the captured effects in order reproduces the state root after each deploy.
+        let state_root_trace = results.maybe_state_root_trace.as_ref().unwrap();
+        let mut state_root_hash = fixture.post_genesis_state_root_hash;
+        for (captured_journal, (checkpoint, traced_state_root_hash)) in
+            captured_journals.iter().zip(state_root_trace)
+        {
+            assert_eq!(
+                *checkpoint,
+                StateRootCheckpoint::Deploy(*captured_journal.deploy_hash())
+            );
+            state_root_hash = commit_transforms(
+                fixture.engine_state(),
+                CorrelationId::new(),
+                None,
+                state_root_hash,
+                captured_journal.effects().clone(),
+            )
+            .unwrap();
+            assert_eq!(state_root_hash, *traced_state_root_hash);
+        }
+    }
+
+    #[test]
+    fn should_execute_deploys_in_order_of_deploy_ordering() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let amount = U512::from(
+            fixture
+                .chainspec
+                .deploy_config
+                .native_transfer_minimum_motes,
+        );
+        let deploys = vec![
+            fixture.transfer_with_gas_price(&mut rng, 1, amount, 1),
+            fixture.transfer_with_gas_price(&mut rng, 2, amount, 3),
+            fixture.transfer_with_gas_price(&mut rng, 3, amount, 2),
+        ];
+        let expected_order = vec![deploys[1].hash(), deploys[2].hash(), deploys[0].hash()];
+
+        // The ordering only depends on the deploys, not on the order they are given in.
+        for _ in 0..10 {
+            let mut shuffled = deploys.clone();
+            shuffled.shuffle(&mut rng);
+            GasPriceOrdering.order(&mut shuffled);
+            assert_eq!(
+                shuffled.iter().map(Deploy::hash).collect_vec(),
+                expected_order
+            );
+        }
+
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+        let execute = |deploy_ordering: Option<&dyn DeployOrdering>| {
+            execute_finalized_block(
+                fixture.engine_state(),
+                None,
+                fixture.chainspec.protocol_version(),
+                execution_pre_state.clone(),
+                finalized_block.clone(),
+                deploys.clone(),
+                EraId::new(0),
+                0,
+                0,
+                BlockExecutionOptions {
+                    slash_equivocators: fixture.chainspec.core_config.slash_equivocators,
+                    deploy_ordering,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let unordered = execute(None);
+        assert_eq!(
+            unordered
+                .execution_results
+                .iter()
+                .map(|deploy_execution_info| &deploy_execution_info.deploy_hash)
+                .collect_vec(),
+            deploys.iter().map(Deploy::hash).collect_vec()
+        );
+
+        let ordered = execute(Some(&GasPriceOrdering));
+        assert_eq!(
+            ordered
+                .execution_results
+                .iter()
+                .map(|deploy_execution_info| &deploy_execution_info.deploy_hash)
+                .collect_vec(),
+            expected_order
+        );
+        // The approvals hashes stay in the order of the block.
+        assert_eq!(ordered.approvals_hashes, unordered.approvals_hashes);
+
+        // Executing the block again with the same ordering yields the same state root hash.
+        let reordered = execute(Some(&GasPriceOrdering));
+        assert_eq!(
+            reordered.block.state_root_hash(),
+            ordered.block.state_root_hash()
+        );
+    }
+
+    #[test]
+    fn should_count_total_gas_consumed_across_blocks() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+
+        let mut expected_total_gas = U512::zero();
+        for deploy_count in [2, 3] {
+            let deploys = (1..=deploy_count)
+                .map(|node_index| fixture.transfer(&mut rng, node_index))
+                .collect_vec();
+            let BlockAndExecutionResults {
+                execution_results, ..
+            } = fixture
+                .execute_block_with_metrics(
+                    deploys,
+                    None,
+                    &metrics,
+                    BlockExecutionOptions {
+                        ..BlockExecutionOptions::default()
+                    },
+                )
+                .unwrap();
+            assert_eq!(execution_results.len(), deploy_count);
+            for execution_info in &execution_results {
+                assert!(!execution_info.cost.is_zero());
+                expected_total_gas += execution_info.cost;
+            }
+            assert_eq!(
+                metrics.total_gas_consumed.get() as u64,
+                expected_total_gas.as_u64()
+            );
+        }
+    }
+
+    #[test]
+    fn should_count_succeeded_and_failed_deploys() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        // The account of a random deploy does not exist, so its execution fails.
+        let deploys = vec![
+            fixture.transfer(&mut rng, 1),
+            Deploy::random_valid_native_transfer(&mut rng),
+        ];
+
+        let BlockAndExecutionResults {
+            execution_results, ..
+        } = fixture
+            .execute_block_with_metrics(
+                deploys,
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(execution_results[0].outcome, DeployOutcome::Success);
+        assert!(matches!(
+            execution_results[1].outcome,
+            DeployOutcome::Failure { .. }
+        ));
+        assert_eq!(metrics.deploys_succeeded_total.get(), 1);
+        assert_eq!(metrics.deploys_failed_total.get(), 1);
+    }
+
+    #[test]
+    fn should_serve_reexecuted_deploys_from_execution_result_cache() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let cache = ExecutionResultCache::new(NonZeroUsize::new(10).unwrap());
+
+        let execute = || {
+            execute_finalized_block(
+                fixture.engine_state(),
+                Some(Arc::clone(&metrics)),
+                fixture.chainspec.protocol_version(),
+                execution_pre_state.clone(),
+                finalized_block.clone(),
+                deploys.clone(),
+                EraId::new(0),
+                0,
+                0,
+                BlockExecutionOptions {
+                    slash_equivocators: fixture.chainspec.core_config.slash_equivocators,
+                    execution_result_cache: Some(&cache),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let first = execute();
+        assert_eq!(metrics.execution_result_cache_hits.get(), 0);
+        assert_eq!(cache.len(), deploys.len());
+
+        let second = execute();
+        assert_eq!(
+            metrics.execution_result_cache_hits.get(),
+            deploys.len() as u64
+        );
+        assert_eq!(
+            first.block.state_root_hash(),
+            second.block.state_root_hash()
+        );
+        assert_eq!(
+            first.execution_results.len(),
+            second.execution_results.len()
+        );
+        for (first_info, second_info) in first
+            .execution_results
+            .iter()
+            .zip(&second.execution_results)
+        {
+            assert_eq!(first_info.deploy_hash, second_info.deploy_hash);
+            assert_eq!(first_info.result, second_info.result);
+        }
+    }
+
+    #[test]
+    fn should_reject_block_without_state_progress_when_required() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        // Deploys from accounts absent from global state fail without any effects.
+        let deploys = (0..2)
+            .map(|_| Deploy::random_valid_native_transfer(&mut rng))
+            .collect_vec();
+        let (_, finalized_block) = fixture.first_block(&deploys, None, PublicKey::System);
+        let execute = |pre_state_root_hash| {
+            execute_finalized_block(
+                fixture.engine_state(),
+                None,
+                fixture.chainspec.protocol_version(),
+                ExecutionPreState::new(
+                    0,
+                    pre_state_root_hash,
+                    BlockHash::default(),
+                    Digest::default(),
+                ),
+                finalized_block.clone(),
+                deploys.clone(),
+                EraId::new(0),
+                0,
+                0,
+                BlockExecutionOptions {
+                    slash_equivocators: fixture.chainspec.core_config.slash_equivocators,
+                    require_state_progress: true,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+        };
+
+        // The first execution writes the checksum registry of the block.
+        let BlockAndExecutionResults {
+            block,
+            execution_results,
+            ..
+        } = execute(fixture.post_genesis_state_root_hash).unwrap();
+        assert!(execution_results
+            .iter()
+            .all(|info| matches!(info.outcome, DeployOutcome::Failure { .. })));
+        let state_root_hash = *block.state_root_hash();
+        assert_ne!(state_root_hash, fixture.post_genesis_state_root_hash);
+
+        // Executing the block again on top of its own result rewrites the same checksum registry,
+        // changing nothing.
+        let error = execute(state_root_hash).unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::NoStateProgress { state_root_hash: unchanged }
+                if unchanged == state_root_hash
+        ));
+    }
+
+    #[test]
+    fn should_execute_deploys_pulled_from_iterator_like_vec() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+
+        let from_vec = execute_finalized_block(
+            fixture.engine_state(),
+            None,
+            fixture.chainspec.protocol_version(),
+            execution_pre_state.clone(),
+            finalized_block.clone(),
+            deploys.clone(),
+            EraId::new(0),
+            0,
+            0,
+            BlockExecutionOptions {
+                slash_equivocators: fixture.chainspec.core_config.slash_equivocators,
+                ..BlockExecutionOptions::default()
+            },
+        )
+        .unwrap();
+        let from_iter = execute_finalized_block_from_iter(
+            fixture.engine_state(),
+            None,
+            fixture.chainspec.protocol_version(),
+            execution_pre_state,
+            finalized_block,
+            deploys.iter().cloned(),
+            EraId::new(0),
+            0,
+            0,
+            BlockExecutionOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            from_iter.block.state_root_hash(),
+            from_vec.block.state_root_hash()
+        );
+        assert_eq!(from_iter.block.hash(), from_vec.block.hash());
+        let executed_deploy_hashes = |results: &BlockAndExecutionResults| {
+            results
+                .execution_results
+                .iter()
+                .map(|execution_info| execution_info.deploy_hash)
+                .collect_vec()
+        };
+        assert_eq!(
+            executed_deploy_hashes(&from_iter),
+            executed_deploy_hashes(&from_vec)
+        );
+    }
+
+    #[test]
+    fn should_execute_deploys_with_proposer_override() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let overriding_proposer = TestFixture::validator_public_key(2);
+        let account_key = Key::Account(overriding_proposer.to_account_hash());
+        let main_purse = match fixture
+            .engine_state()
+            .get_state()
+            .checkout(fixture.post_genesis_state_root_hash)
+            .unwrap()
+            .unwrap()
+            .read(CorrelationId::new(), &account_key)
+            .unwrap()
+        {
+            Some(StoredValue::Account(account)) => account.main_purse(),
+            other => panic!("expected account, got {:?}", other),
+        };
+        let proposer_balance = Key::Balance(main_purse.addr());
+
+        // The fees of the deploy are paid to the proposer its execute request names.
+        let mut pays_fee_to_overriding_proposer = |proposer_override: Option<PublicKey>| {
+            let modified_keys = RefCell::new(HashSet::new());
+            let effect_observer = |_: &DeployHash, key: &Key, _: &Transform| {
+                modified_keys.borrow_mut().insert(*key);
+            };
+            fixture
+                .execute_block_with_options(
+                    vec![fixture.transfer(&mut rng, 1)],
+                    None,
+                    BlockExecutionOptions {
+                        effect_observer: Some(&effect_observer),
+                        proposer_override,
+                        ..BlockExecutionOptions::default()
+                    },
+                )
+                .unwrap();
+            modified_keys.into_inner().contains(&proposer_balance)
+        };
+
+        assert!(!pays_fee_to_overriding_proposer(None));
+        assert!(pays_fee_to_overriding_proposer(Some(overriding_proposer)));
+    }
+
+    #[test]
+    fn should_report_scratch_state_transform_count() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let modified_keys = RefCell::new(HashSet::new());
+        let effect_observer = |_: &DeployHash, key: &Key, _: &Transform| {
+            modified_keys.borrow_mut().insert(*key);
+        };
+
+        fixture
+            .execute_block_with_metrics(
+                deploys,
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    effect_observer: Some(&effect_observer),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+
+        // Every key the deploys transformed is written, along with the checksum registry.
+        let mut written_keys = modified_keys.into_inner();
+        assert!(!written_keys.is_empty());
+        written_keys.insert(Key::ChecksumRegistry);
+        assert_eq!(
+            metrics.scratch_state_transform_count.get(),
+            written_keys.len() as i64
+        );
+    }
+
+    #[test]
+    fn should_record_trie_reads_and_writes_per_block() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let trie_store = fixture.engine_state().get_state().trie_store();
+        let writes_before = trie_store.write_count();
+
+        let deploys = vec![fixture.transfer(&mut rng, 1)];
+        fixture
+            .execute_block_with_metrics(
+                deploys,
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+
+        // Committing the transforms of the block writes the modified tries to the trie store.
+        let writes = trie_store.write_count() - writes_before;
+        assert!(writes > 0);
+        assert_eq!(metrics.trie_writes_per_block.get_sample_count(), 1);
+        assert_eq!(
+            metrics.trie_writes_per_block.get_sample_sum() as u64,
+            writes
+        );
+        assert_eq!(metrics.trie_reads_per_block.get_sample_count(), 1);
+        assert!(metrics.trie_reads_per_block.get_sample_sum() > 0.0);
+
+        // A simulated block is not written to the trie store.
+        let deploys = vec![fixture.transfer(&mut rng, 2)];
+        fixture
+            .execute_block_with_metrics(
+                deploys,
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    commit_mode: CommitMode::Forbidden,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(metrics.trie_writes_per_block.get_sample_count(), 2);
+        assert_eq!(
+            metrics.trie_writes_per_block.get_sample_sum() as u64,
+            writes
+        );
+    }
+
+    #[test]
+    fn should_record_block_execution_phase_metrics() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+
+        fixture
+            .execute_block_with_metrics(
+                deploys,
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(metrics.deploys_executed_total.get(), 3);
+        assert_eq!(metrics.scratch_write_to_db.get_sample_count(), 1);
+        assert_eq!(metrics.flush_environment.get_sample_count(), 1);
+    }
+
+    #[test]
+    fn should_time_switch_and_normal_blocks_apart() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let execute = |deploys: Vec<Deploy>, era_report: Option<EraReport<PublicKey>>| {
+            fixture
+                .execute_block_with_metrics(
+                    deploys,
+                    era_report,
+                    &metrics,
+                    BlockExecutionOptions::default(),
+                )
+                .unwrap();
+        };
+
+        execute(vec![fixture.transfer(&mut rng, 1)], None);
+        assert_eq!(metrics.exec_block_normal.get_sample_count(), 1);
+        assert_eq!(metrics.exec_block_switch.get_sample_count(), 0);
+
+        execute(
+            vec![fixture.transfer(&mut rng, 1)],
+            Some(EraReport::default()),
+        );
+        assert_eq!(metrics.exec_block_normal.get_sample_count(), 1);
+        assert_eq!(metrics.exec_block_switch.get_sample_count(), 1);
+        assert_eq!(metrics.exec_block.get_sample_count(), 2);
+    }
+
+    #[test]
+    fn should_set_slowest_deploy_in_block() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+
+        fixture
+            .execute_block_with_metrics(
+                deploys,
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+
+        // The slowest deploy takes at least the mean execution time of the deploys, and at most the
+        // time to execute the whole block.
+        let block_run_execute = metrics.run_execute(ExecutionKind::Block);
+        assert_eq!(block_run_execute.get_sample_count(), 3);
+        let slowest_deploy = metrics.slowest_deploy_in_block.get();
+        assert!(slowest_deploy > 0.0);
+        assert!(slowest_deploy >= block_run_execute.get_sample_sum() / 3.0);
+        assert!(slowest_deploy <= metrics.exec_block.get_sample_sum());
+
+        // Speculative execution is observed apart from the execution of blocks.
+        let speculative_run_execute = metrics.run_execute(ExecutionKind::Speculative);
+        assert_eq!(speculative_run_execute.get_sample_count(), 0);
+        let deploy = fixture.transfer(&mut rng, 1);
+        let execution_state = SpeculativeExecutionState {
+            state_root_hash: fixture.post_genesis_state_root_hash,
+            block_time: Timestamp::now(),
+            protocol_version: fixture.chainspec.protocol_version(),
+            proposer: None,
+            era_id: None,
+        };
+        execute_only(
+            fixture.engine_state(),
+            Some(Arc::clone(&metrics)),
+            execution_state,
+            DeployItem::from(deploy.clone()),
+            deploy.header(),
+            &fixture.chainspec.network_config.name,
+            Timestamp::now(),
+        )
+        .unwrap();
+        assert_eq!(speculative_run_execute.get_sample_count(), 1);
+        assert_eq!(block_run_execute.get_sample_count(), 3);
+        assert_eq!(metrics.slowest_deploy_in_block.get(), slowest_deploy);
+    }
+
+    #[test]
+    fn should_verify_approvals_hashes() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=2)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let BlockAndExecutionResults {
+            block,
+            approvals_hashes,
+            ..
+        } = fixture.execute_block(deploys.clone(), None).unwrap();
+        verify_approvals_hashes(&block, &approvals_hashes, &deploys).unwrap();
+
+        // An additional approval changes the approvals hash, but not the deploy hash.
+        let mut tampered_deploys = deploys.clone();
+        tampered_deploys[1].sign(&TestFixture::validator_secret_key(3));
+        assert!(matches!(
+            verify_approvals_hashes(&block, &approvals_hashes, &tampered_deploys),
+            Err(ApprovalsError::ApprovalsHashMismatch { deploy_hash, .. })
+                if deploy_hash == *deploys[1].hash()
+        ));
+
+        let mut reordered_deploys = deploys.clone();
+        reordered_deploys.reverse();
+        assert!(matches!(
+            verify_approvals_hashes(&block, &approvals_hashes, &reordered_deploys),
+            Err(ApprovalsError::WrongDeploy { index: 0, .. })
+        ));
+        assert!(matches!(
+            verify_approvals_hashes(&block, &approvals_hashes, &deploys[..1]),
+            Err(ApprovalsError::CountMismatch { .. })
+        ));
+
+        let other_block = fixture
+            .execute_block(vec![fixture.transfer(&mut rng, 3)], None)
+            .unwrap()
+            .block;
+        assert!(matches!(
+            verify_approvals_hashes(&other_block, &approvals_hashes, &deploys),
+            Err(ApprovalsError::WrongBlockHash { expected, actual })
+                if expected == *other_block.hash() && actual == *block.hash()
+        ));
+    }
+
+    #[test]
+    fn should_retry_failed_flushes() {
+        // A flush failing twice before succeeding.
+        let flaky_flush = || {
+            let mut failures = 2;
+            move || {
+                if failures == 0 {
+                    return Ok(());
+                }
+                failures -= 1;
+                Err(lmdb::Error::Panic)
+            }
+        };
+
+        retry_flush(2, Duration::ZERO, flaky_flush()).unwrap();
+        assert!(matches!(
+            retry_flush(1, Duration::ZERO, flaky_flush()),
+            Err(BlockExecutionError::FlushFailed {
+                attempts: 2,
+                source: lmdb::Error::Panic,
+            })
+        ));
+        assert!(matches!(
+            retry_flush(0, Duration::ZERO, flaky_flush()),
+            Err(BlockExecutionError::Lmdb(lmdb::Error::Panic))
+        ));
+
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let results = fixture
+            .execute_block_with_options(
+                vec![fixture.transfer(&mut rng, 1)],
+                None,
+                BlockExecutionOptions {
+                    flush_retries: 2,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(results.execution_results.len(), 1);
+    }
+
+    #[test]
+    fn should_batch_flushes_across_blocks() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let protocol_version = fixture.chainspec.protocol_version();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let batched_flushes = BatchedFlushes::new(NonZeroUsize::new(3).unwrap());
+
+        let mut block = fixture
+            .execute_block_with_metrics(
+                vec![fixture.transfer(&mut rng, 1)],
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    batched_flushes: Some(&batched_flushes),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap()
+            .block;
+        let mut state_root_hashes = vec![*block.state_root_hash()];
+        for node_index in 2..=3 {
+            assert_eq!(metrics.flush_environment.get_sample_count(), 0);
+            block = fixture
+                .execute_child_block_with_flushes(
+                    &block,
+                    vec![fixture.transfer(&mut rng, node_index)],
+                    protocol_version,
+                    Some(Arc::clone(&metrics)),
+                    Some(&batched_flushes),
+                    None,
+                )
+                .unwrap()
+                .block;
+            state_root_hashes.push(*block.state_root_hash());
+        }
+        // The third block completes the batch, so the environment is flushed once for all three.
+        assert_eq!(metrics.flush_environment.get_sample_count(), 1);
+        assert_eq!(batched_flushes.pending_blocks(), 0);
+
+        // The next block starts a new batch, which is flushed explicitly.
+        block = fixture
+            .execute_child_block_with_flushes(
+                &block,
+                vec![fixture.transfer(&mut rng, 4)],
+                protocol_version,
+                Some(Arc::clone(&metrics)),
+                Some(&batched_flushes),
+                None,
+            )
+            .unwrap()
+            .block;
+        state_root_hashes.push(*block.state_root_hash());
+        assert_eq!(metrics.flush_environment.get_sample_count(), 1);
+        assert_eq!(batched_flushes.pending_blocks(), 1);
+
+        batched_flushes
+            .flush_now(fixture.engine_state(), Some(&metrics))
+            .unwrap();
+        assert_eq!(metrics.flush_environment.get_sample_count(), 2);
+        assert_eq!(batched_flushes.pending_blocks(), 0);
+        for state_root_hash in state_root_hashes {
+            assert!(fixture
+                .engine_state()
+                .get_state()
+                .checkout(state_root_hash)
+                .unwrap()
+                .is_some());
+        }
+    }
+
+    #[test]
+    fn should_abort_block_exceeding_gas_limit() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=4)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+
+        let BlockAndExecutionResults {
+            execution_results, ..
+        } = fixture.execute_block(deploys.clone(), None).unwrap();
+        let costs = execution_results
+            .iter()
+            .map(|info| Gas::new(info.cost))
+            .collect_vec();
+
+        // The limit is reached but not exceeded by the first two deploys.
+        let block_gas_limit = costs[0] + costs[1];
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let error = fixture
+            .execute_block_with_metrics(
+                deploys,
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    block_gas_limit: Some(block_gas_limit),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::BlockGasLimitExceeded {
+                executed: 3,
+                total: 4
+            }
+        ));
+        assert_eq!(metrics.exec_block_gas_limit_exceeded.get(), 1);
+        assert_eq!(metrics.deploys_executed_total.get(), 3);
+    }
+
+    #[test]
+    fn should_execute_non_conflicting_deploys_in_parallel_with_identical_results() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        // Transfers from the same account conflict with each other, while those from distinct
+        // accounts do not.
+        let mut deploys = [1, 2, 1, 3, 4, 3, 5]
+            .iter()
+            .map(|node_index| fixture.transfer(&mut rng, *node_index))
+            .collect_vec();
+        // The session of a transfer from a newly funded account reads the balance written by the
+        // funding transfer, and fails as the balance left after paying for it does not cover it.
+        let amount = U512::from(
+            fixture
+                .chainspec
+                .deploy_config
+                .native_transfer_minimum_motes,
+        );
+        let secret_key = SecretKey::random(&mut rng);
+        deploys.push(fixture.transfer_from(
+            &TestFixture::validator_secret_key(2),
+            PublicKey::from(&secret_key),
+            amount,
+            1,
+        ));
+        deploys.push(fixture.transfer_from(&secret_key, PublicKey::random(&mut rng), amount, 1));
+
+        let sequential = fixture.execute_block(deploys.clone(), None).unwrap();
+        let parallel = fixture
+            .execute_block_with_options(
+                deploys,
+                None,
+                BlockExecutionOptions {
+                    parallel_deploy_execution: true,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            parallel.block.state_root_hash(),
+            sequential.block.state_root_hash()
+        );
+        let results = |block_and_execution_results: &BlockAndExecutionResults| {
+            block_and_execution_results
+                .execution_results
+                .iter()
+                .map(|info| (info.deploy_hash, info.result.clone(), info.cost))
+                .collect_vec()
+        };
+        assert_eq!(results(&parallel), results(&sequential));
+        assert!(matches!(
+            parallel.execution_results.last().unwrap().outcome,
+            DeployOutcome::InsufficientBalance { .. }
+        ));
+    }
+
+    #[test]
+    fn should_execute_block_against_injected_scratch_state() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let correlation_id = CorrelationId::new();
+
+        // Seed the scratch state with a value absent from the genesis state.
+        let seeded_key = Key::Hash(rng.gen());
+        let seeded_value = StoredValue::CLValue(CLValue::from_t(rng.gen::<u64>()).unwrap());
+        let scratch_state = fixture.engine_state().get_scratch_engine_state();
+        let mut effects = AdditiveMap::new();
+        let _ = effects.insert(seeded_key, Transform::Write(seeded_value.clone()));
+        scratch_state
+            .apply_effect(
+                correlation_id,
+                fixture.post_genesis_state_root_hash,
+                effects,
+            )
+            .unwrap();
+
+        let BlockAndExecutionResults {
+            block,
+            execution_results,
+            ..
+        } = fixture
+            .execute_block_with_options(
+                vec![fixture.transfer(&mut rng, 1)],
+                None,
+                BlockExecutionOptions {
+                    scratch_state: Some(scratch_state),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(execution_results[0].outcome, DeployOutcome::Success);
+
+        // The deploy was committed on top of the seeded value, which was written to LMDB with it.
+        let read = |state_root_hash: Digest| {
+            fixture
+                .engine_state()
+                .get_state()
+                .checkout(state_root_hash)
+                .unwrap()
+                .unwrap()
+                .read(correlation_id, &seeded_key)
+                .unwrap()
+        };
+        assert_eq!(read(fixture.post_genesis_state_root_hash), None);
+        assert_eq!(read(*block.state_root_hash()), Some(seeded_value));
+    }
+
+    #[test]
+    fn should_verify_written_root_is_readable() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let verified_block_result = fixture.execute_block_with_options(
+            vec![fixture.transfer(&mut rng, 1)],
+            None,
+            BlockExecutionOptions {
+                verify_roundtrip: true,
+                ..BlockExecutionOptions::default()
+            },
+        );
+        assert!(verified_block_result.is_ok());
+
+        // A simulated block's root is never written to LMDB, so reading it back fails as it would
+        // if the written trie were corrupted.
+        let BlockAndExecutionResults { block, .. } = fixture
+            .execute_block_with_options(
+                vec![fixture.transfer(&mut rng, 2)],
+                None,
+                BlockExecutionOptions {
+                    commit_mode: CommitMode::Forbidden,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        let unwritten_state_root_hash = *block.state_root_hash();
+        assert!(matches!(
+            verify_root_readable(
+                fixture.engine_state(),
+                CorrelationId::new(),
+                unwritten_state_root_hash,
+            ),
+            Err(BlockExecutionError::RootNotReadable { state_root_hash })
+                if state_root_hash == unwritten_state_root_hash
+        ));
+    }
+
+    #[test]
+    fn should_execute_first_deploy_even_if_all_conflict() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let scratch_state = fixture.engine_state().get_scratch_engine_state();
+        let deploy_items = (0..3)
+            .map(|_| DeployItem::from(fixture.transfer(&mut rng, 1)))
+            .collect_vec();
+
+        let results = execute_non_conflicting_deploys(
+            &scratch_state,
+            CorrelationId::new(),
+            None,
+            fixture.post_genesis_state_root_hash,
+            Timestamp::now().millis(),
+            fixture.chainspec.protocol_version(),
+            &PublicKey::System,
+            deploy_items.iter(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, DeployHash::from(deploy_items[0].deploy_hash));
+    }
+
+    #[test]
+    fn should_resume_block_execution_from_checkpoint() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let results = |block_and_results: &BlockAndExecutionResults| {
+            block_and_results
+                .execution_results
+                .iter()
+                .map(|info| (info.deploy_hash, info.result.clone()))
+                .collect_vec()
+        };
+
+        let full_run = fixture.execute_block(deploys.clone(), None).unwrap();
+
+        let mut checkpoints = vec![];
+        let mut on_checkpoint = |checkpoint: &ExecutionCheckpoint| {
+            checkpoints.push(checkpoint.clone());
+        };
+        let checkpointed_run = fixture
+            .execute_block_with_options(
+                deploys.clone(),
+                None,
+                BlockExecutionOptions {
+                    checkpointing: Some(BlockCheckpointing {
+                        interval: NonZeroUsize::new(1).unwrap(),
+                        resume_from: None,
+                        on_checkpoint: &mut on_checkpoint,
+                    }),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            checkpointed_run.block.state_root_hash(),
+            full_run.block.state_root_hash()
+        );
+        // No checkpoint is recorded after the last deploy.
+        assert_eq!(
+            checkpoints
+                .iter()
+                .map(ExecutionCheckpoint::deploy_index)
+                .collect_vec(),
+            vec![1, 2]
+        );
+
+        for checkpoint in checkpoints.iter().cloned() {
+            let resumed_run = fixture
+                .execute_block_with_options(
+                    deploys.clone(),
+                    None,
+                    BlockExecutionOptions {
+                        checkpointing: Some(BlockCheckpointing {
+                            interval: NonZeroUsize::new(1).unwrap(),
+                            resume_from: Some(checkpoint),
+                            on_checkpoint: &mut |_| {},
+                        }),
+                        ..BlockExecutionOptions::default()
+                    },
+                )
+                .unwrap();
+            assert_eq!(
+                resumed_run.block.state_root_hash(),
+                full_run.block.state_root_hash()
+            );
+            assert_eq!(results(&resumed_run), results(&full_run));
+        }
+
+        // A checkpoint of a different block is rejected.
+        let error = fixture
+            .execute_block_with_options(
+                deploys[1..].to_vec(),
+                None,
+                BlockExecutionOptions {
+                    checkpointing: Some(BlockCheckpointing {
+                        interval: NonZeroUsize::new(1).unwrap(),
+                        resume_from: Some(checkpoints[0].clone()),
+                        on_checkpoint: &mut |_| {},
+                    }),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::InvalidCheckpoint { block_height: 0 }
+        ));
+    }
+
+    #[test]
+    fn should_simulate_block_without_writing_to_global_state() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+
+        let simulated = fixture
+            .simulate_block(deploys.clone(), BlockExecutionOptions::default())
+            .unwrap();
+        let simulated_state_root_hash = *simulated.block.state_root_hash();
+        assert_ne!(
+            simulated_state_root_hash,
+            fixture.post_genesis_state_root_hash
+        );
+        // Nothing was written to LMDB, so the simulated state root is unknown to it.
+        assert!(fixture
+            .engine_state()
+            .tracking_copy(simulated_state_root_hash)
+            .unwrap()
+            .is_none());
+
+        // Executing the same deploys for real yields the simulated state root and results.
+        let executed = fixture.execute_block(deploys, None).unwrap();
+        assert_eq!(*executed.block.state_root_hash(), simulated_state_root_hash);
+        assert!(fixture
+            .engine_state()
+            .tracking_copy(simulated_state_root_hash)
+            .unwrap()
+            .is_some());
+        let results = |block_and_results: &BlockAndExecutionResults| {
+            block_and_results
+                .execution_results
+                .iter()
+                .map(|info| (info.deploy_hash, info.result.clone()))
+                .collect_vec()
+        };
+        assert_eq!(results(&simulated), results(&executed));
+    }
+
+    /// The default checksum scheme, counting how often it is used.
+    #[derive(Default)]
+    struct CountingChecksum(AtomicUsize);
+
+    impl CountingChecksum {
+        fn count(&self) -> usize {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    impl ExecutionResultsChecksum for CountingChecksum {
+        fn checksum(&self, serialized_execution_results: &[u8]) -> Digest {
+            let _ = self.0.fetch_add(1, Ordering::SeqCst);
+            DefaultExecutionResultsChecksum.checksum(serialized_execution_results)
+        }
+    }
+
+    #[test]
+    fn should_not_compute_execution_results_checksum_in_dry_run() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let checksum_scheme = CountingChecksum::default();
+        let options = || BlockExecutionOptions {
+            checksum_scheme: Some(&checksum_scheme),
+            ..BlockExecutionOptions::default()
+        };
+
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+        let dry_run_results = dry_run_finalized_block(
+            fixture.engine_state(),
+            fixture.chainspec.protocol_version(),
+            execution_pre_state,
+            finalized_block,
+            deploys.clone(),
+            options(),
+        )
+        .unwrap();
+        assert_eq!(checksum_scheme.count(), 0);
+
+        // The checksum registry is part of the state root hash of a simulated block, so the
+        // checksum is computed as when committing.
+        let simulated = fixture.simulate_block(deploys.clone(), options()).unwrap();
+        assert_eq!(checksum_scheme.count(), 1);
+        let executed = fixture
+            .execute_block_with_options(deploys, None, options())
+            .unwrap();
+        assert_eq!(checksum_scheme.count(), 2);
+        assert_eq!(
+            simulated.block.state_root_hash(),
+            executed.block.state_root_hash()
+        );
+
+        // The dry run yields the results of executing the block.
+        let results = |execution_results: &[DeployExecutionInfo]| {
+            execution_results
+                .iter()
+                .map(|info| (info.deploy_hash, info.result.clone()))
+                .collect_vec()
+        };
+        assert_eq!(
+            results(&dry_run_results),
+            results(&executed.execution_results)
+        );
+    }
+
+    /// A tracing layer capturing the fields recorded on `execute_block` spans.
+    #[derive(Clone, Default)]
+    struct ExecuteBlockSpanFields(Arc<Mutex<BTreeMap<String, String>>>);
+
+    impl ExecuteBlockSpanFields {
+        fn get(&self, name: &str) -> Option<String> {
+            self.0.lock().unwrap().get(name).cloned()
+        }
+    }
+
+    struct FieldVisitor<'a>(&'a mut BTreeMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S> Layer<S> for ExecuteBlockSpanFields
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            if attrs.metadata().name() == "execute_block" {
+                attrs.record(&mut FieldVisitor(&mut self.0.lock().unwrap()));
+            }
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            if ctx
+                .metadata(id)
+                .map_or(false, |metadata| metadata.name() == "execute_block")
+            {
+                values.record(&mut FieldVisitor(&mut self.0.lock().unwrap()));
+            }
+        }
+    }
+
+    /// A tracing layer capturing the correlation ids recorded on events.
+    #[derive(Clone, Default)]
+    struct EventCorrelationIds(Arc<Mutex<Vec<String>>>);
+
+    impl<S: Subscriber> Layer<S> for EventCorrelationIds {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let mut fields = BTreeMap::new();
+            event.record(&mut FieldVisitor(&mut fields));
+            if let Some(correlation_id) = fields.remove("correlation_id") {
+                self.0.lock().unwrap().push(correlation_id);
+            }
+        }
+    }
+
+    fn execute_block_capturing_span_fields(
+        fixture: &TestFixture,
+        deploys: Vec<Deploy>,
+    ) -> (
+        Result<BlockAndExecutionResults, BlockExecutionError>,
+        ExecuteBlockSpanFields,
+    ) {
+        let span_fields = ExecuteBlockSpanFields::default();
+        let subscriber = tracing_subscriber::registry().with(span_fields.clone());
+        let result =
+            tracing::subscriber::with_default(subscriber, || fixture.execute_block(deploys, None));
+        (result, span_fields)
+    }
+
+    #[test]
+    fn should_record_block_execution_span_fields() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=2)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+
+        let (result, span_fields) = execute_block_capturing_span_fields(&fixture, deploys);
+        let BlockAndExecutionResults { block, .. } = result.unwrap();
+        assert_eq!(span_fields.get("height"), Some("0".to_string()));
+        assert_eq!(span_fields.get("era_id"), Some("0".to_string()));
+        assert_eq!(span_fields.get("deploy_count"), Some("2".to_string()));
+        assert_eq!(
+            span_fields.get("state_root_hash"),
+            Some(block.state_root_hash().to_string())
+        );
+        assert!(span_fields.get("elapsed_ms").is_some());
+    }
+
+    #[test]
+    fn should_record_block_execution_span_fields_on_error() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploy = fixture.transfer(&mut rng, 1);
+
+        let (result, span_fields) =
+            execute_block_capturing_span_fields(&fixture, vec![deploy.clone(), deploy]);
+        assert!(matches!(
+            result,
+            Err(BlockExecutionError::DuplicateDeploy { .. })
+        ));
+        assert_eq!(span_fields.get("deploy_count"), Some("2".to_string()));
+        assert_eq!(span_fields.get("state_root_hash"), None);
+        assert!(span_fields.get("elapsed_ms").is_some());
+    }
+
+    #[test]
+    fn should_use_one_correlation_id_for_all_calls_of_a_block() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=2)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+
+        let span_fields = ExecuteBlockSpanFields::default();
+        let correlation_ids = EventCorrelationIds::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(span_fields.clone())
+            .with(correlation_ids.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            fixture.execute_block(deploys, Some(EraReport::default()))
+        })
+        .unwrap();
+
+        let block_correlation_id = span_fields.get("correlation_id").unwrap();
+        let correlation_ids = correlation_ids.0.lock().unwrap();
+        // The deploys are executed and their effects committed, then the step is run.
+        assert!(correlation_ids.len() >= 3);
+        assert!(correlation_ids
+            .iter()
+            .all(|correlation_id| *correlation_id == block_correlation_id));
+    }
+
+    #[test]
+    fn should_report_first_divergent_deploy_on_root_mismatch() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let deploy_hashes = deploys.iter().map(|deploy| *deploy.hash()).collect_vec();
+
+        let reference = fixture
+            .execute_block_with_options(
+                deploys.clone(),
+                None,
+                BlockExecutionOptions {
+                    trace_state_roots: true,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        let reference_root = *reference.block.state_root_hash();
+        let reference_trace = reference.maybe_state_root_trace.unwrap();
+        fixture
+            .verify_block(deploys.clone(), reference_root, Some(&reference_trace))
+            .unwrap();
+
+        // Pretend the reference execution diverged from the second deploy onwards.
+        let divergent_root = Digest::hash(b"divergent state root");
+        let divergent_trace = reference_trace
+            .iter()
+            .map(|(checkpoint, root)| match checkpoint {
+                StateRootCheckpoint::Deploy(deploy_hash) if *deploy_hash == deploy_hashes[0] => {
+                    (*checkpoint, *root)
+                }
+                _ => (*checkpoint, divergent_root),
+            })
+            .collect_vec();
+        let error = fixture
+            .verify_block(deploys.clone(), divergent_root, Some(&divergent_trace))
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::RootMismatch {
+                expected,
+                actual,
+                first_divergent_deploy: Some(deploy_hash),
+            } if expected == divergent_root
+                && actual == reference_root
+                && deploy_hash == deploy_hashes[1]
+        ));
+
+        // Without a reference trace, the divergent deploy is unknown.
+        let error = fixture
+            .verify_block(deploys, divergent_root, None)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::RootMismatch {
+                first_divergent_deploy: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn should_not_write_checksum_registry_for_empty_block_from_protocol_version() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let parent = fixture
+            .execute_block(vec![fixture.transfer(&mut rng, 1)], None)
+            .unwrap()
+            .block;
+
+        // Under earlier protocol versions, empty blocks rewrite the checksum registry.
+        let legacy = fixture
+            .execute_child_block(&parent, vec![], fixture.chainspec.protocol_version())
+            .unwrap();
+        assert!(
+            fixture.chainspec.protocol_version()
+                < SKIP_EMPTY_BLOCK_CHECKSUM_REGISTRY_PROTOCOL_VERSION
+        );
+        assert_ne!(legacy.block.state_root_hash(), parent.state_root_hash());
+
+        let empty = fixture
+            .execute_child_block(
+                &parent,
+                vec![],
+                SKIP_EMPTY_BLOCK_CHECKSUM_REGISTRY_PROTOCOL_VERSION,
+            )
+            .unwrap();
+        assert_eq!(empty.block.state_root_hash(), parent.state_root_hash());
+        // The approvals hashes carry a proof of the parent's checksum registry.
+        empty.approvals_hashes.validate(&empty.block).unwrap();
+
+        // Under earlier protocol versions, the checksum registry of an empty block must hold the
+        // checksum of its approvals, which that of the parent doesn't.
+        let pre_upgrade_block = Block::new(
+            *parent.hash(),
+            parent.header().accumulated_seed(),
+            *parent.state_root_hash(),
+            FinalizedBlock::from(empty.block.clone()),
+            None,
+            fixture.chainspec.protocol_version(),
+        )
+        .unwrap();
+        let pre_upgrade_approvals_hashes = ApprovalsHashes::new(
+            pre_upgrade_block.hash(),
+            vec![],
+            checksum_registry_proof(
+                fixture.engine_state(),
+                None,
+                CorrelationId::new(),
+                *parent.state_root_hash(),
+            )
+            .unwrap(),
+        );
+        assert!(matches!(
+            pre_upgrade_approvals_hashes.validate(&pre_upgrade_block),
+            Err(types::ApprovalsHashesValidationError::ApprovalsChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn should_capture_state_root_of_failed_checksum_registry_proof() {
+        let fixture = TestFixture::new();
+        // Genesis doesn't write the checksum registry.
+        let state_root_hash = fixture.post_genesis_state_root_hash;
+        let error = checksum_registry_proof(
+            fixture.engine_state(),
+            None,
+            CorrelationId::new(),
+            state_root_hash,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::ChecksumRegistryProof {
+                state_root_hash: root,
+                source: engine_state::Error::MissingChecksumRegistry,
+            } if root == state_root_hash
+        ));
+
+        let missing_state_root_hash = Digest::hash(b"missing state root");
+        let error = checksum_registry_proof(
+            fixture.engine_state(),
+            None,
+            CorrelationId::new(),
+            missing_state_root_hash,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::ChecksumRegistryProof {
+                state_root_hash: root,
+                source: engine_state::Error::RootNotFound(_),
+            } if root == missing_state_root_hash
+        ));
+    }
+
+    #[test]
+    fn should_notify_era_transition_only_at_switch_blocks() {
+        let fixture = TestFixture::new();
+        for era_report in [None, Some(EraReport::default())] {
+            let is_switch_block = era_report.is_some();
+            let transitions = Rc::new(RefCell::new(vec![]));
+            let recorded_transitions = Rc::clone(&transitions);
+            let on_era_transition = move |era_id: EraId, weights: &BTreeMap<PublicKey, U512>| {
+                recorded_transitions
+                    .borrow_mut()
+                    .push((era_id, weights.clone()))
+            };
+            let options = BlockExecutionOptions {
+                on_era_transition: Some(&on_era_transition),
+                ..BlockExecutionOptions::default()
+            };
+            let BlockAndExecutionResults { block, .. } = fixture
+                .execute_block_with_options(vec![], era_report, options)
+                .unwrap();
+
+            let transitions = transitions.take();
+            if is_switch_block {
+                assert_eq!(transitions.len(), 1);
+                let (era_id, weights) = &transitions[0];
+                assert_eq!(*era_id, block.header().era_id().successor());
+                assert_eq!(Some(weights), block.header().next_era_validator_weights());
+            } else {
+                assert!(transitions.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn should_validate_proposer_if_enabled() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let execute = |proposer: PublicKey, validate_proposer: bool| {
+            let options = BlockExecutionOptions {
+                validate_proposer,
+                ..BlockExecutionOptions::default()
+            };
+            fixture.execute_proposed_block(vec![], None, proposer, None, options)
+        };
+
+        execute(TestFixture::validator_public_key(1), true).unwrap();
+        execute(PublicKey::System, true).unwrap();
+
+        let unknown_proposer = PublicKey::random(&mut rng);
+        let error = execute(unknown_proposer.clone(), true).unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::UnknownProposer { proposer } if *proposer == unknown_proposer
+        ));
+        execute(unknown_proposer, false).unwrap();
+    }
+
+    #[test]
+    fn should_reject_block_with_wrong_parent_hash() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        // The first block of the fixture is a child of the default block hash.
+        let wrong_parent_hash = BlockHash::random(&mut rng);
+        let mut execute = |expected_parent_hash: BlockHash| {
+            let options = BlockExecutionOptions {
+                expected_parent_hash: Some(expected_parent_hash),
+                ..BlockExecutionOptions::default()
+            };
+            let deploys = vec![fixture.transfer(&mut rng, 1)];
+            fixture.execute_block_with_metrics(deploys, None, &metrics, options)
+        };
+
+        let error = execute(wrong_parent_hash).unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::WrongParentHash { expected, actual }
+                if expected == wrong_parent_hash && actual == BlockHash::default()
+        ));
+        // The block is rejected before any deploy is executed.
+        assert_eq!(metrics.deploys_executed_total.get(), 0);
+
+        execute(BlockHash::default()).unwrap();
+        assert_eq!(metrics.deploys_executed_total.get(), 1);
+    }
+
+    #[test]
+    fn should_stream_deploy_results_in_deploy_order() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=4)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let deploy_hashes = deploys.iter().map(|deploy| *deploy.hash()).collect_vec();
+
+        let collected = fixture.execute_block(deploys.clone(), None).unwrap();
+
+        let mut streamed_results = vec![];
+        let streamed = fixture
+            .execute_block_streaming(
+                deploys,
+                BlockExecutionOptions::default(),
+                &mut |deploy_execution_info| streamed_results.push(deploy_execution_info),
+            )
+            .unwrap();
+        assert!(streamed.execution_results.is_empty());
+        assert_eq!(
+            streamed_results
+                .iter()
+                .map(|info| info.deploy_hash)
+                .collect_vec(),
+            deploy_hashes
+        );
+        // The execution results checksum, and hence the state root, is unaffected by streaming.
+        assert_eq!(
+            streamed.block.state_root_hash(),
+            collected.block.state_root_hash()
+        );
+        for (streamed, collected) in streamed_results.iter().zip(&collected.execution_results) {
+            assert_eq!(streamed.result, collected.result);
+            assert_eq!(streamed.header, collected.header);
+        }
+    }
+
+    #[test]
+    fn should_reject_checkpointing_while_streaming() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = vec![fixture.transfer(&mut rng, 1)];
+
+        let mut streamed_deploys = 0;
+        let error = fixture
+            .execute_block_streaming(
+                deploys,
+                BlockExecutionOptions {
+                    checkpointing: Some(BlockCheckpointing {
+                        interval: NonZeroUsize::new(1).unwrap(),
+                        resume_from: None,
+                        on_checkpoint: &mut |_| {},
+                    }),
+                    ..BlockExecutionOptions::default()
+                },
+                &mut |_| streamed_deploys += 1,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::CheckpointingWhileStreaming
+        ));
+        assert_eq!(streamed_deploys, 0);
+    }
+
+    #[test]
+    fn should_reject_block_with_duplicate_deploys() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploy = fixture.transfer(&mut rng, 1);
+        let deploys = vec![
+            fixture.transfer(&mut rng, 2),
+            deploy.clone(),
+            deploy.clone(),
+        ];
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+
+        let error = fixture
+            .execute_block_with_metrics(
+                deploys,
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::DuplicateDeploy { deploy_hash } if deploy_hash == *deploy.hash()
+        ));
+
+        // Nothing was executed, let alone committed.
+        assert_eq!(
+            metrics.run_execute(ExecutionKind::Block).get_sample_count(),
+            0
+        );
+        assert_eq!(metrics.apply_effect.get_sample_count(), 0);
+        assert_eq!(metrics.scratch_write_to_db.get_sample_count(), 0);
+    }
+
+    #[test]
+    fn pre_execution_checks_should_pass_valid_block() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = vec![fixture.transfer(&mut rng, 1), fixture.transfer(&mut rng, 2)];
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+
+        pre_execution_checks(&finalized_block, &execution_pre_state, &deploys, None).unwrap();
+        pre_execution_checks(
+            &finalized_block,
+            &execution_pre_state,
+            &deploys,
+            Some(BlockHash::default()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn pre_execution_checks_should_reject_wrong_height() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = vec![fixture.transfer(&mut rng, 1)];
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+        let execution_pre_state = ExecutionPreState::new(
+            finalized_block.height() + 1,
+            execution_pre_state.pre_state_root_hash,
+            execution_pre_state.parent_hash,
+            execution_pre_state.parent_seed,
+        );
+
+        let error = pre_execution_checks(&finalized_block, &execution_pre_state, &deploys, None)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::WrongBlockHeight { .. }
+        ));
+    }
+
+    #[test]
+    fn pre_execution_checks_should_reject_wrong_parent_hash() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = vec![fixture.transfer(&mut rng, 1)];
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+        let wrong_parent_hash = BlockHash::random(&mut rng);
+
+        let error = pre_execution_checks(
+            &finalized_block,
+            &execution_pre_state,
+            &deploys,
+            Some(wrong_parent_hash),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::WrongParentHash { expected, .. } if expected == wrong_parent_hash
+        ));
+    }
+
+    #[test]
+    fn pre_execution_checks_should_reject_duplicate_deploys() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploy = fixture.transfer(&mut rng, 1);
+        let deploys = vec![
+            deploy.clone(),
+            fixture.transfer(&mut rng, 2),
+            deploy.clone(),
+        ];
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+
+        let error = pre_execution_checks(&finalized_block, &execution_pre_state, &deploys, None)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::DuplicateDeploy { deploy_hash } if deploy_hash == *deploy.hash()
+        ));
+    }
+
+    /// Distributes each reward as reported.
+    struct IdentityRewardStrategy;
+
+    impl RewardStrategy for IdentityRewardStrategy {
+        fn reward(&self, _validator_id: &PublicKey, reward: u64) -> u64 {
+            reward
+        }
+    }
+
+    /// Caps the reward of every validator.
+    struct CappedRewardStrategy(u64);
+
+    impl RewardStrategy for CappedRewardStrategy {
+        fn reward(&self, _validator_id: &PublicKey, reward: u64) -> u64 {
+            cmp::min(reward, self.0)
+        }
+    }
+
+    fn era_report_rewarding_validators(rewards: &[u64]) -> EraReport<PublicKey> {
+        EraReport {
+            equivocators: vec![],
+            rewards: rewards
+                .iter()
+                .enumerate()
+                .map(|(index, reward)| (TestFixture::validator_public_key(index + 1), *reward))
+                .collect(),
+            inactive_validators: vec![],
+        }
+    }
+
+    #[test]
+    fn should_return_state_roots_before_and_after_step() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+
+        let BlockAndExecutionResults {
+            block,
+            post_deploys_root,
+            final_root,
+            ..
+        } = fixture
+            .execute_block(vec![fixture.transfer(&mut rng, 1)], None)
+            .unwrap();
+        assert_eq!(post_deploys_root, final_root);
+        assert_eq!(final_root, *block.state_root_hash());
+
+        let era_report = era_report_rewarding_validators(&[1_000, 2_000, 3_000]);
+        let BlockAndExecutionResults {
+            block,
+            post_deploys_root,
+            final_root,
+            ..
+        } = fixture
+            .execute_block(vec![fixture.transfer(&mut rng, 1)], Some(era_report))
+            .unwrap();
+        assert_ne!(post_deploys_root, final_root);
+        assert_eq!(final_root, *block.state_root_hash());
+    }
+
+    #[test]
+    fn should_record_total_rewards_of_last_era() {
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let execute = |era_report| {
+            fixture
+                .execute_block_with_metrics(
+                    vec![],
+                    era_report,
+                    &metrics,
+                    BlockExecutionOptions {
+                        ..BlockExecutionOptions::default()
+                    },
+                )
+                .unwrap()
+        };
+
+        execute(Some(era_report_rewarding_validators(&[
+            1_000, 2_000, 3_000,
+        ])));
+        assert_eq!(metrics.last_era_total_rewards.get(), 6_000.0);
+
+        // Non-switch blocks leave the gauge as it was.
+        execute(None);
+        assert_eq!(metrics.last_era_total_rewards.get(), 6_000.0);
+    }
+
+    #[test]
+    fn should_record_number_of_evicted_and_rewarded_validators() {
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let mut era_report = era_report_rewarding_validators(&[1_000, 2_000, 3_000]);
+        era_report.inactive_validators = vec![TestFixture::validator_public_key(3)];
+        let results = fixture
+            .execute_block_with_metrics(
+                vec![],
+                Some(era_report),
+                &metrics,
+                BlockExecutionOptions {
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        let step_outcome_summary = results
+            .maybe_step_effect_and_upcoming_era_validators
+            .unwrap()
+            .step_outcome_summary;
+        assert_eq!(step_outcome_summary.evicted.len(), 1);
+        assert_eq!(step_outcome_summary.rewarded.len(), 3);
+        assert_eq!(metrics.validators_evicted.get(), 1);
+        assert_eq!(metrics.validators_rewarded.get(), 3);
+    }
+
+    #[test]
+    fn should_commit_identical_step_with_identity_reward_strategy() {
+        let fixture = TestFixture::new();
+        let era_report = era_report_rewarding_validators(&[1_000, 2_000, 3_000]);
+        let era_end_timestamp_millis = Timestamp::now().millis();
+        let step = |reward_strategy: Option<&dyn RewardStrategy>| {
+            commit_step(
+                &fixture.engine_state().get_scratch_engine_state(),
+                CorrelationId::new(),
+                None,
+                fixture.chainspec.protocol_version(),
+                fixture.post_genesis_state_root_hash,
+                &era_report,
+                era_end_timestamp_millis,
+                EraId::new(1),
+                false,
+                reward_strategy,
+                None,
+                None,
+            )
+            .unwrap()
+            .0
+            .execution_journal
+        };
+
+        let default_step_journal = step(None);
+        assert!(!default_step_journal.is_empty());
+        assert_eq!(step(Some(&IdentityRewardStrategy)), default_step_journal);
+    }
+
+    #[test]
+    fn should_cap_rewards_with_reward_strategy() {
+        let era_report = era_report_rewarding_validators(&[1_000, 2_000, 3_000]);
+        let step_request = create_step_request(
+            ProtocolVersion::V1_0_0,
+            Digest::default(),
+            &era_report,
+            0,
+            EraId::new(1),
+            false,
+            Some(&CappedRewardStrategy(2_000)),
+        );
+        let rewards = step_request
+            .reward_items
+            .iter()
+            .map(|item| (item.validator_id.clone(), item.value))
+            .collect::<BTreeMap<_, _>>();
+        let expected_rewards = era_report
+            .rewards
+            .iter()
+            .map(|(validator_id, reward)| (validator_id.clone(), cmp::min(*reward, 2_000)))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(rewards, expected_rewards);
+    }
+
+    #[test]
+    fn should_sort_reward_items_by_public_key() {
+        let mut rng = crate::new_rng();
+        let era_report = EraReport {
+            rewards: (0..20)
+                .map(|_| (PublicKey::random(&mut rng), rng.gen_range(1..1_000)))
+                .collect(),
+            ..EraReport::default()
+        };
+        let step_request = create_step_request(
+            ProtocolVersion::V1_0_0,
+            Digest::default(),
+            &era_report,
+            0,
+            EraId::new(1),
+            false,
+            None,
+        );
+        let validator_ids = step_request
+            .reward_items
+            .iter()
+            .map(|item| item.validator_id.clone())
+            .collect_vec();
+        let mut sorted_validator_ids = validator_ids.clone();
+        sorted_validator_ids.sort();
+        assert_eq!(validator_ids.len(), 20);
+        assert_eq!(validator_ids, sorted_validator_ids);
+    }
+
+    #[test]
+    fn should_override_era_end_timestamp_from_protocol_version() {
+        let fixture = TestFixture::new();
+        let (_, finalized_block) =
+            fixture.first_block(&[], Some(EraReport::default()), PublicKey::System);
+        let era_end_timestamp = finalized_block.timestamp() + TimeDiff::from_seconds(60);
+        let step_request = |protocol_version, era_end_timestamp| {
+            create_step_request(
+                protocol_version,
+                Digest::default(),
+                finalized_block.era_report().unwrap(),
+                era_end_timestamp_millis(&finalized_block, protocol_version, era_end_timestamp),
+                finalized_block.era_id().successor(),
+                false,
+                None,
+            )
+        };
+
+        let block_timestamp_millis = finalized_block.timestamp().millis();
+        assert_eq!(
+            step_request(ERA_END_TIMESTAMP_OVERRIDE_PROTOCOL_VERSION, None)
+                .era_end_timestamp_millis,
+            block_timestamp_millis
+        );
+        assert_eq!(
+            step_request(ProtocolVersion::V1_0_0, Some(era_end_timestamp)).era_end_timestamp_millis,
+            block_timestamp_millis
+        );
+        assert_eq!(
+            step_request(
+                ERA_END_TIMESTAMP_OVERRIDE_PROTOCOL_VERSION,
+                Some(era_end_timestamp)
+            )
+            .era_end_timestamp_millis,
+            era_end_timestamp.millis()
+        );
+    }
+
+    #[test]
+    fn should_transform_era_report_before_creating_step_request() {
+        // Shares half of each validator's reward with another account, as if delegating to it,
+        // doubling the recipients.
+        let share_rewards = |era_report: &EraReport<PublicKey>| {
+            let mut rewards = BTreeMap::new();
+            for (index, (validator_id, reward)) in era_report.rewards.iter().enumerate() {
+                let shared = reward / 2;
+                rewards.insert(validator_id.clone(), reward - shared);
+                let delegator_secret_key =
+                    SecretKey::ed25519_from_bytes([index as u8 + 1; 32]).unwrap();
+                rewards.insert(PublicKey::from(&delegator_secret_key), shared);
+            }
+            EraReport {
+                rewards,
+                ..era_report.clone()
+            }
+        };
+        let era_report = era_report_rewarding_validators(&[1_000, 2_000, 3_000]);
+        let reward_items = |protocol_version| {
+            let era_report =
+                transform_era_report(&era_report, protocol_version, Some(&share_rewards));
+            create_step_request(
+                protocol_version,
+                Digest::default(),
+                &era_report,
+                0,
+                EraId::new(1),
+                false,
+                None,
+            )
+            .reward_items
+            .into_iter()
+            .map(|item| (item.validator_id, item.value))
+            .collect::<BTreeMap<_, _>>()
+        };
+
+        // The transform is ignored before its protocol version.
+        assert_eq!(reward_items(ProtocolVersion::V1_0_0), era_report.rewards);
+
+        let rewards = reward_items(ERA_REPORT_TRANSFORM_PROTOCOL_VERSION);
+        assert_eq!(rewards.len(), 2 * era_report.rewards.len());
+        assert_eq!(rewards, share_rewards(&era_report).rewards);
+        assert_eq!(
+            rewards.values().sum::<u64>(),
+            era_report.rewards.values().sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn should_distinguish_missing_and_extra_execution_results() {
+        let mut rng = crate::new_rng();
+        let deploy_hash = DeployHash::random(&mut rng);
+        let execution_results = |count: usize| -> ExecutionResults {
+            (0..count)
+                .map(|_| {
+                    EngineExecutionResult::precondition_failure(
+                        engine_state::Error::InsufficientPayment,
+                    )
+                })
+                .collect()
+        };
+
+        let error = pair_execution_results(vec![deploy_hash], execution_results(0)).unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::NoExecutionResult { deploy_hash: hash } if hash == deploy_hash
+        ));
+
+        let error = pair_execution_results(vec![deploy_hash], execution_results(3)).unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::MoreThanOneExecutionResult { deploy_hash: hash, count: 3 }
+                if hash == deploy_hash
+        ));
+
+        let paired = pair_execution_results(vec![deploy_hash], execution_results(1)).unwrap();
+        assert_eq!(paired.len(), 1);
+        assert_eq!(paired[0].0, deploy_hash);
+
+        // Batches of several deploys still report the mismatched counts.
+        let deploy_hashes = vec![deploy_hash, DeployHash::random(&mut rng)];
+        let error = pair_execution_results(deploy_hashes, execution_results(1)).unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::ExecutionResultCountMismatch {
+                expected: 2,
+                actual: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn should_carry_over_rewards_below_minimum() {
+        let validator = TestFixture::validator_public_key;
+        let step_request = |protocol_version, rewards: &[u64]| {
+            create_step_request(
+                protocol_version,
+                Digest::default(),
+                &era_report_rewarding_validators(rewards),
+                0,
+                EraId::new(1),
+                false,
+                None,
+            )
+        };
+        let rewarded = |step_request: &StepRequest| {
+            step_request
+                .reward_items
+                .iter()
+                .map(|item| (item.validator_id.clone(), item.value))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        // The first validator's reward is pushed over the minimum by its carried over reward, the
+        // second one's stays below it, and the third one's exceeds it.
+        let minimum_reward = MinimumReward::new(
+            U512::from(2_500),
+            BTreeMap::from([(validator(1), U512::from(1_500))]),
+        );
+        let mut request = step_request(MINIMUM_REWARD_PROTOCOL_VERSION, &[1_000, 2_000, 3_000]);
+        let withheld = withhold_rewards_below_minimum(&mut request, Some(&minimum_reward));
+        assert_eq!(
+            rewarded(&request),
+            BTreeMap::from([(validator(1), 2_500), (validator(3), 3_000)])
+        );
+        assert_eq!(
+            withheld,
+            BTreeMap::from([(validator(2), U512::from(2_000))])
+        );
+
+        // In the next era, the withheld reward is carried over, even if the validator is not
+        // rewarded.
+        let minimum_reward = MinimumReward::new(U512::from(2_500), withheld);
+        let mut request = step_request(MINIMUM_REWARD_PROTOCOL_VERSION, &[1_000]);
+        let withheld = withhold_rewards_below_minimum(&mut request, Some(&minimum_reward));
+        assert!(rewarded(&request).is_empty());
+        assert_eq!(
+            withheld,
+            BTreeMap::from([
+                (validator(1), U512::from(1_000)),
+                (validator(2), U512::from(2_000))
+            ])
+        );
+
+        // Under earlier protocol versions, all rewards are paid as reported.
+        let mut request = step_request(ProtocolVersion::V1_0_0, &[1_000, 2_000, 3_000]);
+        let withheld = withhold_rewards_below_minimum(&mut request, Some(&minimum_reward));
+        assert_eq!(
+            rewarded(&request),
+            rewarded(&step_request(
+                ProtocolVersion::V1_0_0,
+                &[1_000, 2_000, 3_000]
+            ))
+        );
+        assert!(withheld.is_empty());
+    }
+
+    #[test]
+    fn should_slash_rather_than_evict_equivocators_if_enabled() {
+        let mut rng = crate::new_rng();
+        let equivocator = PublicKey::random(&mut rng);
+        let inactive_validator = PublicKey::random(&mut rng);
+        let era_report = EraReport {
+            equivocators: vec![equivocator.clone()],
+            rewards: BTreeMap::new(),
+            inactive_validators: vec![inactive_validator.clone()],
+        };
+        let step_request = |slash_equivocators| {
+            create_step_request(
+                ProtocolVersion::V1_0_0,
+                Digest::default(),
+                &era_report,
+                0,
+                EraId::new(1),
+                slash_equivocators,
+                None,
+            )
+        };
+        let validator_ids = |items: &[EvictItem]| {
+            items
+                .iter()
+                .map(|item| item.validator_id.clone())
+                .collect_vec()
+        };
+
+        let step_request_with_slashing = step_request(true);
+        assert_eq!(
+            step_request_with_slashing
+                .slash_items
+                .iter()
+                .map(|item| item.validator_id.clone())
+                .collect_vec(),
+            vec![equivocator.clone()]
+        );
+        assert_eq!(
+            validator_ids(&step_request_with_slashing.evict_items),
+            vec![inactive_validator.clone()]
+        );
+
+        let step_request_without_slashing = step_request(false);
+        assert!(step_request_without_slashing.slash_items.is_empty());
+        let mut evicted_validators = vec![inactive_validator, equivocator];
+        evicted_validators.sort();
+        assert_eq!(
+            validator_ids(&step_request_without_slashing.evict_items),
+            evicted_validators
+        );
+    }
+
+    #[test]
+    fn should_report_missing_system_contract_registry() {
+        let fixture = TestFixture::new();
+        let protocol_version = fixture.chainspec.protocol_version();
+
+        // The empty root is present in global state, but holds no system contract registry.
+        let empty_root_hash = fixture.engine_state().get_state().empty_root();
+        let error = get_upcoming_era_validators(
+            fixture.engine_state(),
+            CorrelationId::new(),
+            protocol_version,
+            empty_root_hash,
+        )
+        .unwrap_err();
+        assert!(
+            matches!(
+                error,
+                BlockExecutionError::MissingSystemContractRegistry { state_root_hash }
+                    if state_root_hash == empty_root_hash
+            ),
+            "unexpected error: {:?}",
+            error
+        );
+
+        assert!(get_upcoming_era_validators(
+            fixture.engine_state(),
+            CorrelationId::new(),
+            protocol_version,
+            fixture.post_genesis_state_root_hash,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn should_serve_upcoming_era_validators_from_cache() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let protocol_version = fixture.chainspec.protocol_version();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let cache = EraValidatorsCache::new(NonZeroUsize::new(10).unwrap());
+        let get_cached = |state_root_hash| {
+            get_cached_upcoming_era_validators(
+                fixture.engine_state(),
+                CorrelationId::new(),
+                Some(&*metrics),
+                Some(&cache),
+                protocol_version,
+                state_root_hash,
+            )
+            .unwrap()
+        };
+
+        let BlockAndExecutionResults {
+            block,
+            maybe_step_effect_and_upcoming_era_validators,
+            ..
+        } = fixture
+            .execute_block_with_metrics(
+                vec![fixture.transfer(&mut rng, 1)],
+                Some(EraReport::default()),
+                &metrics,
+                BlockExecutionOptions {
+                    era_validators_cache: Some(&cache),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        let upcoming_era_validators = maybe_step_effect_and_upcoming_era_validators
+            .unwrap()
+            .upcoming_era_validators;
+        assert_eq!(cache.len(), 1);
+        assert_eq!(metrics.era_validators_cache_hits.get(), 0);
+
+        // Reading the era validators again at the switch block's state root is served from the
+        // cache.
+        assert_eq!(
+            get_cached(*block.state_root_hash()),
+            upcoming_era_validators
+        );
+        assert_eq!(metrics.era_validators_cache_hits.get(), 1);
+        assert_eq!(cache.len(), 1);
+
+        // Those at another state root are read from global state, and cached in turn.
+        let expected_era_validators = get_upcoming_era_validators(
+            fixture.engine_state(),
+            CorrelationId::new(),
+            protocol_version,
+            fixture.post_genesis_state_root_hash,
+        )
+        .unwrap();
+        assert_eq!(
+            get_cached(fixture.post_genesis_state_root_hash),
+            expected_era_validators
+        );
+        assert_eq!(metrics.era_validators_cache_hits.get(), 1);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn should_evict_inactive_equivocator_once() {
+        let fixture = TestFixture::new();
+        let inactive_equivocator = TestFixture::validator_public_key(1);
+        let inactive_validator = TestFixture::validator_public_key(2);
+        let era_report = |equivocators| EraReport {
+            equivocators,
+            rewards: BTreeMap::new(),
+            inactive_validators: vec![inactive_equivocator.clone(), inactive_validator.clone()],
+        };
+        let overlapping_era_report = era_report(vec![inactive_equivocator.clone()]);
+        let disjoint_era_report = era_report(vec![]);
+
+        let step_request = create_step_request(
+            ProtocolVersion::V1_0_0,
+            Digest::default(),
+            &overlapping_era_report,
+            0,
+            EraId::new(1),
+            false,
+            None,
+        );
+        let mut evicted_validators = vec![inactive_equivocator.clone(), inactive_validator.clone()];
+        evicted_validators.sort();
+        assert_eq!(
+            step_request
+                .evict_items
+                .iter()
+                .map(|item| item.validator_id.clone())
+                .collect_vec(),
+            evicted_validators
+        );
+
+        // The step is the same as if the inactive validator had not also equivocated.
+        let era_end_timestamp_millis = Timestamp::now().millis();
+        let step = |era_report: &EraReport<PublicKey>| {
+            commit_step(
+                &fixture.engine_state().get_scratch_engine_state(),
+                CorrelationId::new(),
+                None,
+                fixture.chainspec.protocol_version(),
+                fixture.post_genesis_state_root_hash,
+                era_report,
+                era_end_timestamp_millis,
+                EraId::new(1),
+                false,
+                None,
+                None,
+                None,
+            )
+            .unwrap()
+            .0
+            .execution_journal
+        };
+        assert_eq!(step(&overlapping_era_report), step(&disjoint_era_report));
+    }
+
+    #[test]
+    fn should_commit_step_for_each_era_report() {
+        let fixture = TestFixture::new();
+        let protocol_version = fixture.chainspec.protocol_version();
+        let auction_delay = fixture.chainspec.core_config.auction_delay;
+        let inactive_validator = TestFixture::validator_public_key(3);
+        let first_era_report = era_report_rewarding_validators(&[1_000, 2_000, 3_000]);
+        let second_era_report = EraReport {
+            inactive_validators: vec![inactive_validator.clone()],
+            ..era_report_rewarding_validators(&[1_000, 2_000])
+        };
+        let commit = |era_reports: &[EraReport<PublicKey>]| {
+            let scratch_state = fixture.engine_state().get_scratch_engine_state();
+            let (execution_journal, step_outcome_summary) = commit_steps(
+                &scratch_state,
+                CorrelationId::new(),
+                None,
+                protocol_version,
+                fixture.post_genesis_state_root_hash,
+                EraId::new(0),
+                era_reports,
+                Timestamp::now().millis(),
+                false,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+            let upcoming_era_validators = get_upcoming_era_validators(
+                &scratch_state,
+                CorrelationId::new(),
+                protocol_version,
+                fixture.post_genesis_state_root_hash,
+            )
+            .unwrap();
+            let state_root_hash = write_scratch_to_db(
+                fixture.engine_state(),
+                None,
+                fixture.post_genesis_state_root_hash,
+                scratch_state,
+            )
+            .unwrap();
+            (
+                execution_journal,
+                step_outcome_summary,
+                upcoming_era_validators,
+                state_root_hash,
+            )
+        };
+
+        let (single_journal, _, single_upcoming_era_validators, single_root) =
+            commit(&[first_era_report.clone()]);
+        let (journal, step_outcome_summary, upcoming_era_validators, root) =
+            commit(&[first_era_report, second_era_report]);
+        // The second step ran on top of the first one, ending era 1.
+        assert!(journal.len() > single_journal.len());
+        assert_ne!(root, single_root);
+        let last_era_id = EraId::new(2 + auction_delay);
+        assert!(!single_upcoming_era_validators.contains_key(&last_era_id));
+        assert!(!upcoming_era_validators[&last_era_id].contains_key(&inactive_validator));
+        assert_eq!(step_outcome_summary.evicted, vec![inactive_validator]);
+        assert_eq!(
+            step_outcome_summary.rewarded[&TestFixture::validator_public_key(1)],
+            U512::from(2_000)
+        );
+    }
+
+    #[test]
+    fn should_reject_additional_era_reports_before_protocol_version() {
+        let fixture = TestFixture::new();
+        assert!(fixture.chainspec.protocol_version() < MULTI_ERA_SWITCH_PROTOCOL_VERSION);
+        let era_report = era_report_rewarding_validators(&[1_000]);
+        let error = fixture
+            .execute_block_with_options(
+                vec![],
+                Some(era_report.clone()),
+                BlockExecutionOptions {
+                    additional_era_reports: &[era_report],
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::UnexpectedEraReports {
+                count: 1,
+                block_height: 0,
+            }
+        ));
+    }
+
+    #[test]
+    fn should_summarize_step_outcome() {
+        let fixture = TestFixture::new();
+        let equivocator = TestFixture::validator_public_key(1);
+        let inactive_validator = TestFixture::validator_public_key(2);
+        let era_report = EraReport {
+            equivocators: vec![equivocator.clone()],
+            inactive_validators: vec![inactive_validator.clone()],
+            ..era_report_rewarding_validators(&[1_000, 2_000, 3_000])
+        };
+        let rewarded: BTreeMap<_, _> = era_report
+            .rewards
+            .iter()
+            .map(|(validator_id, reward)| (validator_id.clone(), U512::from(*reward)))
+            .collect();
+
+        let BlockAndExecutionResults {
+            maybe_step_effect_and_upcoming_era_validators,
+            ..
+        } = fixture
+            .execute_block(vec![], Some(era_report.clone()))
+            .unwrap();
+        let step_outcome_summary = maybe_step_effect_and_upcoming_era_validators
+            .unwrap()
+            .step_outcome_summary;
+        let mut evicted = vec![equivocator.clone(), inactive_validator.clone()];
+        evicted.sort();
+        assert_eq!(
+            step_outcome_summary,
+            StepOutcomeSummary {
+                evicted,
+                slashed: vec![],
+                rewarded: rewarded.clone(),
+                withheld: BTreeMap::new(),
+            }
+        );
+
+        let (_, step_outcome_summary) = commit_step(
+            &fixture.engine_state().get_scratch_engine_state(),
+            CorrelationId::new(),
+            None,
+            fixture.chainspec.protocol_version(),
+            fixture.post_genesis_state_root_hash,
+            &era_report,
+            Timestamp::now().millis(),
+            EraId::new(1),
+            true,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            step_outcome_summary,
+            StepOutcomeSummary {
+                evicted: vec![inactive_validator],
+                slashed: vec![equivocator],
+                rewarded,
+                withheld: BTreeMap::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn should_commit_maintenance_eviction() {
+        let fixture = TestFixture::with_chainspec(|chainspec| {
+            chainspec.core_config.allow_maintenance_steps = true;
+        });
+        let evicted_validator = TestFixture::validator_public_key(1);
+        let step_success = fixture
+            .contract_runtime
+            .commit_maintenance_step(
+                fixture.post_genesis_state_root_hash,
+                vec![evicted_validator.clone()],
+                vec![],
+                EraId::new(1),
+                Timestamp::now(),
+            )
+            .unwrap();
+        assert_ne!(
+            step_success.post_state_hash,
+            fixture.post_genesis_state_root_hash
+        );
+
+        let bids = fixture
+            .engine_state()
+            .get_bids(
+                CorrelationId::new(),
+                GetBidsRequest::new(step_success.post_state_hash),
+            )
+            .unwrap()
+            .into_success()
+            .unwrap();
+        assert!(bids[&evicted_validator].inactive());
+        assert!(!bids[&TestFixture::validator_public_key(2)].inactive());
+    }
+
+    #[test]
+    fn should_not_commit_maintenance_step_unless_allowed() {
+        let fixture = TestFixture::new();
+        assert!(!fixture.chainspec.core_config.allow_maintenance_steps);
+        assert!(matches!(
+            fixture.contract_runtime.commit_maintenance_step(
+                fixture.post_genesis_state_root_hash,
+                vec![TestFixture::validator_public_key(1)],
+                vec![],
+                EraId::new(1),
+                Timestamp::now(),
+            ),
+            Err(MaintenanceStepError::NotAllowed)
+        ));
+    }
+
+    #[test]
+    fn should_execute_batch_of_independent_deploys() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let execution_state = SpeculativeExecutionState {
+            state_root_hash: fixture.post_genesis_state_root_hash,
+            block_time: Timestamp::now(),
+            protocol_version: fixture.chainspec.protocol_version(),
+            proposer: None,
+            era_id: None,
+        };
+        // The first two deploys are from the same account, and no validator can afford the third.
+        let deploy_items = vec![
+            DeployItem::from(fixture.transfer(&mut rng, 1)),
+            DeployItem::from(fixture.transfer(&mut rng, 1)),
+            DeployItem::from(fixture.transfer_amount(&mut rng, 2, U512::MAX)),
+        ];
+
+        let results = execute_only_batch(
+            fixture.engine_state(),
+            execution_state.clone(),
+            deploy_items.clone(),
+        );
+        assert_eq!(results.len(), 3);
+        assert!(matches!(
+            results[2],
+            Ok(Some(ExecutionResult::Failure { .. }))
+        ));
+        // Each deploy is executed as if on its own.
+        for (result, deploy_item) in results.into_iter().zip(deploy_items.clone()) {
+            let expected = execute_only_at(
+                fixture.engine_state(),
+                execution_state.clone(),
+                deploy_item,
+                PublicKey::System,
+            )
+            .unwrap();
+            assert_eq!(result.unwrap(), expected);
+        }
+
+        let missing_state_root_hash = Digest::hash(b"missing state root");
+        let results = execute_only_batch(
+            fixture.engine_state(),
+            SpeculativeExecutionState {
+                state_root_hash: missing_state_root_hash,
+                ..execution_state
+            },
+            deploy_items,
+        );
+        assert_eq!(results.len(), 3);
+        for result in results {
+            assert!(matches!(
+                result,
+                Err(engine_state::Error::RootNotFound(state_root_hash))
+                    if state_root_hash == missing_state_root_hash
+            ));
+        }
+    }
+
+    #[test]
+    fn should_speculatively_execute_at_historical_state_root() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let historical_state_root_hash = fixture.post_genesis_state_root_hash;
+        let BlockAndExecutionResults { block, .. } = fixture
+            .execute_block(vec![fixture.transfer(&mut rng, 1)], None)
+            .unwrap();
+        assert_ne!(*block.state_root_hash(), historical_state_root_hash);
+
+        let proposer = TestFixture::validator_public_key(2);
+        let execution_state = SpeculativeExecutionState {
+            state_root_hash: historical_state_root_hash,
+            block_time: Timestamp::now(),
+            protocol_version: fixture.chainspec.protocol_version(),
+            proposer: None,
+            era_id: None,
+        };
+        let deploy = DeployItem::from(fixture.transfer(&mut rng, 1));
+        let execution_result =
+            execute_only_at(fixture.engine_state(), execution_state, deploy, proposer).unwrap();
+        assert!(matches!(
+            execution_result,
+            Some(ExecutionResult::Success { .. })
+        ));
+    }
+
+    #[test]
+    fn should_reuse_cached_scratch_state_for_speculative_execution() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let pre_state_root_hash = fixture.post_genesis_state_root_hash;
+        let BlockAndExecutionResults { block, .. } = fixture
+            .execute_block(vec![fixture.transfer(&mut rng, 1)], None)
+            .unwrap();
+        let post_state_root_hash = *block.state_root_hash();
+
+        let executor = SpeculativeExecutor::new(
+            Arc::clone(fixture.contract_runtime.engine_state()),
+            NonZeroUsize::new(1).unwrap(),
+        );
+        let execution_state = SpeculativeExecutionState {
+            state_root_hash: pre_state_root_hash,
+            block_time: Timestamp::now(),
+            protocol_version: fixture.chainspec.protocol_version(),
+            proposer: None,
+            era_id: None,
+        };
+        let cached = executor.scratch_state(pre_state_root_hash).unwrap();
+        for _ in 0..2 {
+            let deploy = fixture.transfer(&mut rng, 2);
+            let execution_result = executor
+                .execute_only(
+                    execution_state.clone(),
+                    DeployItem::from(deploy.clone()),
+                    deploy.header(),
+                    &fixture.chainspec.network_config.name,
+                    Timestamp::now(),
+                )
+                .unwrap();
+            assert!(matches!(
+                execution_result,
+                Some(ExecutionResult::Success { .. })
+            ));
+            assert!(Arc::ptr_eq(
+                &executor.scratch_state(pre_state_root_hash).unwrap(),
+                &cached
+            ));
+        }
+
+        // Executing on top of another root evicts the least recently used scratch state.
+        executor.scratch_state(post_state_root_hash).unwrap();
+        assert!(!Arc::ptr_eq(
+            &executor.scratch_state(pre_state_root_hash).unwrap(),
+            &cached
+        ));
+
+        let missing_state_root_hash = Digest::hash(b"missing state root");
+        assert!(matches!(
+            executor.scratch_state(missing_state_root_hash),
+            Err(engine_state::Error::RootNotFound(root)) if root == missing_state_root_hash
+        ));
+    }
+
+    /// Global state which can be read from, but not committed to.
+    struct StateProviderOnly(Arc<EngineState<LmdbGlobalState>>);
+
+    impl StateProvider for StateProviderOnly {
+        type Error = <LmdbGlobalState as StateProvider>::Error;
+
+        type Reader = <LmdbGlobalState as StateProvider>::Reader;
+
+        fn checkout(&self, state_hash: Digest) -> Result<Option<Self::Reader>, Self::Error> {
+            self.0.get_state().checkout(state_hash)
+        }
+
+        fn empty_root(&self) -> Digest {
+            self.0.get_state().empty_root()
+        }
+
+        fn get_trie_full(
+            &self,
+            correlation_id: CorrelationId,
+            trie_key: &Digest,
+        ) -> Result<Option<TrieRaw>, Self::Error> {
+            self.0.get_state().get_trie_full(correlation_id, trie_key)
+        }
+
+        fn put_trie(
+            &self,
+            correlation_id: CorrelationId,
+            trie: &[u8],
+        ) -> Result<Digest, Self::Error> {
+            self.0.get_state().put_trie(correlation_id, trie)
+        }
+
+        fn missing_children(
+            &self,
+            correlation_id: CorrelationId,
+            trie_raw: &[u8],
+        ) -> Result<Vec<Digest>, Self::Error> {
+            self.0
+                .get_state()
+                .missing_children(correlation_id, trie_raw)
+        }
+
+        fn delete_keys(
+            &self,
+            correlation_id: CorrelationId,
+            root: Digest,
+            keys_to_delete: &[Key],
+        ) -> Result<DeleteResult, Self::Error> {
+            self.0
+                .get_state()
+                .delete_keys(correlation_id, root, keys_to_delete)
+        }
+    }
+
+    #[test]
+    fn should_execute_deploy_with_read_only_executor() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let engine_state = Arc::clone(fixture.contract_runtime.engine_state());
+        let executor = ReadOnlyExecutor::new(
+            StateProviderOnly(engine_state),
+            fixture.engine_state().config().clone(),
+        );
+        let execution_state = SpeculativeExecutionState {
+            state_root_hash: fixture.post_genesis_state_root_hash,
+            block_time: Timestamp::now(),
+            protocol_version: fixture.chainspec.protocol_version(),
+            proposer: None,
+            era_id: None,
+        };
+        let deploy = fixture.transfer(&mut rng, 1);
+        let execution_result = executor
+            .execute_only(
+                execution_state,
+                DeployItem::from(deploy.clone()),
+                deploy.header(),
+                &fixture.chainspec.network_config.name,
+                Timestamp::now(),
+            )
+            .unwrap();
+        assert!(matches!(
+            execution_result,
+            Some(ExecutionResult::Success { .. })
+        ));
+
+        // Writes through the executor's global state are refused.
+        assert!(matches!(
+            executor.engine_state.apply_effect(
+                CorrelationId::new(),
+                fixture.post_genesis_state_root_hash,
+                AdditiveMap::new(),
+            ),
+            Err(engine_state::Error::Exec(execution::Error::Storage(
+                StorageError::CommitError(CommitError::ReadOnly)
+            )))
+        ));
+    }
+
+    #[test]
+    fn should_pass_engine_self_check() {
+        let fixture = TestFixture::new();
+        engine_self_check(
+            fixture.engine_state(),
+            fixture.post_genesis_state_root_hash,
+            fixture.chainspec.protocol_version(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn should_fail_engine_self_check_on_missing_root() {
+        let fixture = TestFixture::new();
+        let missing_state_root_hash = Digest::hash(b"missing state root");
+        match engine_self_check(
+            fixture.engine_state(),
+            missing_state_root_hash,
+            fixture.chainspec.protocol_version(),
+        ) {
+            Err(SelfCheckError::MissingRoot { state_root_hash }) => {
+                assert_eq!(state_root_hash, missing_state_root_hash)
+            }
+            other => panic!("unexpected self-check result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_not_speculatively_execute_invalid_deploys() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let execution_state = SpeculativeExecutionState {
+            state_root_hash: fixture.post_genesis_state_root_hash,
+            block_time: Timestamp::now(),
+            protocol_version: fixture.chainspec.protocol_version(),
+            proposer: None,
+            era_id: None,
+        };
+        let deploy = fixture.transfer(&mut rng, 1);
+        let speculatively_execute = |chain_name: &str, current_time: Timestamp| {
+            execute_only(
+                fixture.engine_state(),
+                None,
+                execution_state.clone(),
+                DeployItem::from(deploy.clone()),
+                deploy.header(),
+                chain_name,
+                current_time,
+            )
+        };
+        let chain_name = fixture.chainspec.network_config.name.as_str();
+
+        assert!(matches!(
+            speculatively_execute(chain_name, Timestamp::now()),
+            Ok(Some(ExecutionResult::Success { .. }))
+        ));
+
+        let after_expiry = deploy.header().expires() + TimeDiff::from_seconds(1);
+        assert!(matches!(
+            speculatively_execute(chain_name, after_expiry),
+            Err(SpeculativeExecutionError::InvalidDeploy(
+                InvalidSpeculativeDeploy::Expired { expiry, current_time }
+            )) if expiry == deploy.header().expires() && current_time == after_expiry
+        ));
+
+        assert!(matches!(
+            speculatively_execute("other-chain", Timestamp::now()),
+            Err(SpeculativeExecutionError::InvalidDeploy(
+                InvalidSpeculativeDeploy::InvalidChainName { expected, got }
+            )) if expected == "other-chain" && got == chain_name
+        ));
+    }
+
+    #[test]
+    fn should_not_speculatively_execute_at_missing_state_root() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let missing_state_root_hash = Digest::hash(b"missing state root");
+        let execution_state = SpeculativeExecutionState {
+            state_root_hash: missing_state_root_hash,
+            block_time: Timestamp::now(),
+            protocol_version: fixture.chainspec.protocol_version(),
+            proposer: None,
+            era_id: None,
+        };
+        let deploy = DeployItem::from(fixture.transfer(&mut rng, 1));
+        let error = execute_only_at(
+            fixture.engine_state(),
+            execution_state,
+            deploy,
+            PublicKey::System,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            engine_state::Error::RootNotFound(state_root_hash)
+                if state_root_hash == missing_state_root_hash
+        ));
+    }
+
+    #[test]
+    fn should_return_execution_journal_of_speculative_execution() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let execution_state = SpeculativeExecutionState {
+            state_root_hash: fixture.post_genesis_state_root_hash,
+            block_time: Timestamp::now(),
+            protocol_version: fixture.chainspec.protocol_version(),
+            proposer: None,
+            era_id: None,
+        };
+        let deploy = DeployItem::from(fixture.transfer(&mut rng, 1));
+        let (execution_result, execution_journal) =
+            execute_only_with_effects(fixture.engine_state(), execution_state, deploy)
+                .unwrap()
+                .unwrap();
+        assert!(matches!(execution_result, ExecutionResult::Success { .. }));
+
+        // A transfer writes at least the balances of the source and target purses.
+        assert!(!execution_journal.is_empty());
+        assert!(execution_journal
+            .iter()
+            .any(|(_, transform)| matches!(transform, Transform::Write(_))));
+    }
+
+    #[test]
+    fn should_compute_post_state_root_of_speculative_execution() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let pre_state_root_hash = fixture.post_genesis_state_root_hash;
+        let execution_state = SpeculativeExecutionState {
+            state_root_hash: pre_state_root_hash,
+            block_time: Timestamp::now(),
+            protocol_version: fixture.chainspec.protocol_version(),
+            proposer: None,
+            era_id: None,
+        };
+        let deploy = DeployItem::from(fixture.transfer(&mut rng, 1));
+
+        let (execution_result, speculative_root) = execute_only_with_root(
+            fixture.engine_state(),
+            execution_state.clone(),
+            deploy.clone(),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(matches!(execution_result, ExecutionResult::Success { .. }));
+        assert_ne!(speculative_root, pre_state_root_hash);
+        // Nothing was written to LMDB.
+        assert!(fixture
+            .engine_state()
+            .tracking_copy(speculative_root)
+            .unwrap()
+            .is_none());
+
+        // Committing the same effects to LMDB yields the same root, while the pre-state root is
+        // left as it was.
+        let (reference_result, effects) =
+            execute_only_with_effects(fixture.engine_state(), execution_state, deploy)
+                .unwrap()
+                .unwrap();
+        assert_eq!(reference_result, execution_result);
+        let reference_root = commit_transforms(
+            fixture.engine_state(),
+            CorrelationId::new(),
+            None,
+            pre_state_root_hash,
+            effects,
+        )
+        .unwrap();
+        assert_eq!(speculative_root, reference_root);
+        assert!(fixture
+            .engine_state()
+            .tracking_copy(pre_state_root_hash)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn should_return_checksums_written_to_checksum_registry() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = vec![fixture.transfer(&mut rng, 1), fixture.transfer(&mut rng, 2)];
+        let results = fixture.execute_block(deploys.clone(), None).unwrap();
+        let checksums = results.checksums().unwrap();
+
+        let expected_approvals_checksum =
+            types::compute_approvals_checksum(deploys.iter().map(Deploy::fetch_id).collect())
+                .unwrap();
+        let expected_execution_results_checksum = results
+            .execution_results
+            .iter()
+            .map(|deploy_execution_info| deploy_execution_info.result.clone())
+            .collect_vec()
+            .hash()
+            .unwrap();
+        assert_eq!(checksums.approvals_checksum(), expected_approvals_checksum);
+        assert_eq!(
+            checksums.execution_results_checksum(),
+            expected_execution_results_checksum
+        );
+
+        let checksum_registry = fixture
+            .engine_state()
+            .get_checksum_registry(CorrelationId::new(), *results.block.state_root_hash())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            checksum_registry.get(APPROVALS_CHECKSUM_NAME),
+            Some(&expected_approvals_checksum)
+        );
+        assert_eq!(
+            checksum_registry.get(EXECUTION_RESULTS_CHECKSUM_NAME),
+            Some(&expected_execution_results_checksum)
+        );
+    }
+
+    #[test]
+    fn should_reproduce_execution_with_seed_override() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        // Transfers to new accounts create their main purses, whose addresses depend on the seed.
+        let deploys = (1..=2)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+        let execute = |seed_override: Option<[u8; 32]>| {
+            execute_finalized_block(
+                fixture.engine_state(),
+                None,
+                fixture.chainspec.protocol_version(),
+                execution_pre_state.clone(),
+                finalized_block.clone(),
+                deploys.clone(),
+                EraId::new(0),
+                0,
+                0,
+                BlockExecutionOptions {
+                    slash_equivocators: fixture.chainspec.core_config.slash_equivocators,
+                    seed_override,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap()
+        };
+
+        let unseeded = execute(None);
+        let seeded = execute(Some([1; 32]));
+        assert_ne!(
+            seeded.block.state_root_hash(),
+            unseeded.block.state_root_hash()
+        );
+        assert_eq!(
+            execute(Some([1; 32])).block.state_root_hash(),
+            seeded.block.state_root_hash()
+        );
+        assert_ne!(
+            execute(Some([2; 32])).block.state_root_hash(),
+            seeded.block.state_root_hash()
+        );
+    }
+
+    #[test]
+    fn should_reject_protocol_version_downgrade() {
+        let mut rng = crate::new_rng();
+        let genesis_protocol_version = ProtocolVersion::from_parts(2, 0, 0);
+        let fixture = TestFixture::with_chainspec(|chainspec| {
+            chainspec.protocol_config.version = genesis_protocol_version;
+        });
+        let parent = fixture
+            .execute_block(vec![fixture.transfer(&mut rng, 1)], None)
+            .unwrap()
+            .block;
+
+        // Executing under the same or a later protocol version is allowed.
+        for protocol_version in [
+            genesis_protocol_version,
+            ProtocolVersion::from_parts(2, 1, 0),
+        ] {
+            fixture
+                .execute_child_block(&parent, vec![], protocol_version)
+                .unwrap();
         }
-    })
-}
 
-fn execute<S>(
-    engine_state: &EngineState<S>,
-    metrics: Option<Arc<Metrics>>,
-    execute_request: ExecuteRequest,
-) -> Result<ExecutionResults, engine_state::Error>
-where
-    S: StateProvider + CommitProvider,
-    S::Error: Into<execution::Error>,
-{
-    trace!(?execute_request, "execute");
-    let correlation_id = CorrelationId::new();
-    let start = Instant::now();
-    let result = engine_state.run_execute(correlation_id, execute_request);
-    if let Some(metrics) = metrics {
-        metrics.run_execute.observe(start.elapsed().as_secs_f64());
+        let error = fixture
+            .execute_child_block(
+                &parent,
+                vec![fixture.transfer(&mut rng, 2)],
+                ProtocolVersion::V1_0_0,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::ProtocolVersionRegression {
+                expected_min,
+                actual,
+            } if expected_min == genesis_protocol_version && actual == ProtocolVersion::V1_0_0
+        ));
     }
-    trace!(?result, "execute result");
-    result
-}
 
-fn commit_step<S>(
-    engine_state: &EngineState<S>,
-    maybe_metrics: Option<Arc<Metrics>>,
-    protocol_version: ProtocolVersion,
-    pre_state_root_hash: Digest,
-    era_report: &EraReport<PublicKey>,
-    era_end_timestamp_millis: u64,
-    next_era_id: EraId,
-) -> Result<StepSuccess, StepError>
-where
-    S: StateProvider + CommitProvider,
-    S::Error: Into<execution::Error>,
-{
-    // Extract the rewards and the inactive validators if this is a switch block
-    let EraReport {
-        equivocators,
-        rewards,
-        inactive_validators,
-    } = era_report;
+    #[test]
+    fn should_reject_block_exceeding_max_trie_depth() {
+        const MAX_TRIE_DEPTH: usize = 16;
 
-    let reward_items = rewards
-        .iter()
-        .map(|(vid, value)| RewardItem::new(vid.clone(), *value))
-        .collect();
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let correlation_id = CorrelationId::new();
+        let deploy = fixture.transfer(&mut rng, 1);
+        let deploy_hash_bytes = deploy.hash().inner().value();
+        let deploy_info_key = Key::DeployInfo(casper_types::DeployHash::new(deploy_hash_bytes));
 
-    // Both inactive validators and equivocators are evicted
-    let evict_items = inactive_validators
-        .iter()
-        .chain(equivocators)
-        .cloned()
-        .map(EvictItem::new)
-        .collect();
+        // An ordinary transfer stays well within the limit.
+        fixture
+            .execute_block_with_options(
+                vec![deploy.clone()],
+                None,
+                BlockExecutionOptions {
+                    max_trie_depth: Some(MAX_TRIE_DEPTH),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
 
-    let step_request = StepRequest {
-        pre_state_hash: pre_state_root_hash,
-        protocol_version,
-        reward_items,
-        // Note: The Casper Network does not slash, but another network could
-        slash_items: vec![],
-        evict_items,
-        next_era_id,
-        era_end_timestamp_millis,
-    };
+        // Seed keys diverging from the deploy info key written by the transfer at each byte of the
+        // deploy hash, so that the trie branches along its whole path.
+        let scratch_state = fixture.engine_state().get_scratch_engine_state();
+        let mut effects = AdditiveMap::new();
+        for index in 0..deploy_hash_bytes.len() {
+            let mut seeded_bytes = deploy_hash_bytes;
+            seeded_bytes[index] ^= 1;
+            let seeded_key = Key::DeployInfo(casper_types::DeployHash::new(seeded_bytes));
+            let seeded_value = StoredValue::CLValue(CLValue::from_t(index as u64).unwrap());
+            let _ = effects.insert(seeded_key, Transform::Write(seeded_value));
+        }
+        scratch_state
+            .apply_effect(
+                correlation_id,
+                fixture.post_genesis_state_root_hash,
+                effects,
+            )
+            .unwrap();
 
-    // Have the EE commit the step.
-    let correlation_id = CorrelationId::new();
-    let start = Instant::now();
-    let result = engine_state.commit_step(correlation_id, step_request);
-    if let Some(metrics) = maybe_metrics {
-        let elapsed = start.elapsed().as_secs_f64();
-        metrics.commit_step.observe(elapsed);
-        metrics.latest_commit_step.set(elapsed);
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let error = fixture
+            .execute_block_with_metrics(
+                vec![deploy],
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    scratch_state: Some(scratch_state),
+                    max_trie_depth: Some(MAX_TRIE_DEPTH),
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+        match error {
+            BlockExecutionError::TrieDepthExceeded { key, depth, max } => {
+                assert_eq!(key, deploy_info_key);
+                assert!(depth > MAX_TRIE_DEPTH);
+                assert_eq!(max, MAX_TRIE_DEPTH);
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+        // The block was rejected before anything was written to LMDB or flushed.
+        assert_eq!(metrics.scratch_write_to_db.get_sample_count(), 0);
+        assert_eq!(metrics.flush_environment.get_sample_count(), 0);
     }
-    trace!(?result, "step response");
-    result
-}
 
-/// Computes the root hash for a Merkle tree constructed from the hashes of execution results.
-///
-/// NOTE: We're hashing vector of execution results, instead of just their hashes, b/c when a joiner
-/// node receives the chunks of *full data* it has to be able to verify it against the Merkle root.
-fn compute_execution_results_checksum(
-    execution_results: &Vec<ExecutionResult>,
-) -> Result<Digest, BlockCreationError> {
-    execution_results
-        .hash()
-        .map_err(BlockCreationError::BytesRepr)
-}
+    #[test]
+    fn should_compute_execution_results_checksum_incrementally() {
+        let mut rng = crate::new_rng();
+        let execution_results: Vec<ExecutionResult> = (0..3).map(|_| rng.gen()).collect();
+        let mut builder = VecChecksumBuilder::new();
+        for execution_result in &execution_results {
+            builder.push(execution_result).unwrap();
+        }
+        assert_eq!(
+            builder.finalize_with(|bytes| DefaultExecutionResultsChecksum.checksum(bytes)),
+            execution_results.hash().unwrap()
+        );
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn should_compute_execution_results_checksum_without_collecting_results() {
+        let mut rng = crate::new_rng();
+        for count in [0, 1, 10] {
+            let execution_results: Vec<ExecutionResult> = (0..count).map(|_| rng.gen()).collect();
+            assert_eq!(
+                compute_execution_results_checksum(
+                    &DefaultExecutionResultsChecksum,
+                    execution_results.iter()
+                )
+                .unwrap(),
+                execution_results.hash().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn should_accumulate_execution_results_checksum() {
+        let mut rng = crate::new_rng();
+        let mut execution_results: Vec<ExecutionResult> = (0..3).map(|_| rng.gen()).collect();
+        // Results spanning several chunks, including one ending on a chunk boundary.
+        let large_result = |error_message_length: usize| ExecutionResult::Failure {
+            effect: Default::default(),
+            transfers: vec![],
+            cost: U512::one(),
+            error_message: "x".repeat(error_message_length),
+        };
+        let serialized_length = execution_results.to_bytes().unwrap().len();
+        let overhead = large_result(0).serialized_length();
+        execution_results.push(large_result(
+            ChunkWithProof::CHUNK_SIZE_BYTES * 2 - serialized_length - overhead,
+        ));
+        execution_results.push(large_result(ChunkWithProof::CHUNK_SIZE_BYTES / 2));
+
+        let mut accumulator = ExecutionResultsAccumulator::new();
+        for appended in 0..=execution_results.len() {
+            if appended > 0 {
+                accumulator
+                    .append(&execution_results[appended - 1])
+                    .unwrap();
+            }
+            assert_eq!(
+                accumulator.root(),
+                compute_execution_results_checksum(
+                    &DefaultExecutionResultsChecksum,
+                    execution_results[..appended].iter()
+                )
+                .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn should_compute_same_execution_results_checksum_with_default_scheme() {
+        let mut rng = crate::new_rng();
+        let execution_results: Vec<ExecutionResult> = (0..3).map(|_| rng.gen()).collect();
+        let serialized_execution_results = execution_results.to_bytes().unwrap();
+        let expected_checksum = execution_results.hash().unwrap();
+        assert_eq!(
+            DefaultExecutionResultsChecksum.checksum(&serialized_execution_results),
+            expected_checksum
+        );
+
+        // Every protocol version so far uses the default scheme.
+        for protocol_version in [
+            ProtocolVersion::V1_0_0,
+            ProtocolVersion::from_parts(2, 0, 0),
+        ] {
+            assert_eq!(
+                execution_results_checksum_scheme(protocol_version)
+                    .checksum(&serialized_execution_results),
+                expected_checksum
+            );
+        }
+    }
 
     #[test]
     fn calculation_is_safe_with_invalid_input() {
@@ -892,4 +8121,654 @@ mod tests {
             None,
         );
     }
+
+    #[test]
+    fn should_compute_state_root_of_full_execution() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=2)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+
+        for era_report in [None, Some(EraReport::default())] {
+            let block_and_execution_results = fixture
+                .execute_block(deploys.clone(), era_report.clone())
+                .unwrap();
+            let (execution_pre_state, finalized_block) =
+                fixture.first_block(&deploys, era_report, PublicKey::System);
+            let state_root_hash = compute_state_root_for_block(
+                fixture.engine_state(),
+                None,
+                fixture.chainspec.protocol_version(),
+                execution_pre_state,
+                finalized_block,
+                deploys.clone(),
+                EraId::new(0),
+                0,
+                0,
+                BlockExecutionOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(
+                state_root_hash,
+                *block_and_execution_results.block.state_root_hash()
+            );
+        }
+    }
+
+    /// A module whose `call` entry point does nothing.
+    const DO_NOTHING_WASM: [u8; 39] = [
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+        0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section: `fn()`
+        0x03, 0x02, 0x01, 0x00, // function section: one function of type 0
+        0x05, 0x03, 0x01, 0x00, 0x01, // memory section: one page
+        0x07, 0x08, 0x01, 0x04, b'c', b'a', b'l', b'l', 0x00, 0x00, // export section: `call`
+        0x0a, 0x04, 0x01, 0x02, 0x00, 0x0b, // code section: empty body
+    ];
+
+    #[test]
+    fn should_refund_to_treasury() {
+        let fixture = TestFixture::with_chainspec(|chainspec| {
+            chainspec.core_config.refund_handling = RefundHandling::Refund {
+                refund_ratio: Ratio::new(1, 1),
+            };
+        });
+        let payer = TestFixture::validator_secret_key(1);
+        let treasury = TestFixture::validator_public_key(2).to_account_hash();
+        let payment_amount = U512::from(2_500_000_000_u64);
+        let deploy = Deploy::new(
+            Timestamp::now(),
+            TimeDiff::from_seconds(100),
+            1,
+            vec![],
+            fixture.chainspec.network_config.name.clone(),
+            ExecutableDeployItem::ModuleBytes {
+                module_bytes: Bytes::new(),
+                args: runtime_args! { "amount" => payment_amount },
+            },
+            ExecutableDeployItem::ModuleBytes {
+                module_bytes: Bytes::from(DO_NOTHING_WASM.to_vec()),
+                args: RuntimeArgs::new(),
+            },
+            &payer,
+            None,
+        );
+        let treasury_balance = |state_root_hash: Digest| {
+            let reader = fixture
+                .engine_state()
+                .get_state()
+                .checkout(state_root_hash)
+                .unwrap()
+                .unwrap();
+            let main_purse = match reader
+                .read(CorrelationId::new(), &Key::Account(treasury))
+                .unwrap()
+            {
+                Some(StoredValue::Account(account)) => account.main_purse(),
+                other => panic!("expected account, got {:?}", other),
+            };
+            match reader
+                .read(CorrelationId::new(), &Key::Balance(main_purse.addr()))
+                .unwrap()
+            {
+                Some(StoredValue::CLValue(cl_value)) => cl_value.into_t::<U512>().unwrap(),
+                other => panic!("expected balance, got {:?}", other),
+            }
+        };
+        let genesis_balance = treasury_balance(fixture.post_genesis_state_root_hash);
+        let execute = |refund_destination: Option<&dyn RefundDestination>| {
+            fixture
+                .execute_block_with_options(
+                    vec![deploy.clone()],
+                    None,
+                    BlockExecutionOptions {
+                        refund_destination,
+                        ..BlockExecutionOptions::default()
+                    },
+                )
+                .unwrap()
+        };
+
+        let refunded_to_payer = execute(None);
+        assert_eq!(
+            treasury_balance(*refunded_to_payer.block.state_root_hash()),
+            genesis_balance
+        );
+
+        // The unspent payment lands in the treasury's main purse once the policy is active.
+        let treasury_refunds = TreasuryRefunds::new(treasury, fixture.chainspec.protocol_version());
+        let refunded_to_treasury = execute(Some(&treasury_refunds));
+        let deploy_execution_info = &refunded_to_treasury.execution_results[0];
+        assert!(matches!(
+            deploy_execution_info.result,
+            ExecutionResult::Success { .. }
+        ));
+        assert_eq!(
+            treasury_balance(*refunded_to_treasury.block.state_root_hash()),
+            genesis_balance + payment_amount - deploy_execution_info.cost
+        );
+
+        // Before its activation, the policy leaves the refund with the payer.
+        let pending_treasury_refunds =
+            TreasuryRefunds::new(treasury, ProtocolVersion::from_parts(9, 0, 0));
+        assert_eq!(
+            execute(Some(&pending_treasury_refunds))
+                .block
+                .state_root_hash(),
+            refunded_to_payer.block.state_root_hash()
+        );
+    }
+
+    #[test]
+    fn should_reject_oversized_execution_results() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploy = fixture.transfer(&mut rng, 1);
+        let deploy_hash = *deploy.hash();
+        let execute = |max_execution_result_bytes: Option<usize>| {
+            fixture.execute_block_with_options(
+                vec![deploy.clone()],
+                None,
+                BlockExecutionOptions {
+                    max_execution_result_bytes,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+        };
+        let size = execute(None).unwrap().execution_results[0]
+            .result
+            .serialized_length();
+
+        // A result of exactly the maximum size is accepted.
+        execute(Some(size)).unwrap();
+        match execute(Some(size - 1)) {
+            Err(BlockExecutionError::ExecutionResultTooLarge {
+                deploy_hash: rejected_deploy_hash,
+                size: rejected_size,
+            }) => {
+                assert_eq!(rejected_deploy_hash, deploy_hash);
+                assert_eq!(rejected_size, size);
+            }
+            other => panic!("expected an oversized execution result, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_report_validator_floor_breaches() {
+        let fixture = TestFixture::new();
+        let breaches = RefCell::new(vec![]);
+        let on_validator_floor_breached = |era_id: EraId, validator_count: usize| {
+            breaches.borrow_mut().push((era_id, validator_count));
+        };
+        let execute = |era_report: Option<EraReport<PublicKey>>, validator_floor: usize| {
+            fixture
+                .execute_block_with_options(
+                    vec![],
+                    era_report,
+                    BlockExecutionOptions {
+                        validator_floor: Some(validator_floor),
+                        on_validator_floor_breached: Some(&on_validator_floor_breached),
+                        ..BlockExecutionOptions::default()
+                    },
+                )
+                .unwrap()
+                .block
+        };
+
+        let switch_block = execute(Some(EraReport::default()), 0);
+        let validator_count = switch_block
+            .header()
+            .next_era_validator_weights()
+            .unwrap()
+            .len();
+        assert!(breaches.borrow().is_empty());
+
+        execute(Some(EraReport::default()), validator_count);
+        assert!(breaches.borrow().is_empty());
+
+        execute(Some(EraReport::default()), validator_count + 1);
+        assert_eq!(
+            breaches.take(),
+            vec![(switch_block.header().era_id().successor(), validator_count)]
+        );
+
+        // Only switch blocks determine the validators of an upcoming era.
+        execute(None, validator_count + 1);
+        assert!(breaches.borrow().is_empty());
+    }
+
+    #[test]
+    fn should_report_phase_of_failed_step() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let step_phase = |error: BlockExecutionError| match error {
+            BlockExecutionError::StepFailed { phase, .. } => phase,
+            other => panic!("expected a failed step, got {:?}", other),
+        };
+
+        // Rewarding a public key which is not a validator fails to distribute the rewards.
+        let mut era_report = era_report_rewarding_validators(&[1_000]);
+        era_report
+            .rewards
+            .insert(PublicKey::random(&mut rng), 1_000);
+        let error = fixture.execute_block(vec![], Some(era_report)).unwrap_err();
+        assert_eq!(step_phase(error), StepPhase::InvalidReward);
+
+        let error = BlockExecutionError::from(StepError::RootNotFound(Digest::hash([1])));
+        assert_eq!(step_phase(error), StepPhase::CommitFailed);
+
+        let error = BlockExecutionError::from(StepError::AuctionError(
+            execution::Error::MissingSystemContractHash(AUCTION.to_string()),
+        ));
+        assert_eq!(step_phase(error), StepPhase::AuctionMissing);
+    }
+
+    #[test]
+    fn should_reject_block_on_pruned_state_root() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = vec![fixture.transfer(&mut rng, 1)];
+        let (execution_pre_state, finalized_block) =
+            fixture.first_block(&deploys, None, PublicKey::System);
+        let pruned_state_root_hash = Digest::hash(b"pruned state root");
+        let pruned_pre_state = ExecutionPreState::new(
+            execution_pre_state.next_block_height,
+            pruned_state_root_hash,
+            execution_pre_state.parent_hash,
+            execution_pre_state.parent_seed,
+        );
+
+        let mut executed_deploys = 0;
+        let error = execute_finalized_block_streaming(
+            fixture.engine_state(),
+            None,
+            fixture.chainspec.protocol_version(),
+            pruned_pre_state,
+            finalized_block,
+            deploys,
+            EraId::new(0),
+            0,
+            0,
+            BlockExecutionOptions::default(),
+            &mut |_| executed_deploys += 1,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            BlockExecutionError::PrunedStateRoot { state_root_hash }
+                if state_root_hash == pruned_state_root_hash
+        ));
+        // The block is rejected before any deploy is executed.
+        assert_eq!(executed_deploys, 0);
+    }
+
+    #[test]
+    fn should_return_gas_price_of_block() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let amount = U512::from(
+            fixture
+                .chainspec
+                .deploy_config
+                .native_transfer_minimum_motes,
+        );
+        let deploys = vec![
+            fixture.transfer_with_gas_price(&mut rng, 1, amount, 2),
+            fixture.transfer_with_gas_price(&mut rng, 2, amount, 3),
+        ];
+        let results = fixture.execute_block(deploys, None).unwrap();
+        assert_eq!(results.gas_price(), Some(3));
+        assert!(results
+            .execution_results
+            .iter()
+            .all(|info| Some(info.header.gas_price()) <= results.gas_price()));
+
+        // No gas is converted at a block without deploys.
+        let results = fixture.execute_block(vec![], None).unwrap();
+        assert_eq!(results.gas_price(), None);
+    }
+
+    #[test]
+    fn should_reject_nondeterministic_execution() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let execute = |deploys: Vec<Deploy>,
+                       options: &dyn Fn() -> BlockExecutionOptions<'static>| {
+            let era_report = era_report_rewarding_validators(&[1_000, 2_000]);
+            let (execution_pre_state, finalized_block) =
+                fixture.first_block(&deploys, Some(era_report), PublicKey::System);
+            execute_finalized_block_checked(
+                fixture.engine_state(),
+                None,
+                fixture.chainspec.protocol_version(),
+                execution_pre_state,
+                finalized_block,
+                deploys,
+                EraId::new(0),
+                0,
+                0,
+                options,
+            )
+        };
+
+        let results = execute(
+            vec![fixture.transfer(&mut rng, 1)],
+            &BlockExecutionOptions::default,
+        )
+        .unwrap();
+        assert_eq!(results.execution_results.len(), 1);
+        assert_eq!(results.execution_results[0].outcome, DeployOutcome::Success);
+        assert_eq!(*results.block.state_root_hash(), results.final_root);
+        // The second run is written to LMDB.
+        assert!(fixture
+            .engine_state()
+            .tracking_copy(results.final_root)
+            .unwrap()
+            .is_some());
+
+        let identity_strategy = || BlockExecutionOptions {
+            reward_strategy: Some(Box::new(IdentityRewardStrategy)),
+            ..BlockExecutionOptions::default()
+        };
+        execute(vec![], &identity_strategy).unwrap();
+
+        // A strategy which distributes less with every run makes the step nondeterministic.
+        let runs = Cell::new(0);
+        let nondeterministic_strategy = || {
+            runs.set(runs.get() + 1);
+            BlockExecutionOptions {
+                reward_strategy: Some(Box::new(CappedRewardStrategy(1_000 / runs.get()))),
+                ..BlockExecutionOptions::default()
+            }
+        };
+        let error = execute(vec![], &nondeterministic_strategy).unwrap_err();
+        assert_eq!(runs.get(), 2);
+        assert!(matches!(
+            error,
+            BlockExecutionError::NondeterministicExecution { first_run, second_run }
+                if first_run != second_run
+        ));
+    }
+
+    #[test]
+    fn should_speculatively_execute_with_given_proposer() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let proposer = TestFixture::validator_public_key(2);
+        let account_key = Key::Account(proposer.to_account_hash());
+        let main_purse = match fixture
+            .engine_state()
+            .get_state()
+            .checkout(fixture.post_genesis_state_root_hash)
+            .unwrap()
+            .unwrap()
+            .read(CorrelationId::new(), &account_key)
+            .unwrap()
+        {
+            Some(StoredValue::Account(account)) => account.main_purse(),
+            other => panic!("expected account, got {:?}", other),
+        };
+        let proposer_balance = Key::Balance(main_purse.addr());
+
+        // The fees of the deploy are paid to the proposer of the execution state.
+        let mut pays_fee_to_proposer = |maybe_proposer: Option<PublicKey>| {
+            let execution_state = SpeculativeExecutionState {
+                state_root_hash: fixture.post_genesis_state_root_hash,
+                block_time: Timestamp::now(),
+                protocol_version: fixture.chainspec.protocol_version(),
+                proposer: maybe_proposer,
+                era_id: Some(EraId::new(0)),
+            };
+            let deploy = DeployItem::from(fixture.transfer(&mut rng, 1));
+            let (execution_result, effects) =
+                execute_only_with_effects(fixture.engine_state(), execution_state, deploy)
+                    .unwrap()
+                    .unwrap();
+            assert!(matches!(execution_result, ExecutionResult::Success { .. }));
+            effects.get(&proposer_balance).is_some()
+        };
+
+        assert!(!pays_fee_to_proposer(None));
+        assert!(pays_fee_to_proposer(Some(proposer)));
+    }
+
+    #[test]
+    fn should_observe_checksum_registry_proof_size() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let results = fixture
+            .execute_block_with_metrics(
+                vec![fixture.transfer(&mut rng, 1)],
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+
+        let proof = checksum_registry_proof(
+            fixture.engine_state(),
+            None,
+            CorrelationId::new(),
+            results.final_root,
+        )
+        .unwrap();
+        let proof_size = &metrics.checksum_registry_proof_size_bytes;
+        assert_eq!(proof_size.get_sample_count(), 1);
+        assert_eq!(
+            proof_size.get_sample_sum() as usize,
+            proof.serialized_length()
+        );
+    }
+
+    #[test]
+    fn should_read_through_cache_without_changing_state_root() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let protocol_version = fixture.chainspec.protocol_version();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let cache = ReadThroughCache::new(NonZeroUsize::new(1_000).unwrap());
+        let parent = fixture
+            .execute_block_with_options(
+                vec![fixture.transfer(&mut rng, 1)],
+                None,
+                BlockExecutionOptions {
+                    read_through_cache: Some(&cache),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap()
+            .block;
+        // The values read by the parent which it didn't write are still cached for its child.
+        assert_eq!(cache.state_root_hash(), Some(*parent.state_root_hash()));
+        assert!(!cache.is_empty());
+        assert_eq!(cache.hits(), 0);
+
+        let deploys = vec![fixture.transfer(&mut rng, 2)];
+        let uncached = fixture
+            .execute_child_block(&parent, deploys.clone(), protocol_version)
+            .unwrap();
+        let cached = fixture
+            .execute_child_block_with_flushes(
+                &parent,
+                deploys,
+                protocol_version,
+                Some(Arc::clone(&metrics)),
+                None,
+                Some(&cache),
+            )
+            .unwrap();
+        assert_eq!(cached.final_root, uncached.final_root);
+        assert!(cache.hits() > 0);
+        assert_eq!(metrics.read_through_cache_hits.get(), cache.hits());
+        assert_eq!(cache.state_root_hash(), Some(cached.final_root));
+    }
+
+    #[test]
+    fn should_not_write_to_lmdb_when_commit_forbidden() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let deploys = vec![fixture.transfer(&mut rng, 1)];
+        let forbidden = fixture
+            .execute_block_with_metrics(
+                deploys.clone(),
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    commit_mode: CommitMode::Forbidden,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_ne!(forbidden.final_root, fixture.post_genesis_state_root_hash);
+        assert!(fixture
+            .engine_state()
+            .tracking_copy(forbidden.final_root)
+            .unwrap()
+            .is_none());
+        assert_eq!(metrics.scratch_write_to_db.get_sample_count(), 0);
+        assert_eq!(metrics.flush_environment.get_sample_count(), 0);
+        forbidden
+            .approvals_hashes
+            .validate(&forbidden.block)
+            .unwrap();
+
+        let committed = fixture.execute_block(deploys, None).unwrap();
+        assert_eq!(committed.final_root, forbidden.final_root);
+        assert!(fixture
+            .engine_state()
+            .tracking_copy(committed.final_root)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn should_veto_deploys_rejected_by_deploy_filter() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let vetoed = fixture.transfer(&mut rng, 1);
+        let vetoed_hash = *vetoed.hash();
+        let deploys = vec![vetoed, fixture.transfer(&mut rng, 2)];
+        let deploy_filter = |deploy: &Deploy| *deploy.hash() != vetoed_hash;
+        let results = fixture
+            .execute_block_with_options(
+                deploys.clone(),
+                None,
+                BlockExecutionOptions {
+                    capture_journals: true,
+                    deploy_filter: Some(&deploy_filter),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+
+        let execution_results = &results.execution_results;
+        assert_eq!(execution_results.len(), 2);
+        assert_eq!(execution_results[0].deploy_hash, vetoed_hash);
+        assert_eq!(execution_results[0].outcome, DeployOutcome::Vetoed);
+        assert_eq!(execution_results[0].cost, U512::zero());
+        assert_eq!(execution_results[0].effect_count(), 0);
+        assert_eq!(execution_results[1].outcome, DeployOutcome::Success);
+        let captured_journals = results.captured_journals().unwrap();
+        assert!(captured_journals[0].effects().is_empty());
+        assert!(!captured_journals[1].effects().is_empty());
+
+        // Vetoing a deploy changes the resulting state root hash.
+        let unfiltered = fixture.execute_block(deploys, None).unwrap();
+        assert_ne!(unfiltered.final_root, results.final_root);
+    }
+
+    #[test]
+    fn should_observe_block_assembly() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let metrics = Arc::new(Metrics::new(&Registry::new()).unwrap());
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        fixture
+            .execute_block_with_metrics(
+                deploys,
+                None,
+                &metrics,
+                BlockExecutionOptions {
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(metrics.block_assembly.get_sample_count(), 1);
+        assert!(metrics.block_assembly.get_sample_sum() > 0.0);
+    }
+
+    #[test]
+    fn should_call_yield_hook_between_deploys() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let deploy_count = deploys.len();
+        let yields = Cell::new(0);
+        let yield_hook = || yields.set(yields.get() + 1);
+        fixture
+            .execute_block_with_options(
+                deploys,
+                None,
+                BlockExecutionOptions {
+                    yield_hook: Some(&yield_hook),
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(yields.get(), deploy_count - 1);
+    }
+
+    #[test]
+    fn should_reproduce_block_state_root_from_recorded_journals() {
+        let mut rng = crate::new_rng();
+        let fixture = TestFixture::new();
+        let deploys = (1..=3)
+            .map(|node_index| fixture.transfer(&mut rng, node_index))
+            .collect_vec();
+        let results = fixture
+            .execute_block_with_options(
+                deploys,
+                None,
+                BlockExecutionOptions {
+                    capture_journals: true,
+                    ..BlockExecutionOptions::default()
+                },
+            )
+            .unwrap();
+
+        let mut journals = results
+            .captured_journals()
+            .unwrap()
+            .iter()
+            .map(|captured_journal| captured_journal.effects().clone())
+            .collect_vec();
+        // The captured journals don't cover the checksum registry written by the block.
+        let checksum_registry = fixture
+            .engine_state()
+            .get_checksum_registry(CorrelationId::new(), results.final_root)
+            .unwrap()
+            .unwrap();
+        let mut checksum_registry_effects = AdditiveMap::new();
+        let _ = checksum_registry_effects.insert(
+            Key::ChecksumRegistry,
+            Transform::Write(CLValue::from_t(checksum_registry).unwrap().into()),
+        );
+        journals.push(checksum_registry_effects);
+
+        let state_root_hash = apply_recorded_journals(
+            fixture.engine_state(),
+            fixture.post_genesis_state_root_hash,
+            journals,
+        )
+        .unwrap();
+        assert_eq!(state_root_hash, results.final_root);
+    }
 }
@@ -1,14 +1,47 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use datasize::DataSize;
+use linked_hash_map::LinkedHashMap;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
 
 use casper_execution_engine::{
-    core::engine_state::GetEraValidatorsRequest, shared::execution_journal::ExecutionJournal,
+    core::engine_state::{
+        DeployItem, EngineState, ExecuteRequest, ExecutionResult as EngineExecutionResult,
+        GetEraValidatorsRequest,
+    },
+    shared::{
+        additive_map::AdditiveMap, execution_journal::ExecutionJournal, transform::Transform,
+    },
+    storage::global_state::{
+        lmdb::LmdbGlobalState,
+        scratch::{ReadCache, ScratchGlobalState},
+    },
+};
+use casper_hashing::{ChunkWithProof, Digest};
+use casper_types::{
+    account::AccountHash,
+    bytesrepr::{self, FromBytes, ToBytes, U32_SERIALIZED_LENGTH, U8_SERIALIZED_LENGTH},
+    system::auction::EraValidators,
+    EraId, ExecutionResult, Gas, Key, ProtocolVersion, PublicKey, StoredValue, Timestamp,
+    Transform as ExecutionTransform, U512,
 };
-use casper_hashing::Digest;
-use casper_types::{EraId, ExecutionResult, ProtocolVersion, PublicKey, U512};
 
-use crate::types::{ApprovalsHashes, Block, DeployHash, DeployHeader};
+use crate::{
+    components::{
+        consensus::EraReport,
+        contract_runtime::{error::VersionedBytesError, operations::flush_environment, Metrics},
+    },
+    types::{ApprovalsHashes, Block, BlockHash, Deploy, DeployHash, DeployHeader},
+    utils::ds,
+};
 
 /// Request for validator weights for a specific era.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -83,6 +116,68 @@ impl From<EraValidatorsRequest> for GetEraValidatorsRequest {
     }
 }
 
+/// An [`ExecuteRequest`] along with the hashes of the deploys it carries, in execution order.
+///
+/// The execution results returned by the EE carry no reference to the deploys which produced them,
+/// but they are returned in the same order as the deploys in the request.  Keeping the hashes
+/// alongside the request allows each result to be paired with its originating deploy.
+#[derive(Debug)]
+pub(crate) struct BatchExecuteRequest {
+    execute_request: ExecuteRequest,
+    deploy_hashes: Vec<DeployHash>,
+}
+
+impl BatchExecuteRequest {
+    /// Constructs a new `BatchExecuteRequest`.
+    pub(crate) fn new(
+        parent_state_hash: Digest,
+        block_time: u64,
+        deploy_items: Vec<DeployItem>,
+        protocol_version: ProtocolVersion,
+        proposer: PublicKey,
+    ) -> Self {
+        let deploy_hashes = deploy_items
+            .iter()
+            .map(|deploy_item| DeployHash::from(deploy_item.deploy_hash))
+            .collect();
+        let execute_request = ExecuteRequest::new(
+            parent_state_hash,
+            block_time,
+            deploy_items,
+            protocol_version,
+            proposer,
+        );
+        BatchExecuteRequest {
+            execute_request,
+            deploy_hashes,
+        }
+    }
+
+    /// Sets the seed mixed into the addresses generated by the deploys, see
+    /// [`ExecuteRequest::address_seed`].
+    pub(crate) fn with_address_seed(mut self, address_seed: Option<[u8; 32]>) -> Self {
+        self.execute_request.address_seed = address_seed;
+        self
+    }
+
+    /// Sets the account unspent payment of the deploys is refunded to, see
+    /// [`ExecuteRequest::refund_account`].
+    pub(crate) fn with_refund_account(mut self, refund_account: Option<AccountHash>) -> Self {
+        self.execute_request.refund_account = refund_account;
+        self
+    }
+
+    /// Returns the hashes of the deploys in the request.
+    pub(crate) fn deploy_hashes(&self) -> &[DeployHash] {
+        &self.deploy_hashes
+    }
+
+    /// Consumes `self`, returning the inner `ExecuteRequest` and the hashes of its deploys.
+    pub(crate) fn destructure(self) -> (ExecuteRequest, Vec<DeployHash>) {
+        (self.execute_request, self.deploy_hashes)
+    }
+}
+
 /// Effects from running step and the next era validators that are gathered when an era ends.
 #[derive(Clone, Debug, DataSize)]
 pub(crate) struct StepEffectAndUpcomingEraValidators {
@@ -90,6 +185,1236 @@ pub(crate) struct StepEffectAndUpcomingEraValidators {
     pub(crate) upcoming_era_validators: BTreeMap<EraId, BTreeMap<PublicKey, U512>>,
     /// An [`ExecutionJournal`] created by an era ending.
     pub(crate) step_execution_journal: ExecutionJournal,
+    /// Which validators the step evicted, slashed and rewarded.
+    pub(crate) step_outcome_summary: StepOutcomeSummary,
+    /// The summary of `step_execution_journal`.
+    ///
+    /// Derived from the journal, so it is not serialized but recomputed when deserializing.
+    pub(crate) step_journal_summary: StepJournalSummary,
+}
+
+impl ToBytes for StepEffectAndUpcomingEraValidators {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut buffer = bytesrepr::allocate_buffer(self)?;
+        self.write_bytes(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.upcoming_era_validators.serialized_length()
+            + self.step_execution_journal.serialized_length()
+            + self.step_outcome_summary.serialized_length()
+    }
+
+    fn write_bytes(&self, writer: &mut Vec<u8>) -> Result<(), bytesrepr::Error> {
+        self.upcoming_era_validators.write_bytes(writer)?;
+        self.step_execution_journal.write_bytes(writer)?;
+        self.step_outcome_summary.write_bytes(writer)
+    }
+}
+
+impl FromBytes for StepEffectAndUpcomingEraValidators {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (upcoming_era_validators, remainder) = FromBytes::from_bytes(bytes)?;
+        let (step_execution_journal, remainder) = ExecutionJournal::from_bytes(remainder)?;
+        let (step_outcome_summary, remainder) = StepOutcomeSummary::from_bytes(remainder)?;
+        let step_journal_summary = summarize_step_journal(&step_execution_journal);
+        let step_effect_and_upcoming_era_validators = StepEffectAndUpcomingEraValidators {
+            upcoming_era_validators,
+            step_execution_journal,
+            step_outcome_summary,
+            step_journal_summary,
+        };
+        Ok((step_effect_and_upcoming_era_validators, remainder))
+    }
+}
+
+/// A summary of the validators affected by the step run at the end of an era, available without
+/// inspecting the step's execution journal.
+#[derive(Clone, Debug, Default, DataSize, PartialEq, Eq)]
+pub struct StepOutcomeSummary {
+    /// The validators evicted from the auction, in the order of their public keys.
+    pub(crate) evicted: Vec<PublicKey>,
+    /// The validators slashed, in the order they were reported as equivocators.
+    pub(crate) slashed: Vec<PublicKey>,
+    /// The reward distributed to each validator.
+    pub(crate) rewarded: BTreeMap<PublicKey, U512>,
+    /// The rewards withheld for falling below the [`MinimumReward`], to be carried over to the
+    /// next era.
+    pub(crate) withheld: BTreeMap<PublicKey, U512>,
+}
+
+impl StepOutcomeSummary {
+    /// Returns the validators evicted by the step.
+    pub fn evicted(&self) -> &[PublicKey] {
+        &self.evicted
+    }
+
+    /// Returns the validators slashed by the step.
+    pub fn slashed(&self) -> &[PublicKey] {
+        &self.slashed
+    }
+
+    /// Returns the reward distributed to each validator by the step.
+    pub fn rewarded(&self) -> &BTreeMap<PublicKey, U512> {
+        &self.rewarded
+    }
+
+    /// Returns the rewards withheld by the step, to be carried over to the next era.
+    pub fn withheld(&self) -> &BTreeMap<PublicKey, U512> {
+        &self.withheld
+    }
+
+    /// Adds the outcome of a later step committed by the same block, ending the following era.
+    ///
+    /// The rewards withheld by this step are carried over to the later one, so only the rewards
+    /// withheld by the later step are kept.
+    pub(super) fn merge(&mut self, later: StepOutcomeSummary) {
+        self.evicted.extend(later.evicted);
+        self.evicted.sort();
+        self.evicted.dedup();
+        self.slashed.extend(later.slashed);
+        for (validator_id, reward) in later.rewarded {
+            *self.rewarded.entry(validator_id).or_default() += reward;
+        }
+        self.withheld = later.withheld;
+    }
+}
+
+impl ToBytes for StepOutcomeSummary {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut buffer = bytesrepr::allocate_buffer(self)?;
+        self.write_bytes(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.evicted.serialized_length()
+            + self.slashed.serialized_length()
+            + self.rewarded.serialized_length()
+            + self.withheld.serialized_length()
+    }
+
+    fn write_bytes(&self, writer: &mut Vec<u8>) -> Result<(), bytesrepr::Error> {
+        self.evicted.write_bytes(writer)?;
+        self.slashed.write_bytes(writer)?;
+        self.rewarded.write_bytes(writer)?;
+        self.withheld.write_bytes(writer)
+    }
+}
+
+impl FromBytes for StepOutcomeSummary {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (evicted, remainder) = Vec::<PublicKey>::from_bytes(bytes)?;
+        let (slashed, remainder) = Vec::<PublicKey>::from_bytes(remainder)?;
+        let (rewarded, remainder) = BTreeMap::<PublicKey, U512>::from_bytes(remainder)?;
+        let (withheld, remainder) = BTreeMap::<PublicKey, U512>::from_bytes(remainder)?;
+        let step_outcome_summary = StepOutcomeSummary {
+            evicted,
+            slashed,
+            rewarded,
+            withheld,
+        };
+        Ok((step_outcome_summary, remainder))
+    }
+}
+
+/// A summary of the effects of the step run at the end of an era, parsed from its execution
+/// journal.
+#[derive(Clone, Debug, Default, DataSize, PartialEq, Eq)]
+pub struct StepJournalSummary {
+    /// The rewards minted by the step, i.e. the sum of the additions to purse balances.
+    pub(crate) minted_rewards: U512,
+    /// The number of bids written as inactive, i.e. the evictions applied by the step.
+    pub(crate) evictions: usize,
+    /// The balances of the bonding purses of the bids written by the step, which the step
+    /// transformed.
+    pub(crate) bid_purses_touched: BTreeSet<Key>,
+}
+
+impl StepJournalSummary {
+    /// Returns the rewards minted by the step.
+    pub fn minted_rewards(&self) -> U512 {
+        self.minted_rewards
+    }
+
+    /// Returns the number of evictions applied by the step.
+    pub fn evictions(&self) -> usize {
+        self.evictions
+    }
+
+    /// Returns the balance keys of the bonding purses touched by the step.
+    pub fn bid_purses_touched(&self) -> &BTreeSet<Key> {
+        &self.bid_purses_touched
+    }
+}
+
+/// Summarizes the execution journal of a step.
+///
+/// The bonding purses of validators and delegators are only known from the bids written by the
+/// step, so a purse whose bid the step left unchanged is not counted as touched.
+pub fn summarize_step_journal(execution_journal: &ExecutionJournal) -> StepJournalSummary {
+    let mut summary = StepJournalSummary::default();
+    let mut balances_touched = BTreeSet::new();
+    let mut bonding_purses = BTreeSet::new();
+    for (key, transform) in execution_journal.iter() {
+        match (key, transform) {
+            (Key::Balance(_), transform) => {
+                if let Transform::AddUInt512(amount) = transform {
+                    summary.minted_rewards += *amount;
+                }
+                balances_touched.insert(*key);
+            }
+            (Key::Bid(_), Transform::Write(StoredValue::Bid(bid))) => {
+                if bid.inactive() {
+                    summary.evictions += 1;
+                }
+                bonding_purses.insert(Key::Balance(bid.bonding_purse().addr()));
+                bonding_purses.extend(
+                    bid.delegators()
+                        .values()
+                        .map(|delegator| Key::Balance(delegator.bonding_purse().addr())),
+                );
+            }
+            _ => {}
+        }
+    }
+    summary.bid_purses_touched = balances_touched
+        .intersection(&bonding_purses)
+        .copied()
+        .collect();
+    summary
+}
+
+/// The outcome of executing a single deploy of a block.
+#[derive(Clone, Debug, DataSize, Serialize, Deserialize)]
+pub struct DeployExecutionInfo {
+    /// The hash of the executed deploy.
+    pub(crate) deploy_hash: DeployHash,
+    /// The header of the executed deploy.
+    pub(crate) header: DeployHeader,
+    /// The result of executing the deploy.
+    pub(crate) result: ExecutionResult,
+    /// Whether the deploy executed successfully.
+    pub(crate) outcome: DeployOutcome,
+    /// The gas cost of executing the deploy.
+    pub(crate) cost: U512,
+    /// The number of keys whose value the deploy changes, see
+    /// [`DeployExecutionInfo::effect_count`].
+    pub(crate) effect_count: usize,
+}
+
+impl DeployExecutionInfo {
+    /// Returns the number of keys in global state whose value the deploy changes, i.e. those with
+    /// a transform in its execution journal other than the identity transforms recording reads.
+    ///
+    /// The effects of the deploy's payment are included, so this is only zero for a deploy which
+    /// was not even charged, e.g. as its account does not exist.
+    pub fn effect_count(&self) -> usize {
+        self.effect_count
+    }
+}
+
+/// Returns the number of keys whose value is changed by the effect of `execution_result`.
+pub(super) fn count_effects(execution_result: &ExecutionResult) -> usize {
+    let effect = match execution_result {
+        ExecutionResult::Success { effect, .. } | ExecutionResult::Failure { effect, .. } => effect,
+    };
+    effect
+        .transforms
+        .iter()
+        .filter(|transform_entry| transform_entry.transform != ExecutionTransform::Identity)
+        .map(|transform_entry| &transform_entry.key)
+        .collect::<BTreeSet<_>>()
+        .len()
+}
+
+impl ToBytes for DeployExecutionInfo {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut buffer = bytesrepr::allocate_buffer(self)?;
+        self.write_bytes(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.deploy_hash.serialized_length()
+            + self.header.serialized_length()
+            + self.result.serialized_length()
+            + self.outcome.serialized_length()
+            + self.cost.serialized_length()
+    }
+
+    fn write_bytes(&self, writer: &mut Vec<u8>) -> Result<(), bytesrepr::Error> {
+        self.deploy_hash.write_bytes(writer)?;
+        self.header.write_bytes(writer)?;
+        self.result.write_bytes(writer)?;
+        self.outcome.write_bytes(writer)?;
+        self.cost.write_bytes(writer)
+    }
+}
+
+impl FromBytes for DeployExecutionInfo {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (deploy_hash, remainder) = DeployHash::from_bytes(bytes)?;
+        let (header, remainder) = DeployHeader::from_bytes(remainder)?;
+        let (result, remainder) = ExecutionResult::from_bytes(remainder)?;
+        let (outcome, remainder) = DeployOutcome::from_bytes(remainder)?;
+        let (cost, remainder) = U512::from_bytes(remainder)?;
+        // The effect count is determined by the execution result, so it is not encoded.
+        let effect_count = count_effects(&result);
+        let deploy_execution_info = DeployExecutionInfo {
+            deploy_hash,
+            header,
+            result,
+            outcome,
+            cost,
+            effect_count,
+        };
+        Ok((deploy_execution_info, remainder))
+    }
+}
+
+/// The effects of a deploy as committed while executing a block, captured for debugging.
+#[derive(Clone, Debug, DataSize)]
+pub struct CapturedJournal {
+    /// The hash of the executed deploy.
+    pub(crate) deploy_hash: DeployHash,
+    /// The effects committed for the deploy.
+    #[data_size(skip)]
+    pub(crate) effects: AdditiveMap<Key, Transform>,
+    /// Whether the deploy executed successfully.
+    pub(crate) outcome: DeployOutcome,
+}
+
+impl CapturedJournal {
+    /// Returns the hash of the executed deploy.
+    pub fn deploy_hash(&self) -> &DeployHash {
+        &self.deploy_hash
+    }
+
+    /// Returns the effects committed for the deploy.
+    pub fn effects(&self) -> &AdditiveMap<Key, Transform> {
+        &self.effects
+    }
+
+    /// Returns whether the deploy executed successfully.
+    pub fn outcome(&self) -> &DeployOutcome {
+        &self.outcome
+    }
+}
+
+/// Whether a deploy executed successfully, available without inspecting its execution result.
+#[derive(Clone, Debug, DataSize, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeployOutcome {
+    /// The deploy executed successfully.
+    Success,
+    /// The deploy failed to execute. Its effects are reverted, apart from those of its payment.
+    Failure {
+        /// The error which caused the deploy to fail.
+        error_message: String,
+    },
+    /// The deploy failed to execute as its account could not pay for it, or could not afford the
+    /// amount it transfers. Its effects are reverted, apart from those of its payment.
+    InsufficientBalance {
+        /// The error which caused the deploy to fail.
+        error_message: String,
+    },
+    /// The deploy was vetoed by the deploy filter, and not executed. It has no effects and costs
+    /// nothing.
+    Vetoed,
+}
+
+const DEPLOY_OUTCOME_SUCCESS_TAG: u8 = 0;
+const DEPLOY_OUTCOME_FAILURE_TAG: u8 = 1;
+const DEPLOY_OUTCOME_INSUFFICIENT_BALANCE_TAG: u8 = 2;
+const DEPLOY_OUTCOME_VETOED_TAG: u8 = 3;
+
+impl ToBytes for DeployOutcome {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut buffer = bytesrepr::allocate_buffer(self)?;
+        self.write_bytes(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn serialized_length(&self) -> usize {
+        U8_SERIALIZED_LENGTH
+            + match self {
+                DeployOutcome::Success | DeployOutcome::Vetoed => 0,
+                DeployOutcome::Failure { error_message }
+                | DeployOutcome::InsufficientBalance { error_message } => {
+                    error_message.serialized_length()
+                }
+            }
+    }
+
+    fn write_bytes(&self, writer: &mut Vec<u8>) -> Result<(), bytesrepr::Error> {
+        match self {
+            DeployOutcome::Success => writer.push(DEPLOY_OUTCOME_SUCCESS_TAG),
+            DeployOutcome::Failure { error_message } => {
+                writer.push(DEPLOY_OUTCOME_FAILURE_TAG);
+                error_message.write_bytes(writer)?;
+            }
+            DeployOutcome::InsufficientBalance { error_message } => {
+                writer.push(DEPLOY_OUTCOME_INSUFFICIENT_BALANCE_TAG);
+                error_message.write_bytes(writer)?;
+            }
+            DeployOutcome::Vetoed => writer.push(DEPLOY_OUTCOME_VETOED_TAG),
+        }
+        Ok(())
+    }
+}
+
+impl FromBytes for DeployOutcome {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (tag, remainder) = u8::from_bytes(bytes)?;
+        match tag {
+            DEPLOY_OUTCOME_SUCCESS_TAG => Ok((DeployOutcome::Success, remainder)),
+            DEPLOY_OUTCOME_FAILURE_TAG => {
+                let (error_message, remainder) = String::from_bytes(remainder)?;
+                Ok((DeployOutcome::Failure { error_message }, remainder))
+            }
+            DEPLOY_OUTCOME_INSUFFICIENT_BALANCE_TAG => {
+                let (error_message, remainder) = String::from_bytes(remainder)?;
+                Ok((
+                    DeployOutcome::InsufficientBalance { error_message },
+                    remainder,
+                ))
+            }
+            DEPLOY_OUTCOME_VETOED_TAG => Ok((DeployOutcome::Vetoed, remainder)),
+            _ => Err(bytesrepr::Error::Formatting),
+        }
+    }
+}
+
+/// A point during the execution of a block at which the state root hash is recorded when tracing
+/// state roots.
+#[derive(Clone, Copy, Debug, DataSize, PartialEq, Eq)]
+pub enum StateRootCheckpoint {
+    /// After committing the effects of the given deploy.
+    Deploy(DeployHash),
+    /// After writing the checksum registry.
+    ChecksumRegistry,
+    /// After committing the step at the end of an era.
+    Step,
+    /// After pruning global state.
+    Prune,
+}
+
+const STATE_ROOT_CHECKPOINT_DEPLOY_TAG: u8 = 0;
+const STATE_ROOT_CHECKPOINT_CHECKSUM_REGISTRY_TAG: u8 = 1;
+const STATE_ROOT_CHECKPOINT_STEP_TAG: u8 = 2;
+const STATE_ROOT_CHECKPOINT_PRUNE_TAG: u8 = 3;
+
+impl ToBytes for StateRootCheckpoint {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut buffer = bytesrepr::allocate_buffer(self)?;
+        self.write_bytes(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn serialized_length(&self) -> usize {
+        U8_SERIALIZED_LENGTH
+            + match self {
+                StateRootCheckpoint::Deploy(deploy_hash) => deploy_hash.serialized_length(),
+                StateRootCheckpoint::ChecksumRegistry
+                | StateRootCheckpoint::Step
+                | StateRootCheckpoint::Prune => 0,
+            }
+    }
+
+    fn write_bytes(&self, writer: &mut Vec<u8>) -> Result<(), bytesrepr::Error> {
+        match self {
+            StateRootCheckpoint::Deploy(deploy_hash) => {
+                writer.push(STATE_ROOT_CHECKPOINT_DEPLOY_TAG);
+                deploy_hash.write_bytes(writer)?;
+            }
+            StateRootCheckpoint::ChecksumRegistry => {
+                writer.push(STATE_ROOT_CHECKPOINT_CHECKSUM_REGISTRY_TAG)
+            }
+            StateRootCheckpoint::Step => writer.push(STATE_ROOT_CHECKPOINT_STEP_TAG),
+            StateRootCheckpoint::Prune => writer.push(STATE_ROOT_CHECKPOINT_PRUNE_TAG),
+        }
+        Ok(())
+    }
+}
+
+impl FromBytes for StateRootCheckpoint {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (tag, remainder) = u8::from_bytes(bytes)?;
+        match tag {
+            STATE_ROOT_CHECKPOINT_DEPLOY_TAG => {
+                let (deploy_hash, remainder) = DeployHash::from_bytes(remainder)?;
+                Ok((StateRootCheckpoint::Deploy(deploy_hash), remainder))
+            }
+            STATE_ROOT_CHECKPOINT_CHECKSUM_REGISTRY_TAG => {
+                Ok((StateRootCheckpoint::ChecksumRegistry, remainder))
+            }
+            STATE_ROOT_CHECKPOINT_STEP_TAG => Ok((StateRootCheckpoint::Step, remainder)),
+            STATE_ROOT_CHECKPOINT_PRUNE_TAG => Ok((StateRootCheckpoint::Prune, remainder)),
+            _ => Err(bytesrepr::Error::Formatting),
+        }
+    }
+}
+
+/// The progress of a partially executed block, from which its execution can be resumed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecutionCheckpoint {
+    /// The height of the block being executed.
+    pub(crate) block_height: u64,
+    /// The state root hash the block is executed on top of.
+    pub(crate) pre_state_root_hash: Digest,
+    /// The state root hash after committing the deploys executed so far, which has been written
+    /// to LMDB.
+    pub(crate) state_root_hash: Digest,
+    /// The outcomes of the deploys executed so far, in the order of the block.
+    pub(crate) execution_results: Vec<DeployExecutionInfo>,
+}
+
+impl ExecutionCheckpoint {
+    /// Returns the index of the first deploy not executed yet.
+    pub fn deploy_index(&self) -> usize {
+        self.execution_results.len()
+    }
+
+    /// Returns the state root hash after committing the deploys executed so far.
+    pub fn state_root_hash(&self) -> Digest {
+        self.state_root_hash
+    }
+}
+
+/// Whether executing a block may write to LMDB.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CommitMode {
+    /// The resulting global state is written to LMDB and flushed.
+    #[default]
+    Commit,
+    /// The block is executed entirely in the scratch state, and nothing is ever written to LMDB, as
+    /// on a read replica.  The resulting state root hash is computed in memory.
+    Forbidden,
+}
+
+/// Periodic checkpointing of the execution of a block, allowing it to be resumed after a crash.
+///
+/// As each checkpoint writes the global state to LMDB, checkpointing changes the flushing cadence
+/// of block execution, though not the resulting state root hash.
+pub struct BlockCheckpointing<'a> {
+    /// The number of deploys to commit between consecutive checkpoints.
+    pub(crate) interval: NonZeroUsize,
+    /// A checkpoint recorded by an interrupted execution of the same block to resume from.
+    pub(crate) resume_from: Option<ExecutionCheckpoint>,
+    /// Called with each checkpoint once its global state has been flushed to LMDB.
+    pub(crate) on_checkpoint: &'a mut dyn FnMut(&ExecutionCheckpoint),
+}
+
+/// The options of executing a finalized block with [`execute_finalized_block`] or any of its
+/// variants, all of which are disabled by default.
+///
+/// [`execute_finalized_block`]: super::execute_finalized_block
+#[derive(Default)]
+pub struct BlockExecutionOptions<'a> {
+    /// Whether the equivocators reported by a switch block are slashed by the step rather than
+    /// evicted.
+    pub slash_equivocators: bool,
+    /// The limit on the gas the deploys of the block may consume together.
+    pub block_gas_limit: Option<Gas>,
+    /// Whether the state root hash after each deploy and the step is recorded.
+    pub trace_state_roots: bool,
+    /// Whether deploys which do not conflict with each other are executed concurrently.
+    pub parallel_deploy_execution: bool,
+    /// Whether the block is rejected unless its proposer is the system or a validator of its era.
+    pub validate_proposer: bool,
+    /// The strategy transforming the rewards of the era report of a switch block.
+    pub reward_strategy: Option<Box<dyn RewardStrategy>>,
+    /// Called at switch blocks with the successor era and the validator weights which become the
+    /// block's `next_era_validator_weights`.
+    pub on_era_transition: Option<&'a dyn Fn(EraId, &BTreeMap<PublicKey, U512>)>,
+    /// If given, the global state is written to LMDB after every `interval` committed deploys and a
+    /// checkpoint is recorded, from which an interrupted execution of the block can be resumed.
+    /// When resuming, the state root trace only covers the deploys executed after the checkpoint.
+    pub checkpointing: Option<BlockCheckpointing<'a>>,
+    /// From protocol version 2.0.0, overrides the timestamp of a switch block as the end of its era
+    /// when running the step. It is ignored under earlier protocol versions.
+    pub era_end_timestamp: Option<Timestamp>,
+    /// If given, the LMDB environment is only flushed once per batch of blocks rather than after
+    /// each block.
+    pub batched_flushes: Option<&'a BatchedFlushes>,
+    /// If given, the block is rejected with a
+    /// [`WrongParentHash`](super::BlockExecutionError::WrongParentHash) error before executing any
+    /// deploy unless it matches the parent hash of the execution pre-state.
+    pub expected_parent_hash: Option<BlockHash>,
+    /// If given, called with every transform in the execution journal of each deploy, in journal
+    /// order, before the deploy's effects are committed.
+    pub effect_observer: Option<&'a dyn Fn(&DeployHash, &Key, &Transform)>,
+    /// If given, the block is executed against it rather than a fresh scratch state, e.g. to seed
+    /// specific global state contents in tests. It must have been derived from the engine state on
+    /// top of the pre-state root hash, as its cached changes are written to LMDB along with those
+    /// of the block.
+    pub scratch_state: Option<EngineState<ScratchGlobalState>>,
+    /// If set, the checksum registry is read back at the state root hash written to LMDB, and a
+    /// [`RootNotReadable`](super::BlockExecutionError::RootNotReadable) error is returned if that
+    /// fails.
+    pub verify_roundtrip: bool,
+    /// From protocol version 2.0.0, rewards below the minimum are withheld by the step and carried
+    /// over to the next era, as described for [`MinimumReward`].
+    pub minimum_reward: Option<&'a MinimumReward>,
+    /// If given, deploys executed sequentially take their execution results from it where the same
+    /// deploy was already executed against the same global state, and the results of the others are
+    /// added to it.
+    pub execution_result_cache: Option<&'a ExecutionResultCache>,
+    /// If given, deploys are executed as if proposed by it rather than by the block's proposer. As
+    /// the proposer receives the fees of the deploys, this changes the resulting state root hash,
+    /// so it must only be used by tools which do not take part in consensus, e.g. to attribute the
+    /// fees of blocks proposed by the system to an actual account.
+    pub proposer_override: Option<PublicKey>,
+    /// If set, execution of the block is aborted with a
+    /// [`DeployFailedInStrictMode`](super::BlockExecutionError::DeployFailedInStrictMode) error as
+    /// soon as a deploy fails, before its effects are committed. Unless checkpoints were written,
+    /// nothing is written to LMDB then.
+    pub strict: bool,
+    /// From protocol version 2.0.0, rewrites the era report of a switch block before the step is
+    /// created from it, e.g. to distribute the reward of a validator among its delegators. It is
+    /// ignored under earlier protocol versions.
+    pub era_report_transform: Option<&'a dyn Fn(&EraReport<PublicKey>) -> EraReport<PublicKey>>,
+    /// If set, a block with deploys which leaves the state root hash unchanged is rejected with a
+    /// [`NoStateProgress`](super::BlockExecutionError::NoStateProgress) error after being written
+    /// to LMDB, as global state is expected to change at least by the checksum registry of the
+    /// block.
+    pub require_state_progress: bool,
+    /// If given, a block with more deploys is rejected with a
+    /// [`TooManyDeploys`](super::BlockExecutionError::TooManyDeploys) error before any of them is
+    /// executed. The node passes the limit of the chainspec, so that all nodes agree on which
+    /// blocks are rejected.
+    pub max_deploys_per_block: Option<usize>,
+    /// If set, the effects committed for each deploy are returned along with its outcome, see
+    /// [`BlockAndExecutionResults::captured_journals`]. As these can be large, it should only be
+    /// set for debugging. When resuming from a checkpoint, only the effects of the deploys executed
+    /// after it are captured.
+    pub capture_journals: bool,
+    /// If given, the deploys are executed in the order it sorts them into rather than in the order
+    /// of the block. The execution results are then in execution order, while the approvals hashes
+    /// stay in the order of the block. As the order changes the resulting state root hash, all
+    /// nodes must apply the same ordering.
+    pub deploy_ordering: Option<&'a dyn DeployOrdering>,
+    /// If given, a deterministic sample of the keys written by the block is looked up in the trie
+    /// its effects would produce before they are written to LMDB, and the block is rejected with a
+    /// [`TrieDepthExceeded`](super::BlockExecutionError::TrieDepthExceeded) error if any of them
+    /// lies deeper than the limit. No checkpoints are recorded, as they would be written first.
+    pub max_trie_depth: Option<usize>,
+    /// If given, the upcoming era validators of a switch block are taken from it where they were
+    /// already read at the resulting state root hash, and added to it otherwise.
+    pub era_validators_cache: Option<&'a EraValidatorsCache>,
+    /// If given, it is mixed into the addresses generated by the deploys, so that test chains can
+    /// reproduce an execution exactly. As this changes the resulting state root hash, it is
+    /// rejected with a
+    /// [`SeedOverrideNotAllowed`](super::BlockExecutionError::SeedOverrideNotAllowed) error unless
+    /// the node is built with the `testing` feature.
+    pub seed_override: Option<[u8; 32]>,
+    /// The number of times a failed flush of the LMDB environment is retried, waiting twice as long
+    /// before each retry as before the previous one, so that a transient I/O error does not lose
+    /// the execution of the block. Once the retries are exhausted, a
+    /// [`FlushFailed`](super::BlockExecutionError::FlushFailed) error is returned.
+    pub flush_retries: u32,
+    /// If given, the unspent payment of each deploy is refunded to the account it chooses for the
+    /// block's protocol version rather than to the paying account.
+    pub refund_destination: Option<&'a dyn RefundDestination>,
+    /// If set, execution of the block is aborted with an
+    /// [`ExecutionResultTooLarge`](super::BlockExecutionError::ExecutionResultTooLarge) error once
+    /// a deploy's serialized execution result exceeds it.  As this changes which blocks can be
+    /// executed, the limit must be identical across the network.
+    pub max_execution_result_bytes: Option<usize>,
+    /// The number of validators below which `on_validator_floor_breached` is called at switch
+    /// blocks.
+    pub validator_floor: Option<usize>,
+    /// Called at switch blocks with the upcoming era and its number of validators if there are
+    /// fewer of them than `validator_floor`.
+    pub on_validator_floor_breached: Option<&'a dyn Fn(EraId, usize)>,
+    /// If given, the global state values read at the pre-state root hash are served from and added
+    /// to it, and it is moved on to the resulting state root hash afterwards, so that the values
+    /// not written by the block are still cached for its child. It only holds values read from
+    /// LMDB, so it never changes the resulting state root hash.
+    pub read_through_cache: Option<&'a ReadThroughCache>,
+    /// From protocol version 2.0.0, a switch block may end further eras, one for each of these
+    /// reports in order, after the one reported by the block. The step is committed for each era in
+    /// turn, and the block carries the validators of the era following the last of them. Otherwise,
+    /// or if the block is not a switch block, giving additional era reports is an
+    /// [`UnexpectedEraReports`](super::BlockExecutionError::UnexpectedEraReports) error.
+    pub additional_era_reports: &'a [EraReport<PublicKey>],
+    /// If given, each deploy it returns `false` for is vetoed: rather than being executed, it is
+    /// recorded as a [`DeployOutcome::Vetoed`] failure without effects or cost. As this changes the
+    /// resulting state root hash, the filter must be applied identically across the network.
+    /// Deploys are not executed concurrently while filtered.
+    pub deploy_filter: Option<&'a dyn Fn(&Deploy) -> bool>,
+    /// If given, called between deploys, i.e. once fewer than there are deploys, or between the
+    /// batches of deploys executed concurrently, so that a single-threaded embedder can run other
+    /// tasks. It has no access to the block's execution state.
+    pub yield_hook: Option<&'a dyn Fn()>,
+    /// With [`CommitMode::Forbidden`], the block is executed as by
+    /// [`simulate_finalized_block`](super::simulate_finalized_block): the results are returned
+    /// along with the state root hash computed in memory, but nothing is written to or flushed in
+    /// LMDB, so neither `checkpointing` nor `batched_flushes` nor pruning apply.
+    pub commit_mode: CommitMode,
+    /// If given, the execution results checksum is computed with it rather than with the scheme of
+    /// the block's protocol version, e.g. to instrument it in tests. As this may change the
+    /// resulting state root hash, it is rejected with a
+    /// [`ChecksumOverrideNotAllowed`](super::BlockExecutionError::ChecksumOverrideNotAllowed)
+    /// error unless the node is built with the `testing` feature.
+    pub checksum_scheme: Option<&'a dyn ExecutionResultsChecksum>,
+}
+
+/// Batching of LMDB flushes across consecutive blocks, e.g. while catching up.
+///
+/// Rather than after each block, the LMDB environment is flushed after every `interval` executed
+/// blocks. Blocks executed since the last flush are not guaranteed to be persisted, so callers must
+/// call [`BatchedFlushes::flush_now`] at sync boundaries.
+#[derive(Debug)]
+pub struct BatchedFlushes {
+    /// The number of blocks to execute between consecutive flushes.
+    interval: NonZeroUsize,
+    /// The number of blocks executed since the last flush.
+    pending_blocks: AtomicUsize,
+}
+
+impl BatchedFlushes {
+    /// Constructs a new `BatchedFlushes`, flushing after every `interval` blocks.
+    pub fn new(interval: NonZeroUsize) -> Self {
+        BatchedFlushes {
+            interval,
+            pending_blocks: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the number of blocks executed since the last flush.
+    pub fn pending_blocks(&self) -> usize {
+        self.pending_blocks.load(Ordering::SeqCst)
+    }
+
+    /// Flushes the LMDB environment, persisting all blocks executed so far.
+    pub fn flush_now(
+        &self,
+        engine_state: &EngineState<LmdbGlobalState>,
+        metrics: Option<&Metrics>,
+    ) -> Result<(), lmdb::Error> {
+        flush_environment(engine_state, metrics)?;
+        self.pending_blocks.store(0, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Records the execution of a block, returning `true` if the batch is complete and the
+    /// environment is due to be flushed.
+    pub(super) fn record_block(&self) -> bool {
+        let pending_blocks = self.pending_blocks.fetch_add(1, Ordering::SeqCst) + 1;
+        pending_blocks >= self.interval.get()
+    }
+}
+
+/// A cache of the execution engine's results of executing deploys, so that re-executing a block,
+/// e.g. to validate it after downloading it, does not execute its deploys again.
+///
+/// Results are keyed by the deploy hash and a digest identifying the global state the deploy is
+/// executed against. As execution is deterministic, a cached result is the one executing the
+/// deploy again would return. Up to `capacity` results are kept, evicting the least recently used
+/// one when full.
+#[derive(Debug)]
+pub struct ExecutionResultCache {
+    capacity: NonZeroUsize,
+    results: Mutex<LinkedHashMap<(DeployHash, Digest), EngineExecutionResult>>,
+}
+
+impl ExecutionResultCache {
+    /// Creates a cache holding up to `capacity` execution results.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        ExecutionResultCache {
+            capacity,
+            results: Mutex::new(LinkedHashMap::new()),
+        }
+    }
+
+    /// Returns the number of cached execution results.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no execution results are cached.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Returns the cached result of executing the deploy against the given pre-state.
+    pub(super) fn get(
+        &self,
+        deploy_hash: DeployHash,
+        pre_state: Digest,
+    ) -> Option<EngineExecutionResult> {
+        self.lock().get_refresh(&(deploy_hash, pre_state)).cloned()
+    }
+
+    /// Caches the result of executing the deploy against the given pre-state.
+    pub(super) fn insert(
+        &self,
+        deploy_hash: DeployHash,
+        pre_state: Digest,
+        execution_result: EngineExecutionResult,
+    ) {
+        let mut results = self.lock();
+        results.insert((deploy_hash, pre_state), execution_result);
+        while results.len() > self.capacity.get() {
+            results.pop_front();
+        }
+    }
+
+    fn lock(
+        &self,
+    ) -> std::sync::MutexGuard<'_, LinkedHashMap<(DeployHash, Digest), EngineExecutionResult>> {
+        self.results
+            .lock()
+            .expect("execution result cache mutex poisoned")
+    }
+}
+
+/// A cache of the era validators read at the state root hash resulting from a switch block, so
+/// that re-executing the block, e.g. to validate it, does not read them from global state again.
+///
+/// The era validators are keyed by the state root hash and the protocol version they are read
+/// with. As they are a pure function of global state, a cached entry is what reading them again
+/// would return. Up to `capacity` entries are kept, evicting the least recently used one when
+/// full.
+#[derive(Debug)]
+pub struct EraValidatorsCache {
+    capacity: NonZeroUsize,
+    era_validators: Mutex<LinkedHashMap<(Digest, ProtocolVersion), EraValidators>>,
+}
+
+impl EraValidatorsCache {
+    /// Creates a cache holding the era validators of up to `capacity` state roots.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        EraValidatorsCache {
+            capacity,
+            era_validators: Mutex::new(LinkedHashMap::new()),
+        }
+    }
+
+    /// Returns the number of cached entries.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if no era validators are cached.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Returns the cached era validators read at the given state root hash.
+    pub(super) fn get(
+        &self,
+        state_root_hash: Digest,
+        protocol_version: ProtocolVersion,
+    ) -> Option<EraValidators> {
+        self.lock()
+            .get_refresh(&(state_root_hash, protocol_version))
+            .cloned()
+    }
+
+    /// Caches the era validators read at the given state root hash.
+    pub(super) fn insert(
+        &self,
+        state_root_hash: Digest,
+        protocol_version: ProtocolVersion,
+        era_validators: EraValidators,
+    ) {
+        let mut cached = self.lock();
+        cached.insert((state_root_hash, protocol_version), era_validators);
+        while cached.len() > self.capacity.get() {
+            cached.pop_front();
+        }
+    }
+
+    fn lock(
+        &self,
+    ) -> std::sync::MutexGuard<'_, LinkedHashMap<(Digest, ProtocolVersion), EraValidators>> {
+        self.era_validators
+            .lock()
+            .expect("era validators cache mutex poisoned")
+    }
+}
+
+/// A read-through cache of values of global state, shared across the execution of consecutive
+/// blocks so that hot values, such as those of the auction contract, are not read from the trie
+/// at every block.
+///
+/// The cache holds values of a single state root. Values are read through it at the pre-state
+/// root of a block, and once the block is written to LMDB the cache moves on to its resulting
+/// state root, dropping the values of the keys the block wrote. Executing a block on top of any
+/// other state root clears it. As every cached value is what reading the trie at the cache's state
+/// root would return, the cache never affects the computed state root hash. Up to `capacity`
+/// values are kept, evicting the least recently used one when full.
+///
+/// Clones share the cached values.
+#[derive(Clone, Debug)]
+pub struct ReadThroughCache(Arc<ReadThroughCacheInner>);
+
+#[derive(Debug)]
+struct ReadThroughCacheInner {
+    capacity: NonZeroUsize,
+    hits: AtomicU64,
+    state: Mutex<CachedState>,
+}
+
+/// The values cached at a single state root.
+#[derive(Debug, Default)]
+struct CachedState {
+    state_root_hash: Option<Digest>,
+    values: LinkedHashMap<Key, StoredValue>,
+}
+
+impl ReadThroughCache {
+    /// Creates a cache holding up to `capacity` values.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        ReadThroughCache(Arc::new(ReadThroughCacheInner {
+            capacity,
+            hits: AtomicU64::new(0),
+            state: Mutex::new(CachedState::default()),
+        }))
+    }
+
+    /// Returns the number of cached values.
+    pub fn len(&self) -> usize {
+        self.lock().values.len()
+    }
+
+    /// Returns `true` if no values are cached.
+    pub fn is_empty(&self) -> bool {
+        self.lock().values.is_empty()
+    }
+
+    /// Returns the state root hash the cached values were read at, or are known to be unchanged at.
+    pub fn state_root_hash(&self) -> Option<Digest> {
+        self.lock().state_root_hash
+    }
+
+    /// Returns the number of reads served from the cache so far.
+    pub fn hits(&self) -> u64 {
+        self.0.hits.load(Ordering::Relaxed)
+    }
+
+    /// Prepares the cache for reads at `state_root_hash`, clearing it unless it holds the values
+    /// of that state root.
+    pub(super) fn checkout(&self, state_root_hash: Digest) {
+        let mut state = self.lock();
+        if state.state_root_hash != Some(state_root_hash) {
+            state.values.clear();
+            state.state_root_hash = Some(state_root_hash);
+        }
+    }
+
+    /// Moves the cache on from `pre_state_root_hash` to `post_state_root_hash`, dropping the
+    /// values of `written_keys`, which must hold every key written between the two state roots.
+    ///
+    /// The cache is cleared if it does not hold the values of `pre_state_root_hash`.
+    pub(super) fn advance(
+        &self,
+        pre_state_root_hash: Digest,
+        post_state_root_hash: Digest,
+        written_keys: impl IntoIterator<Item = Key>,
+    ) {
+        let mut state = self.lock();
+        if state.state_root_hash == Some(pre_state_root_hash) {
+            for key in written_keys {
+                state.values.remove(&key);
+            }
+        } else {
+            state.values.clear();
+        }
+        state.state_root_hash = Some(post_state_root_hash);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CachedState> {
+        self.0
+            .state
+            .lock()
+            .expect("read-through cache mutex poisoned")
+    }
+}
+
+impl ReadCache for ReadThroughCache {
+    fn get(&self, state_root_hash: Digest, key: &Key) -> Option<StoredValue> {
+        let mut state = self.lock();
+        if state.state_root_hash != Some(state_root_hash) {
+            return None;
+        }
+        let value = state.values.get_refresh(key).cloned();
+        if value.is_some() {
+            self.0.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        value
+    }
+
+    fn insert(&self, state_root_hash: Digest, key: Key, value: StoredValue) {
+        let mut state = self.lock();
+        if state.state_root_hash != Some(state_root_hash) {
+            return;
+        }
+        state.values.insert(key, value);
+        while state.values.len() > self.0.capacity.get() {
+            state.values.pop_front();
+        }
+    }
+}
+
+/// A minimum reward below which validators are not paid by the step, to avoid writing many tiny
+/// rewards to global state.
+///
+/// A reward below the minimum is withheld and carried over: it is added to the validator's reward
+/// at the end of the next era. The rewards withheld by a step are reported by
+/// [`StepOutcomeSummary::withheld`], and must be passed as `carried_over` to the next era's step.
+#[derive(Clone, Debug, DataSize, PartialEq, Eq)]
+pub struct MinimumReward {
+    minimum_reward: U512,
+    carried_over: BTreeMap<PublicKey, U512>,
+}
+
+impl MinimumReward {
+    /// Constructs a new `MinimumReward`, with the rewards carried over from the previous era.
+    pub fn new(minimum_reward: U512, carried_over: BTreeMap<PublicKey, U512>) -> Self {
+        MinimumReward {
+            minimum_reward,
+            carried_over,
+        }
+    }
+
+    /// Returns the minimum reward paid to a validator.
+    pub fn minimum_reward(&self) -> U512 {
+        self.minimum_reward
+    }
+
+    /// Returns the rewards carried over from the previous era.
+    pub fn carried_over(&self) -> &BTreeMap<PublicKey, U512> {
+        &self.carried_over
+    }
+}
+
+/// A strategy for transforming the rewards of an era report before the step distributes them, e.g.
+/// to implement a custom emission schedule.
+///
+/// If no strategy is given, the rewards are distributed as reported.
+pub trait RewardStrategy: Send {
+    /// Returns the reward to distribute to `validator_id`, given the `reward` in the era report.
+    fn reward(&self, validator_id: &PublicKey, reward: u64) -> u64;
+}
+
+/// An ordering of the deploys of a block to execute them in, rather than in the order of the block.
+///
+/// Changing the order in which deploys are executed changes the resulting state root hash, so the
+/// ordering must be a total, deterministic function of the deploys' contents, applied identically
+/// by every node.
+pub trait DeployOrdering: Send + Sync {
+    /// Sorts `deploys` into the order they are to be executed in.
+    fn order(&self, deploys: &mut [Deploy]);
+}
+
+/// Orders deploys by descending gas price, breaking ties by ascending deploy hash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GasPriceOrdering;
+
+impl DeployOrdering for GasPriceOrdering {
+    fn order(&self, deploys: &mut [Deploy]) {
+        deploys.sort_by(|lhs, rhs| {
+            rhs.header()
+                .gas_price()
+                .cmp(&lhs.header().gas_price())
+                .then_with(|| lhs.hash().cmp(rhs.hash()))
+        });
+    }
+}
+
+/// A policy choosing the account the unspent payment of each deploy is refunded to, rather than
+/// the account which paid for it.
+///
+/// Redirecting refunds changes balances and so the state root hash, so the policy must depend only
+/// on the protocol version the block is executed with, and be applied identically by every node.
+/// Refunds to a refund purse set by the payment code of a deploy are not redirected.
+pub trait RefundDestination: Send + Sync {
+    /// Returns the account to refund to under `protocol_version`, or `None` to refund the paying
+    /// account.
+    fn refund_account(&self, protocol_version: ProtocolVersion) -> Option<AccountHash>;
+}
+
+/// Refunds the unspent payment of every deploy to a treasury account, from the given protocol
+/// version on.
+#[derive(Clone, Copy, Debug)]
+pub struct TreasuryRefunds {
+    treasury: AccountHash,
+    activation_protocol_version: ProtocolVersion,
+}
+
+impl TreasuryRefunds {
+    /// Creates a policy refunding to `treasury` from `activation_protocol_version` on.
+    pub fn new(treasury: AccountHash, activation_protocol_version: ProtocolVersion) -> Self {
+        TreasuryRefunds {
+            treasury,
+            activation_protocol_version,
+        }
+    }
+}
+
+impl RefundDestination for TreasuryRefunds {
+    fn refund_account(&self, protocol_version: ProtocolVersion) -> Option<AccountHash> {
+        (protocol_version >= self.activation_protocol_version).then(|| self.treasury)
+    }
+}
+
+/// A scheme for computing the checksum of the execution results of a block, which is written to
+/// its checksum registry.
+///
+/// Changing the scheme changes the state root hash of every block with deploys, so it is selected
+/// by protocol version, see [`execution_results_checksum_scheme`].
+pub trait ExecutionResultsChecksum: Send + Sync {
+    /// Returns the checksum of the execution results of a block, serialized as a
+    /// `Vec<ExecutionResult>` in the order of the block's deploys.
+    fn checksum(&self, serialized_execution_results: &[u8]) -> Digest;
+}
+
+/// The checksum scheme of execution results used by every protocol version so far: the root hash
+/// of the Merkle tree of the serialized results' chunks, or their hash if they fit into a single
+/// chunk.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultExecutionResultsChecksum;
+
+impl ExecutionResultsChecksum for DefaultExecutionResultsChecksum {
+    fn checksum(&self, serialized_execution_results: &[u8]) -> Digest {
+        Digest::hash_into_chunks_if_necessary(serialized_execution_results)
+    }
+}
+
+/// Accumulates the execution results of a block one at a time, maintaining the checksum of those
+/// appended so far, e.g. for a proposer to publish a running commitment to them.
+///
+/// Once every result of a block has been appended, the root equals the checksum computed with
+/// [`DefaultExecutionResultsChecksum`]. Only the hashes of the completed chunks of the serialized
+/// results are retained, along with the first chunk, which starts with their count, and the chunk
+/// being filled.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionResultsAccumulator {
+    count: u32,
+    /// The bytes of the first chunk following the count.
+    first_chunk: Vec<u8>,
+    /// The hashes of the completed chunks after the first one.
+    chunk_hashes: Vec<Digest>,
+    /// The bytes of the chunk being filled, if the first one is complete.
+    current_chunk: Vec<u8>,
+}
+
+impl ExecutionResultsAccumulator {
+    /// Creates an accumulator holding no execution results.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the next execution result of the block.
+    pub fn append(&mut self, execution_result: &ExecutionResult) -> Result<(), bytesrepr::Error> {
+        self.count = self
+            .count
+            .checked_add(1)
+            .ok_or(bytesrepr::Error::NotRepresentable)?;
+        let serialized_execution_result = execution_result.to_bytes()?;
+        let first_chunk_capacity = ChunkWithProof::CHUNK_SIZE_BYTES - U32_SERIALIZED_LENGTH;
+        let first_chunk_free = first_chunk_capacity - self.first_chunk.len();
+        let (into_first_chunk, mut remainder) = serialized_execution_result
+            .split_at(first_chunk_free.min(serialized_execution_result.len()));
+        self.first_chunk.extend_from_slice(into_first_chunk);
+        while !remainder.is_empty() {
+            let current_chunk_free = ChunkWithProof::CHUNK_SIZE_BYTES - self.current_chunk.len();
+            let (into_current_chunk, rest) =
+                remainder.split_at(current_chunk_free.min(remainder.len()));
+            self.current_chunk.extend_from_slice(into_current_chunk);
+            if self.current_chunk.len() == ChunkWithProof::CHUNK_SIZE_BYTES {
+                self.chunk_hashes.push(Digest::hash(&self.current_chunk));
+                self.current_chunk.clear();
+            }
+            remainder = rest;
+        }
+        Ok(())
+    }
+
+    /// Returns the checksum of the execution results appended so far.
+    pub fn root(&self) -> Digest {
+        // Serialized as a `u32`, the count is the little-endian representation of its value.
+        let first_chunk_hash = Digest::hash_pair(self.count.to_le_bytes(), &self.first_chunk);
+        if self.chunk_hashes.is_empty() && self.current_chunk.is_empty() {
+            return first_chunk_hash;
+        }
+        let mut leaves = Vec::with_capacity(self.chunk_hashes.len() + 2);
+        leaves.push(first_chunk_hash);
+        leaves.extend_from_slice(&self.chunk_hashes);
+        if !self.current_chunk.is_empty() {
+            leaves.push(Digest::hash(&self.current_chunk));
+        }
+        Digest::hash_merkle_tree(leaves)
+    }
+}
+
+/// Returns the checksum scheme of execution results used under `protocol_version`.
+///
+/// [`ExecutionResultInclusionProof`](super::ExecutionResultInclusionProof)s can only be verified
+/// against checksums computed with the default scheme.
+pub fn execution_results_checksum_scheme(
+    _protocol_version: ProtocolVersion,
+) -> &'static dyn ExecutionResultsChecksum {
+    &DefaultExecutionResultsChecksum
+}
+
+/// The checksums written to the checksum registry in global state when executing a block.
+#[derive(Clone, Copy, Debug, DataSize, PartialEq, Eq)]
+pub struct BlockChecksums {
+    approvals_checksum: Digest,
+    execution_results_checksum: Digest,
+}
+
+impl BlockChecksums {
+    pub(crate) fn new(approvals_checksum: Digest, execution_results_checksum: Digest) -> Self {
+        BlockChecksums {
+            approvals_checksum,
+            execution_results_checksum,
+        }
+    }
+
+    /// Returns the checksum of the approvals of the block's deploys.
+    pub fn approvals_checksum(&self) -> Digest {
+        self.approvals_checksum
+    }
+
+    /// Returns the checksum of the execution results of the block's deploys.
+    pub fn execution_results_checksum(&self) -> Digest {
+        self.execution_results_checksum
+    }
+}
+
+impl ToBytes for BlockChecksums {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut buffer = bytesrepr::allocate_buffer(self)?;
+        self.write_bytes(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.approvals_checksum.serialized_length()
+            + self.execution_results_checksum.serialized_length()
+    }
+
+    fn write_bytes(&self, writer: &mut Vec<u8>) -> Result<(), bytesrepr::Error> {
+        self.approvals_checksum.write_bytes(writer)?;
+        self.execution_results_checksum.write_bytes(writer)
+    }
+}
+
+impl FromBytes for BlockChecksums {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (approvals_checksum, remainder) = Digest::from_bytes(bytes)?;
+        let (execution_results_checksum, remainder) = Digest::from_bytes(remainder)?;
+        Ok((
+            BlockChecksums::new(approvals_checksum, execution_results_checksum),
+            remainder,
+        ))
+    }
 }
 
 #[doc(hidden)]
@@ -102,8 +1427,432 @@ pub struct BlockAndExecutionResults {
     /// The [`ApprovalsHashes`] for the deploys in this block.
     pub(crate) approvals_hashes: Box<ApprovalsHashes>,
     /// The results from executing the deploys in the block.
-    pub(crate) execution_results: Vec<(DeployHash, DeployHeader, ExecutionResult)>,
+    pub(crate) execution_results: Vec<DeployExecutionInfo>,
     /// The [`ExecutionJournal`] and the upcoming validator sets determined by the `step`
     pub(crate) maybe_step_effect_and_upcoming_era_validators:
         Option<StepEffectAndUpcomingEraValidators>,
+    /// The state root hashes recorded during execution, if tracing of state roots was enabled.
+    pub(crate) maybe_state_root_trace: Option<Vec<(StateRootCheckpoint, Digest)>>,
+    /// The state root hash after committing the deploys and the checksum registry, but before
+    /// running the step.
+    pub(crate) post_deploys_root: Digest,
+    /// The state root hash of the block, after running the step at switch blocks and pruning.
+    pub(crate) final_root: Digest,
+    /// The index of each deploy's result in `execution_results`, built on the first lookup.
+    #[data_size(with = ds::once_cell)]
+    pub(crate) execution_result_index: OnceCell<HashMap<DeployHash, usize>>,
+    /// The effects of each deploy in the order they were committed, if capturing them was
+    /// requested.
+    ///
+    /// Only meant for debugging, so they are not serialized.
+    pub(crate) captured_journals: Option<Vec<CapturedJournal>>,
+    /// The checksums written to the checksum registry, or `None` if it was not written for the
+    /// block.
+    pub(crate) checksums: Option<BlockChecksums>,
+    /// The highest gas price declared by the block's deploys, or `None` if it has none.
+    pub(crate) gas_price: Option<u64>,
+}
+
+/// The version of the binary format written by [`BlockAndExecutionResults::to_bytes_versioned`].
+const BLOCK_AND_EXECUTION_RESULTS_FORMAT_VERSION: u8 = 3;
+
+/// The earliest version of the binary format which can still be read. Version 1 lacks the block's
+/// checksums, and versions 1 and 2 lack its gas price.
+const MIN_BLOCK_AND_EXECUTION_RESULTS_FORMAT_VERSION: u8 = 1;
+
+impl BlockAndExecutionResults {
+    /// Returns the result of executing the deploy with the given hash, if it is in the block.
+    ///
+    /// The results are indexed by deploy hash on the first call, so that later lookups take
+    /// constant time. `execution_results` remains in the order the deploys were executed.
+    pub fn result_for(&self, deploy_hash: &DeployHash) -> Option<&ExecutionResult> {
+        let index = self.execution_result_index.get_or_init(|| {
+            self.execution_results
+                .iter()
+                .enumerate()
+                .map(|(index, deploy_execution_info)| (deploy_execution_info.deploy_hash, index))
+                .collect()
+        });
+        index
+            .get(deploy_hash)
+            .and_then(|index| self.execution_results.get(*index))
+            .filter(|deploy_execution_info| deploy_execution_info.deploy_hash == *deploy_hash)
+            .map(|deploy_execution_info| &deploy_execution_info.result)
+    }
+
+    /// Returns the effects of each deploy in the order they were committed, if capturing them
+    /// was requested when executing the block.
+    pub fn captured_journals(&self) -> Option<&[CapturedJournal]> {
+        self.captured_journals.as_deref()
+    }
+
+    /// Returns the checksums written to the checksum registry in global state, or `None` if the
+    /// registry was not written for the block, e.g. as it has no deploys.
+    ///
+    /// These are also `None` if decoded from version 1 of the binary format.
+    pub fn checksums(&self) -> Option<BlockChecksums> {
+        self.checksums
+    }
+
+    /// Returns the gas price at which the gas consumed by the block's deploys was converted to
+    /// motes, e.g. to compute their refunds.
+    ///
+    /// Neither the chainspec nor the block itself declare a gas price: each deploy converts its gas
+    /// at the price in its header, so where they differ this is the highest of them. It is `None`
+    /// for a block without deploys, as no gas was converted, and if decoded from versions 1 or 2 of
+    /// the binary format.
+    pub fn gas_price(&self) -> Option<u64> {
+        self.gas_price
+    }
+
+    /// Serializes `self` into a stable binary format suitable for export.
+    ///
+    /// The encoding starts with a format version byte and ends with a checksum of everything
+    /// preceding it, computed as [`Chunkable`](crate::types::Chunkable) data is hashed.
+    pub fn to_bytes_versioned(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut bytes = vec![BLOCK_AND_EXECUTION_RESULTS_FORMAT_VERSION];
+        self.block.write_bytes(&mut bytes)?;
+        self.approvals_hashes.write_bytes(&mut bytes)?;
+        self.execution_results.write_bytes(&mut bytes)?;
+        self.maybe_step_effect_and_upcoming_era_validators
+            .write_bytes(&mut bytes)?;
+        self.maybe_state_root_trace.write_bytes(&mut bytes)?;
+        self.post_deploys_root.write_bytes(&mut bytes)?;
+        self.final_root.write_bytes(&mut bytes)?;
+        self.checksums.write_bytes(&mut bytes)?;
+        self.gas_price.write_bytes(&mut bytes)?;
+        let checksum = Digest::hash_into_chunks_if_necessary(&bytes);
+        checksum.write_bytes(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Deserializes an instance written by [`BlockAndExecutionResults::to_bytes_versioned`],
+    /// verifying its format version and checksum.
+    pub fn from_bytes_versioned(bytes: &[u8]) -> Result<Self, VersionedBytesError> {
+        if bytes.len() < Digest::LENGTH {
+            return Err(bytesrepr::Error::EarlyEndOfStream.into());
+        }
+        let (contents, checksum_bytes) = bytes.split_at(bytes.len() - Digest::LENGTH);
+        let (checksum, _) = Digest::from_bytes(checksum_bytes)?;
+        if Digest::hash_into_chunks_if_necessary(contents) != checksum {
+            return Err(VersionedBytesError::ChecksumMismatch);
+        }
+
+        let (version, remainder) = u8::from_bytes(contents)?;
+        if !(MIN_BLOCK_AND_EXECUTION_RESULTS_FORMAT_VERSION
+            ..=BLOCK_AND_EXECUTION_RESULTS_FORMAT_VERSION)
+            .contains(&version)
+        {
+            return Err(VersionedBytesError::UnsupportedVersion(version));
+        }
+        let (block, remainder) = Block::from_bytes(remainder)?;
+        let (approvals_hashes, remainder) = ApprovalsHashes::from_bytes(remainder)?;
+        let (execution_results, remainder) = Vec::<DeployExecutionInfo>::from_bytes(remainder)?;
+        let (maybe_step_effect_and_upcoming_era_validators, remainder) =
+            Option::<StepEffectAndUpcomingEraValidators>::from_bytes(remainder)?;
+        let (maybe_state_root_trace, remainder) =
+            Option::<Vec<(StateRootCheckpoint, Digest)>>::from_bytes(remainder)?;
+        let (post_deploys_root, remainder) = Digest::from_bytes(remainder)?;
+        let (final_root, remainder) = Digest::from_bytes(remainder)?;
+        let (checksums, remainder) = if version > 1 {
+            Option::<BlockChecksums>::from_bytes(remainder)?
+        } else {
+            (None, remainder)
+        };
+        let (gas_price, remainder) = if version > 2 {
+            Option::<u64>::from_bytes(remainder)?
+        } else {
+            (None, remainder)
+        };
+        if !remainder.is_empty() {
+            return Err(bytesrepr::Error::LeftOverBytes.into());
+        }
+
+        Ok(BlockAndExecutionResults {
+            block: Arc::new(block),
+            approvals_hashes: Box::new(approvals_hashes),
+            execution_results,
+            maybe_step_effect_and_upcoming_era_validators,
+            maybe_state_root_trace,
+            post_deploys_root,
+            final_root,
+            execution_result_index: OnceCell::new(),
+            captured_journals: None,
+            checksums,
+            gas_price,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use rand::Rng;
+
+    use casper_execution_engine::storage::trie::merkle_proof::TrieMerkleProof;
+    use casper_types::{
+        system::auction::{Bid, Delegator},
+        testing::TestRng,
+        AccessRights, CLValue, URef,
+    };
+
+    use super::*;
+    use crate::types::{ApprovalsHash, Deploy};
+
+    fn random_digest(rng: &mut TestRng) -> Digest {
+        Digest::hash(rng.gen::<[u8; 32]>())
+    }
+
+    fn random_block_and_execution_results(rng: &mut TestRng) -> BlockAndExecutionResults {
+        let block = Block::random(rng);
+        let approvals_hashes = (0..rng.gen_range(0..4))
+            .map(|_| ApprovalsHash::random(rng))
+            .collect();
+        let merkle_proof_approvals = TrieMerkleProof::new(
+            Key::ChecksumRegistry,
+            StoredValue::CLValue(CLValue::from_t(rng.gen::<u64>()).unwrap()),
+            VecDeque::new(),
+        );
+        let approvals_hashes =
+            ApprovalsHashes::new(block.hash(), approvals_hashes, merkle_proof_approvals);
+
+        let execution_results = (0..rng.gen_range(0..4))
+            .map(|_| {
+                let error_message = format!("error {}", rng.gen::<u32>());
+                let outcome = match rng.gen_range(0..4) {
+                    0 => DeployOutcome::Success,
+                    1 => DeployOutcome::Failure { error_message },
+                    2 => DeployOutcome::InsufficientBalance { error_message },
+                    _ => DeployOutcome::Vetoed,
+                };
+                let result = rng.gen::<ExecutionResult>();
+                DeployExecutionInfo {
+                    deploy_hash: DeployHash::random(rng),
+                    header: Deploy::random(rng).header().clone(),
+                    effect_count: count_effects(&result),
+                    result,
+                    outcome,
+                    cost: U512::from(rng.gen::<u64>()),
+                }
+            })
+            .collect();
+
+        let maybe_step_effect_and_upcoming_era_validators = if rng.gen() {
+            let step_execution_journal = ExecutionJournal::new(vec![
+                (Key::Hash(rng.gen()), Transform::Identity),
+                (
+                    Key::Hash(rng.gen()),
+                    Transform::Write(StoredValue::CLValue(
+                        CLValue::from_t(rng.gen::<u64>()).unwrap(),
+                    )),
+                ),
+                (
+                    Key::Balance(rng.gen()),
+                    Transform::AddUInt512(U512::from(rng.gen::<u64>())),
+                ),
+                (Key::Hash(rng.gen()), Transform::AddKeys(Default::default())),
+            ]);
+            let validators = (0..rng.gen_range(1..4))
+                .map(|_| (PublicKey::random(rng), U512::from(rng.gen::<u64>())))
+                .collect();
+            let step_outcome_summary = StepOutcomeSummary {
+                evicted: vec![PublicKey::random(rng)],
+                slashed: vec![PublicKey::random(rng)],
+                rewarded: BTreeMap::from([(PublicKey::random(rng), U512::from(rng.gen::<u64>()))]),
+                withheld: BTreeMap::from([(PublicKey::random(rng), U512::from(rng.gen::<u64>()))]),
+            };
+            Some(StepEffectAndUpcomingEraValidators {
+                upcoming_era_validators: BTreeMap::from([(EraId::new(rng.gen()), validators)]),
+                step_journal_summary: summarize_step_journal(&step_execution_journal),
+                step_execution_journal,
+                step_outcome_summary,
+            })
+        } else {
+            None
+        };
+
+        let maybe_state_root_trace = if rng.gen() {
+            Some(vec![
+                (
+                    StateRootCheckpoint::Deploy(DeployHash::random(rng)),
+                    random_digest(rng),
+                ),
+                (StateRootCheckpoint::ChecksumRegistry, random_digest(rng)),
+                (StateRootCheckpoint::Step, random_digest(rng)),
+                (StateRootCheckpoint::Prune, random_digest(rng)),
+            ])
+        } else {
+            None
+        };
+
+        BlockAndExecutionResults {
+            block: Arc::new(block),
+            approvals_hashes: Box::new(approvals_hashes),
+            execution_results,
+            maybe_step_effect_and_upcoming_era_validators,
+            maybe_state_root_trace,
+            post_deploys_root: random_digest(rng),
+            final_root: random_digest(rng),
+            execution_result_index: OnceCell::new(),
+            captured_journals: None,
+            checksums: rng
+                .gen::<bool>()
+                .then(|| BlockChecksums::new(random_digest(rng), random_digest(rng))),
+            gas_price: rng.gen(),
+        }
+    }
+
+    #[test]
+    fn should_look_up_execution_results_by_deploy_hash() {
+        let mut rng = TestRng::new();
+        let results = loop {
+            let results = random_block_and_execution_results(&mut rng);
+            if results.execution_results.len() > 1 {
+                break results;
+            }
+        };
+        let deploy_hashes = results
+            .execution_results
+            .iter()
+            .map(|deploy_execution_info| deploy_execution_info.deploy_hash)
+            .collect::<Vec<_>>();
+
+        for deploy_execution_info in &results.execution_results {
+            assert_eq!(
+                results.result_for(&deploy_execution_info.deploy_hash),
+                Some(&deploy_execution_info.result)
+            );
+        }
+        assert_eq!(results.result_for(&DeployHash::random(&mut rng)), None);
+
+        // Indexing the results leaves them in execution order.
+        let deploy_hashes_after_lookup = results
+            .execution_results
+            .iter()
+            .map(|deploy_execution_info| deploy_execution_info.deploy_hash)
+            .collect::<Vec<_>>();
+        assert_eq!(deploy_hashes_after_lookup, deploy_hashes);
+    }
+
+    #[test]
+    fn should_summarize_step_journal() {
+        let mut rng = TestRng::new();
+        let mut random_purse = || URef::new(rng.gen(), AccessRights::READ_ADD_WRITE);
+        let (validator_purse, delegator_purse, evicted_purse, other_purse) = (
+            random_purse(),
+            random_purse(),
+            random_purse(),
+            random_purse(),
+        );
+        let validator = PublicKey::random(&mut rng);
+        let delegator = PublicKey::random(&mut rng);
+        let evicted = PublicKey::random(&mut rng);
+
+        let mut bid = Bid::unlocked(validator.clone(), validator_purse, U512::from(100), 0);
+        bid.delegators_mut().insert(
+            delegator.clone(),
+            Delegator::unlocked(
+                delegator,
+                U512::from(10),
+                delegator_purse,
+                validator.clone(),
+            ),
+        );
+        let mut evicted_bid = Bid::unlocked(evicted.clone(), evicted_purse, U512::from(50), 0);
+        evicted_bid.deactivate();
+        let execution_journal = ExecutionJournal::new(vec![
+            (
+                Key::Balance(validator_purse.addr()),
+                Transform::AddUInt512(U512::from(30)),
+            ),
+            (
+                Key::Balance(delegator_purse.addr()),
+                Transform::AddUInt512(U512::from(5)),
+            ),
+            (Key::Balance(other_purse.addr()), Transform::Identity),
+            (
+                Key::Bid(validator.to_account_hash()),
+                Transform::Write(StoredValue::Bid(Box::new(bid))),
+            ),
+            (
+                Key::Bid(evicted.to_account_hash()),
+                Transform::Write(StoredValue::Bid(Box::new(evicted_bid))),
+            ),
+            (
+                Key::EraInfo(EraId::new(1)),
+                Transform::Write(StoredValue::CLValue(CLValue::from_t(()).unwrap())),
+            ),
+        ]);
+
+        let summary = summarize_step_journal(&execution_journal);
+        assert_eq!(summary.minted_rewards(), U512::from(35));
+        assert_eq!(summary.evictions(), 1);
+        // The evicted validator's purse was not touched, and the other purse is no bonding purse.
+        assert_eq!(
+            summary.bid_purses_touched(),
+            &BTreeSet::from([
+                Key::Balance(validator_purse.addr()),
+                Key::Balance(delegator_purse.addr()),
+            ])
+        );
+    }
+
+    #[test]
+    fn should_roundtrip_versioned_bytes() {
+        // `TestRng` can only be constructed once per thread, so the random cases are generated in
+        // a loop rather than by `proptest`.
+        let mut rng = TestRng::new();
+        for _ in 0..50 {
+            let results = random_block_and_execution_results(&mut rng);
+
+            let bytes = results.to_bytes_versioned().unwrap();
+            let decoded = BlockAndExecutionResults::from_bytes_versioned(&bytes).unwrap();
+            assert_eq!(decoded.block.hash(), results.block.hash());
+            assert_eq!(decoded.post_deploys_root, results.post_deploys_root);
+            assert_eq!(decoded.final_root, results.final_root);
+            assert_eq!(decoded.checksums, results.checksums);
+            assert_eq!(decoded.gas_price, results.gas_price);
+            assert_eq!(decoded.to_bytes_versioned().unwrap(), bytes);
+
+            // Version 2 lacks the gas price, but can still be read.
+            let version_2_len =
+                bytes.len() - Digest::LENGTH - results.gas_price.serialized_length();
+            let mut version_2 = bytes[..version_2_len].to_vec();
+            version_2[0] = 2;
+            let checksum = Digest::hash_into_chunks_if_necessary(&version_2);
+            version_2.extend_from_slice(checksum.as_ref());
+            let decoded = BlockAndExecutionResults::from_bytes_versioned(&version_2).unwrap();
+            assert_eq!(decoded.checksums, results.checksums);
+            assert_eq!(decoded.gas_price, None);
+
+            // Version 1 also lacks the checksums.
+            let mut version_1 =
+                bytes[..version_2_len - results.checksums.serialized_length()].to_vec();
+            version_1[0] = 1;
+            let checksum = Digest::hash_into_chunks_if_necessary(&version_1);
+            version_1.extend_from_slice(checksum.as_ref());
+            let decoded = BlockAndExecutionResults::from_bytes_versioned(&version_1).unwrap();
+            assert_eq!(decoded.final_root, results.final_root);
+            assert!(decoded.checksums.is_none());
+
+            let mut corrupted = bytes.clone();
+            let index = rng.gen_range(0..corrupted.len());
+            corrupted[index] ^= 1;
+            assert!(matches!(
+                BlockAndExecutionResults::from_bytes_versioned(&corrupted),
+                Err(VersionedBytesError::ChecksumMismatch)
+            ));
+
+            let mut future_version = bytes[..bytes.len() - Digest::LENGTH].to_vec();
+            future_version[0] = BLOCK_AND_EXECUTION_RESULTS_FORMAT_VERSION + 1;
+            let checksum = Digest::hash_into_chunks_if_necessary(&future_version);
+            future_version.extend_from_slice(checksum.as_ref());
+            assert!(matches!(
+                BlockAndExecutionResults::from_bytes_versioned(&future_version),
+                Err(VersionedBytesError::UnsupportedVersion(version))
+                    if version == BLOCK_AND_EXECUTION_RESULTS_FORMAT_VERSION + 1
+            ));
+        }
+    }
 }
@@ -21,7 +21,7 @@ use super::{
     Error, ErrorCode, ReactorEventT, RpcWithParams,
 };
 use crate::{
-    components::contract_runtime::SpeculativeExecutionState,
+    components::contract_runtime::{SpeculativeExecutionError, SpeculativeExecutionState},
     effect::EffectBuilder,
     types::{Block, BlockHash, Deploy},
 };
@@ -103,6 +103,8 @@ impl RpcWithParams for SpeculativeExec {
             state_root_hash: *block.state_root_hash(),
             block_time: block.timestamp(),
             protocol_version: block.protocol_version(),
+            proposer: None,
+            era_id: Some(block.header().next_block_era_id()),
         };
 
         let accept_deploy_result = effect_builder
@@ -130,7 +132,10 @@ impl RpcWithParams for SpeculativeExec {
                 ErrorCode::NoSuchBlock,
                 "block hash not found".to_string(),
             )),
-            Err(error) => {
+            Err(SpeculativeExecutionError::InvalidDeploy(error)) => {
+                Err(Error::new(ErrorCode::InvalidDeploy, error.to_string()))
+            }
+            Err(SpeculativeExecutionError::EngineState(error)) => {
                 let rpc_error = match error {
                     EngineStateError::RootNotFound(_) => Error::new(ErrorCode::NoSuchStateRoot, ""),
                     EngineStateError::WasmPreprocessing(error) => {
@@ -46,7 +46,9 @@ use crate::{
         network::NetworkInsights,
         upgrade_watcher::NextUpgrade,
     },
-    contract_runtime::{ContractRuntimeError, SpeculativeExecutionState},
+    contract_runtime::{
+        ContractRuntimeError, SpeculativeExecutionError, SpeculativeExecutionState,
+    },
     effect::{AutoClosingResponder, Responder},
     reactor::main_reactor::ReactorState,
     rpcs::docs::OpenRpcSchema,
@@ -925,7 +927,7 @@ pub(crate) enum ContractRuntimeRequest {
         /// Deploy to execute.
         deploy: Arc<Deploy>,
         /// Results
-        responder: Responder<Result<Option<ExecutionResult>, engine_state::Error>>,
+        responder: Responder<Result<Option<ExecutionResult>, SpeculativeExecutionError>>,
     },
 }
 
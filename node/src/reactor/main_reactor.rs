@@ -1059,6 +1059,16 @@ impl reactor::Reactor for MainReactor {
             chainspec.core_config.allow_unrestricted_transfers,
             chainspec.core_config.refund_handling,
             chainspec.core_config.fee_handling,
+            chainspec.core_config.slash_equivocators,
+            chainspec.core_config.block_execution_gas_limit,
+            chainspec.core_config.max_execution_result_bytes,
+            chainspec.core_config.validator_floor,
+            chainspec.core_config.strict_block_execution,
+            Some(chainspec.deploy_config.max_deploys_per_block()),
+            chainspec.core_config.allow_maintenance_steps,
+            chainspec.core_config.validate_proposer,
+            chainspec.core_config.max_trie_depth,
+            chainspec.network_config.name.clone(),
         )?;
 
         let network = Network::new(
@@ -16,6 +16,7 @@ use std::{
     error::Error as StdError,
     fmt::{self, Debug, Display, Formatter},
     hash::{Hash, Hasher},
+    marker::PhantomData,
 };
 
 use datasize::DataSize;
@@ -30,7 +31,7 @@ use tracing::error;
 
 use casper_hashing::{ChunkWithProofVerificationError, Digest};
 use casper_types::{
-    bytesrepr::{self, FromBytes, ToBytes},
+    bytesrepr::{self, FromBytes, ToBytes, U32_SERIALIZED_LENGTH},
     crypto, EraId, ProtocolVersion, PublicKey, SecretKey, Signature, Timestamp, U512,
 };
 #[cfg(any(feature = "testing", test))]
@@ -54,7 +55,7 @@ use crate::{
     },
     utils::{ds, DisplayIter},
 };
-pub(crate) use approvals_hashes::ApprovalsHashes;
+pub(crate) use approvals_hashes::{ApprovalsHashes, ApprovalsHashesValidationError};
 pub(crate) use meta_block::{
     MergeMismatchError as MetaBlockMergeError, MetaBlock, State as MetaBlockState,
 };
@@ -2582,8 +2583,56 @@ impl Display for FinalitySignatureId {
 pub(crate) fn compute_approvals_checksum(
     deploy_ids: Vec<DeployId>,
 ) -> Result<Digest, bytesrepr::Error> {
-    let bytes = deploy_ids.into_bytes()?;
-    Ok(Digest::hash(bytes))
+    let mut builder = VecChecksumBuilder::new();
+    for deploy_id in &deploy_ids {
+        builder.push(deploy_id)?;
+    }
+    Ok(builder.finalize())
+}
+
+/// Computes the checksum of items added one at a time, e.g. the approvals or the execution results
+/// of a block, over their serialization as a `Vec<T>`, without collecting them into one.
+///
+/// As the serialization starts with the count of items, it is only hashed once all items have been
+/// pushed: until then, the serialized items are retained.
+pub(crate) struct VecChecksumBuilder<T> {
+    count: u32,
+    bytes: Vec<u8>,
+    _item: PhantomData<T>,
+}
+
+impl<T: ToBytes> VecChecksumBuilder<T> {
+    /// Constructs a builder holding no items.
+    pub(crate) fn new() -> Self {
+        // Reserve room for the count, which is only known once all items have been pushed.
+        VecChecksumBuilder {
+            count: 0,
+            bytes: vec![0; U32_SERIALIZED_LENGTH],
+            _item: PhantomData,
+        }
+    }
+
+    /// Appends an item.
+    pub(crate) fn push(&mut self, item: &T) -> Result<(), bytesrepr::Error> {
+        self.count = self
+            .count
+            .checked_add(1)
+            .ok_or(bytesrepr::Error::NotRepresentable)?;
+        item.write_bytes(&mut self.bytes)
+    }
+
+    /// Returns the hash of the items pushed so far.
+    pub(crate) fn finalize(self) -> Digest {
+        self.finalize_with(Digest::hash)
+    }
+
+    /// Returns the checksum of the items pushed so far, computed by `checksum` from their
+    /// serialization.
+    pub(crate) fn finalize_with(mut self, checksum: impl FnOnce(&[u8]) -> Digest) -> Digest {
+        // The count is serialized as a little-endian `u32`, as for any `Vec`.
+        self.bytes[..U32_SERIALIZED_LENGTH].copy_from_slice(&self.count.to_le_bytes());
+        checksum(&self.bytes)
+    }
 }
 
 #[cfg(test)]
@@ -2722,4 +2771,23 @@ mod tests {
         // Test should fail b/c `signature` is over `era_id=1` and here we're using `era_id=2`.
         assert!(fs_manufactured.is_verified().is_err());
     }
+
+    #[test]
+    fn should_compute_approvals_checksum_incrementally() {
+        let mut rng = TestRng::new();
+        let deploy_ids = (0..3)
+            .map(|_| Deploy::random(&mut rng).fetch_id())
+            .collect::<Vec<_>>();
+
+        let mut builder = VecChecksumBuilder::new();
+        for deploy_id in &deploy_ids {
+            builder.push(deploy_id).unwrap();
+        }
+        let expected = Digest::hash(deploy_ids.clone().into_bytes().unwrap());
+        assert_eq!(builder.finalize(), expected);
+        assert_eq!(compute_approvals_checksum(deploy_ids).unwrap(), expected);
+
+        let empty = Digest::hash(Vec::<DeployId>::new().into_bytes().unwrap());
+        assert_eq!(VecChecksumBuilder::<DeployId>::new().finalize(), empty);
+    }
 }
@@ -10,12 +10,17 @@ use thiserror::Error;
 
 use casper_execution_engine::storage::trie::merkle_proof::TrieMerkleProof;
 use casper_hashing::Digest;
-use casper_types::{bytesrepr, Key, StoredValue};
+use casper_types::{
+    bytesrepr::{self, FromBytes, ToBytes},
+    Key, StoredValue,
+};
 
 use super::{Block, BlockHash};
 use crate::{
     components::{
-        contract_runtime::APPROVALS_CHECKSUM_NAME,
+        contract_runtime::{
+            APPROVALS_CHECKSUM_NAME, SKIP_EMPTY_BLOCK_CHECKSUM_REGISTRY_PROTOCOL_VERSION,
+        },
         fetcher::{FetchItem, Tag},
     },
     types::{self, ApprovalsHash, DeployId},
@@ -69,6 +74,16 @@ impl ApprovalsHashes {
             });
         }
 
+        // From `SKIP_EMPTY_BLOCK_CHECKSUM_REGISTRY_PROTOCOL_VERSION`, blocks without deploys may
+        // leave the checksum registry of their parent in place, and there are no approvals to
+        // check it against.
+        if self.approvals_hashes.is_empty()
+            && block.deploy_and_transfer_hashes().next().is_none()
+            && block.protocol_version() >= SKIP_EMPTY_BLOCK_CHECKSUM_REGISTRY_PROTOCOL_VERSION
+        {
+            return Ok(());
+        }
+
         let value_in_proof = self
             .merkle_proof_approvals
             .value()
@@ -128,6 +143,38 @@ impl FetchItem for ApprovalsHashes {
     }
 }
 
+impl ToBytes for ApprovalsHashes {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut buffer = bytesrepr::allocate_buffer(self)?;
+        self.write_bytes(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.block_hash.serialized_length()
+            + self.approvals_hashes.serialized_length()
+            + self.merkle_proof_approvals.serialized_length()
+    }
+
+    fn write_bytes(&self, writer: &mut Vec<u8>) -> Result<(), bytesrepr::Error> {
+        self.block_hash.write_bytes(writer)?;
+        self.approvals_hashes.write_bytes(writer)?;
+        self.merkle_proof_approvals.write_bytes(writer)
+    }
+}
+
+impl FromBytes for ApprovalsHashes {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (block_hash, remainder) = BlockHash::from_bytes(bytes)?;
+        let (approvals_hashes, remainder) = Vec::<ApprovalsHash>::from_bytes(remainder)?;
+        let (merkle_proof_approvals, remainder) =
+            TrieMerkleProof::<Key, StoredValue>::from_bytes(remainder)?;
+        let approvals_hashes =
+            ApprovalsHashes::new(&block_hash, approvals_hashes, merkle_proof_approvals);
+        Ok((approvals_hashes, remainder))
+    }
+}
+
 impl Display for ApprovalsHashes {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "approvals hashes for {}", self.block_hash)
@@ -112,6 +112,40 @@ pub struct CoreConfig {
     pub(crate) refund_handling: RefundHandling,
     /// Fee handling.
     pub(crate) fee_handling: FeeHandling,
+    /// If set to true, equivocators are slashed at the end of an era rather than merely evicted.
+    /// Setting this option makes sense only for private chains.  Defaults to `false`.
+    #[serde(default)]
+    pub(crate) slash_equivocators: bool,
+    /// The maximum cumulative gas the deploys of a block may consume before execution of the block
+    /// is aborted.  Unlimited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) block_execution_gas_limit: Option<u64>,
+    /// The maximum size in bytes of the serialized execution result of a deploy, above which
+    /// execution of the block is aborted.  Unlimited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_execution_result_bytes: Option<u32>,
+    /// The number of validators below which an alert is raised when a switch block determines the
+    /// validators of an upcoming era.  No alert is raised if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) validator_floor: Option<u32>,
+    /// If set to true, execution of a block is aborted as soon as one of its deploys fails, so
+    /// blocks are either executed in full or not at all.  Setting this option makes sense only for
+    /// private chains.  Defaults to `false`.
+    #[serde(default)]
+    pub(crate) strict_block_execution: bool,
+    /// If set to true, steps evicting or slashing validators may be committed outside switch
+    /// blocks for chain maintenance.  Setting this option makes sense only for private chains.
+    /// Defaults to `false`.
+    #[serde(default)]
+    pub(crate) allow_maintenance_steps: bool,
+    /// If set to true, finalized blocks whose proposer is neither the system nor a validator of
+    /// the block's era are rejected.  Defaults to `false`.
+    #[serde(default)]
+    pub(crate) validate_proposer: bool,
+    /// The maximum depth in the global state trie of the values written by the deploys of a block,
+    /// above which the block is rejected before its effects are written.  Unlimited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_trie_depth: Option<u32>,
 }
 
 impl CoreConfig {
@@ -209,6 +243,30 @@ impl CoreConfig {
         } else {
             FeeHandling::Accumulate
         };
+        let slash_equivocators = rng.gen();
+        let block_execution_gas_limit = if rng.gen() {
+            Some(rng.gen_range(1..1_000_000_000_000))
+        } else {
+            None
+        };
+        let max_execution_result_bytes = if rng.gen() {
+            Some(rng.gen_range(1..100_000_000))
+        } else {
+            None
+        };
+        let validator_floor = if rng.gen() {
+            Some(rng.gen_range(1..100))
+        } else {
+            None
+        };
+        let strict_block_execution = rng.gen();
+        let allow_maintenance_steps = rng.gen();
+        let validate_proposer = rng.gen();
+        let max_trie_depth = if rng.gen() {
+            Some(rng.gen_range(1..100))
+        } else {
+            None
+        };
 
         CoreConfig {
             era_duration,
@@ -237,6 +295,14 @@ impl CoreConfig {
             compute_rewards,
             refund_handling,
             fee_handling,
+            slash_equivocators,
+            block_execution_gas_limit,
+            max_execution_result_bytes,
+            validator_floor,
+            strict_block_execution,
+            allow_maintenance_steps,
+            validate_proposer,
+            max_trie_depth,
         }
     }
 }
@@ -273,6 +339,14 @@ impl ToBytes for CoreConfig {
         buffer.extend(self.administrators.to_bytes()?);
         buffer.extend(self.refund_handling.to_bytes()?);
         buffer.extend(self.fee_handling.to_bytes()?);
+        buffer.extend(self.slash_equivocators.to_bytes()?);
+        buffer.extend(self.block_execution_gas_limit.to_bytes()?);
+        buffer.extend(self.max_execution_result_bytes.to_bytes()?);
+        buffer.extend(self.validator_floor.to_bytes()?);
+        buffer.extend(self.strict_block_execution.to_bytes()?);
+        buffer.extend(self.allow_maintenance_steps.to_bytes()?);
+        buffer.extend(self.validate_proposer.to_bytes()?);
+        buffer.extend(self.max_trie_depth.to_bytes()?);
         Ok(buffer)
     }
 
@@ -305,6 +379,14 @@ impl ToBytes for CoreConfig {
             + self.administrators.serialized_length()
             + self.refund_handling.serialized_length()
             + self.fee_handling.serialized_length()
+            + self.slash_equivocators.serialized_length()
+            + self.block_execution_gas_limit.serialized_length()
+            + self.max_execution_result_bytes.serialized_length()
+            + self.validator_floor.serialized_length()
+            + self.strict_block_execution.serialized_length()
+            + self.allow_maintenance_steps.serialized_length()
+            + self.validate_proposer.serialized_length()
+            + self.max_trie_depth.serialized_length()
     }
 }
 
@@ -337,6 +419,14 @@ impl FromBytes for CoreConfig {
         let (administrative_accounts, remainder) = FromBytes::from_bytes(remainder)?;
         let (refund_handling, remainder) = FromBytes::from_bytes(remainder)?;
         let (fee_handling, remainder) = FromBytes::from_bytes(remainder)?;
+        let (slash_equivocators, remainder) = bool::from_bytes(remainder)?;
+        let (block_execution_gas_limit, remainder) = FromBytes::from_bytes(remainder)?;
+        let (max_execution_result_bytes, remainder) = FromBytes::from_bytes(remainder)?;
+        let (validator_floor, remainder) = FromBytes::from_bytes(remainder)?;
+        let (strict_block_execution, remainder) = bool::from_bytes(remainder)?;
+        let (allow_maintenance_steps, remainder) = bool::from_bytes(remainder)?;
+        let (validate_proposer, remainder) = bool::from_bytes(remainder)?;
+        let (max_trie_depth, remainder) = FromBytes::from_bytes(remainder)?;
         let config = CoreConfig {
             era_duration,
             minimum_era_height,
@@ -364,6 +454,14 @@ impl FromBytes for CoreConfig {
             administrators: administrative_accounts,
             refund_handling,
             fee_handling,
+            slash_equivocators,
+            block_execution_gas_limit,
+            max_execution_result_bytes,
+            validator_floor,
+            strict_block_execution,
+            allow_maintenance_steps,
+            validate_proposer,
+            max_trie_depth,
         };
         Ok((config, remainder))
     }
@@ -540,6 +638,29 @@ mod tests {
         assert_eq!(config, decoded);
     }
 
+    #[test]
+    fn should_parse_without_optional_flags() {
+        let mut rng = crate::new_rng();
+        let mut config = CoreConfig::random(&mut rng);
+        let mut encoded = toml::Value::try_from(&config).unwrap();
+        let table = encoded.as_table_mut().unwrap();
+        for key in &[
+            "slash_equivocators",
+            "strict_block_execution",
+            "allow_maintenance_steps",
+            "validate_proposer",
+        ] {
+            assert!(table.remove(*key).is_some());
+        }
+        let decoded: CoreConfig = encoded.try_into().unwrap();
+
+        config.slash_equivocators = false;
+        config.strict_block_execution = false;
+        config.allow_maintenance_steps = false;
+        config.validate_proposer = false;
+        assert_eq!(config, decoded);
+    }
+
     #[test]
     fn should_validate_for_finality_threshold() {
         let mut rng = crate::new_rng();
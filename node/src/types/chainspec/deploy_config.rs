@@ -50,6 +50,11 @@ impl DeployConfig {
             false
         }
     }
+
+    /// Returns the maximum number of deploys and transfers a block may contain in total.
+    pub(crate) fn max_deploys_per_block(&self) -> usize {
+        self.block_max_deploy_count as usize + self.block_max_transfer_count as usize
+    }
 }
 
 #[cfg(test)]